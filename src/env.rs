@@ -79,17 +79,29 @@ static ENV_LOCK: StaticMutex = MUTEX_INIT;
 
 /// An iterator over a snapshot of the environment variables of this process.
 ///
-/// This iterator is created through `std::env::vars()` and yields `(OsString,
-/// OsString)` pairs.
-pub struct Vars { inner: os_imp::Env }
+/// This iterator is created through `std::env::vars()` and yields `(String,
+/// String)` pairs.
+pub struct Vars { inner: VarsOs }
 
-/// Returns an iterator of (variable, value) pairs, for all the environment
-/// variables of the current process.
+/// An iterator over a snapshot of the environment variables of this process.
+///
+/// This iterator is created through `std::env::vars_os()` and yields
+/// `(OsString, OsString)` pairs.
+pub struct VarsOs { inner: os_imp::Env }
+
+/// Returns an iterator of (variable, value) pairs of strings, for all the
+/// environment variables of the current process.
 ///
 /// The returned iterator contains a snapshot of the process's environment
 /// variables at the time of this invocation, modifications to environment
 /// variables afterwards will not be reflected in the returned iterator.
 ///
+/// # Panics
+///
+/// While iterating, the returned iterator will panic if any key or value in
+/// the environment is not valid unicode. If this is not desired, consider
+/// using the `env::vars_os` function.
+///
 /// # Example
 ///
 /// ```rust
@@ -98,15 +110,48 @@ pub struct Vars { inner: os_imp::Env }
 /// // We will iterate through the references to the element returned by
 /// // env::vars();
 /// for (key, value) in env::vars() {
-///     println!("{:?}: {:?}", key, value);
+///     println!("{}: {}", key, value);
 /// }
 /// ```
 pub fn vars() -> Vars {
+    Vars { inner: vars_os() }
+}
+
+/// Returns an iterator of (variable, value) pairs of OS strings, for all the
+/// environment variables of the current process.
+///
+/// The returned iterator contains a snapshot of the process's environment
+/// variables at the time of this invocation, modifications to environment
+/// variables afterwards will not be reflected in the returned iterator.
+///
+/// Unlike `env::vars`, this iterator never panics on keys or values which are
+/// not valid unicode.
+///
+/// # Example
+///
+/// ```rust
+/// use std::env;
+///
+/// for (key, value) in env::vars_os() {
+///     println!("{:?}: {:?}", key, value);
+/// }
+/// ```
+pub fn vars_os() -> VarsOs {
     let _g = ENV_LOCK.lock();
-    Vars { inner: os_imp::env() }
+    VarsOs { inner: os_imp::env() }
 }
 
 impl Iterator for Vars {
+    type Item = (String, String);
+    fn next(&mut self) -> Option<(String, String)> {
+        self.inner.next().map(|(a, b)| {
+            (a.into_string().unwrap(), b.into_string().unwrap())
+        })
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) { self.inner.size_hint() }
+}
+
+impl Iterator for VarsOs {
     type Item = (OsString, OsString);
     fn next(&mut self) -> Option<(OsString, OsString)> { self.inner.next() }
     fn size_hint(&self) -> (usize, Option<usize>) { self.inner.size_hint() }
@@ -141,7 +186,7 @@ pub fn var<K: ?Sized>(key: &K) -> Option<OsString> where K: AsOsStr {
 ///
 /// let key = "KEY";
 /// env::set_var(key, "VALUE");
-/// match os::var(key) {
+/// match env::var(key) {
 ///     Some(ref val) => println!("{}: {:?}", key, val),
 ///     None => println!("{} is not defined in the environment.", key)
 /// }
@@ -197,7 +242,7 @@ impl<'a> Iterator for SplitPaths<'a> {
 
 /// Error type returned from `std::env::join_paths` when paths fail to be
 /// joined.
-#[derive(Show)]
+#[derive(Debug)]
 pub struct JoinPathsError {
     inner: os_imp::JoinPathsError
 }
@@ -219,7 +264,7 @@ pub struct JoinPathsError {
 /// let key = "PATH";
 /// let mut paths = env::var(key).map_or(Vec::new(), env::split_paths);
 /// paths.push(Path::new("/home/xyz/bin"));
-/// env::setenv(key, env::join_paths(paths.as_slice()).unwrap());
+/// env::set_var(key, &env::join_paths(paths.iter()).unwrap());
 /// ```
 pub fn join_paths<'a, I, T: ?Sized>(paths: I) -> Result<OsString, JoinPathsError>
     where I: Iterator<Item=&'a T>, T: AsOsStr
@@ -231,7 +276,7 @@ pub fn join_paths<'a, I, T: ?Sized>(paths: I) -> Result<OsString, JoinPathsError
 
 impl fmt::Display for JoinPathsError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        self.inner.fmt(f)
+        fmt::Display::fmt(&self.inner, f)
     }
 }
 
@@ -244,7 +289,8 @@ impl Error for JoinPathsError {
 /// # Unix
 ///
 /// Returns the value of the 'HOME' environment variable if it is set
-/// and not equal to the empty string.
+/// and not equal to the empty string. Otherwise, returns the home directory
+/// recorded in the password database for the current user, if any.
 ///
 /// # Windows
 ///
@@ -258,13 +304,13 @@ impl Error for JoinPathsError {
 /// ```rust
 /// use std::env;
 ///
-/// match env::homedir() {
+/// match env::home_dir() {
 ///     Some(ref p) => println!("{}", p.display()),
 ///     None => println!("Impossible to get your home dir!")
 /// }
 /// ```
 pub fn home_dir() -> Option<Path> {
-    os_imp::homedi()
+    os_imp::home_dir()
 }
 
 /// Returns the path to a temporary directory.
@@ -293,9 +339,8 @@ pub fn temp_dir() -> Path {
 }
 
 fn env_path(key: &str) -> Option<Path> {
-    var(key).and_then(|p| {
-        // TODO: Path should be constructible from OsString
-        let s = p.to_str().unwrap();
+    // TODO: Path should be constructible from OsString
+    var(key).and_then(|p| p.into_string().ok()).and_then(|s| {
         if s.is_empty() {None} else {Some(Path::new(s))}
     })
 }
@@ -340,11 +385,17 @@ pub fn get_exit_status() -> i32 {
     EXIT_STATUS.load(Ordering::SeqCst) as i32
 }
 
-/// An iterator over the arguments of a process, yielding an `OsString` value
+/// An iterator over the arguments of a process, yielding a `String` value
 /// for each argument.
 ///
 /// This structure is created through the `std::env::args` method.
-pub struct Args { inner: os_imp::Args }
+pub struct Args { inner: ArgsOs }
+
+/// An iterator over the arguments of a process, yielding an `OsString` value
+/// for each argument.
+///
+/// This structure is created through the `std::env::args_os` method.
+pub struct ArgsOs { inner: os_imp::Args }
 
 /// Returns the arguments which this program was started with (normally passed
 /// via the command line).
@@ -353,6 +404,12 @@ pub struct Args { inner: os_imp::Args }
 /// set to arbitrary text, and it may not even exist, so this property should
 /// not be relied upon for security purposes.
 ///
+/// # Panics
+///
+/// The returned iterator will panic during iteration if any argument to the
+/// process is not valid unicode. If this is not desired it is recommended to
+/// use the `args_os` function instead.
+///
 /// # Example
 ///
 /// ```rust
@@ -360,14 +417,42 @@ pub struct Args { inner: os_imp::Args }
 ///
 /// // Prints each argument on a separate line
 /// for argument in env::args() {
-///     println!("{:?}", argument);
+///     println!("{}", argument);
 /// }
 /// ```
 pub fn args() -> Args {
-    Args { inner: os_imp::args() }
+    Args { inner: args_os() }
+}
+
+/// Returns the arguments which this program was started with (normally passed
+/// via the command line) as OS strings.
+///
+/// Unlike `env::args`, the returned iterator never panics on arguments which
+/// are not valid unicode.
+///
+/// # Example
+///
+/// ```rust
+/// use std::env;
+///
+/// // Prints each argument on a separate line
+/// for argument in env::args_os() {
+///     println!("{:?}", argument);
+/// }
+/// ```
+pub fn args_os() -> ArgsOs {
+    ArgsOs { inner: os_imp::args() }
 }
 
 impl Iterator for Args {
+    type Item = String;
+    fn next(&mut self) -> Option<String> {
+        self.inner.next().map(|s| s.into_string().unwrap())
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) { self.inner.size_hint() }
+}
+
+impl Iterator for ArgsOs {
     type Item = OsString;
     fn next(&mut self) -> Option<OsString> { self.inner.next() }
     fn size_hint(&self) -> (usize, Option<usize>) { self.inner.size_hint() }
@@ -0,0 +1,366 @@
+// Copyright 2015 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Recursive copies of directory trees.
+
+use prelude::v1::*;
+
+use fs::{self, DirEntry, File, FileAttr, FileType, OpenOptions, WalkDir};
+use fs::private;
+use io::{self, Error, ErrorKind};
+use path::{Path, GenericPath};
+use sys::fs as fs_imp;
+
+/// What `copy_dir` does when something already exists at a destination.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ExistingPolicy {
+    /// Replace existing files. Existing directories are merged with the
+    /// source directories, and contents not in the source are kept.
+    Overwrite,
+    /// Leave existing files alone. Existing directories are still merged.
+    Skip,
+    /// Fail as soon as anything exists at a destination path.
+    Error,
+}
+
+/// How `copy_dir` treats symlinks in the source tree.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SymlinkPolicy {
+    /// Create a symlink with the same target at the destination.
+    Copy,
+    /// Copy whatever the symlink points to, as if it were in its place.
+    /// A symlink which points at one of its own parent directories is an
+    /// error.
+    Follow,
+    /// Leave symlinks out of the copy.
+    Skip,
+}
+
+/// Options which configure how `copy_dir` copies a tree.
+#[derive(Clone, Debug)]
+pub struct CopyOptions {
+    existing: ExistingPolicy,
+    symlinks: SymlinkPolicy,
+    preserve_perm: bool,
+    preserve_times: bool,
+}
+
+/// The progress of a `copy_dir_with_progress` call, reported after every
+/// entry which is copied.
+pub struct CopyProgress<'a> {
+    from: &'a Path,
+    to: &'a Path,
+    bytes: u64,
+    total_bytes: u64,
+    entries: u64,
+}
+
+impl CopyOptions {
+    /// Creates the default options: fail on existing destinations, copy
+    /// symlinks as symlinks, and preserve permissions but not timestamps.
+    pub fn new() -> CopyOptions {
+        CopyOptions {
+            existing: ExistingPolicy::Error,
+            symlinks: SymlinkPolicy::Copy,
+            preserve_perm: true,
+            preserve_times: false,
+        }
+    }
+
+    /// Sets what happens when a destination path already exists.
+    pub fn existing(&mut self, policy: ExistingPolicy) -> &mut CopyOptions {
+        self.existing = policy; self
+    }
+
+    /// Sets how symlinks in the source tree are treated.
+    pub fn symlinks(&mut self, policy: SymlinkPolicy) -> &mut CopyOptions {
+        self.symlinks = policy; self
+    }
+
+    /// Sets whether copies get the permissions of their source.
+    pub fn preserve_perm(&mut self, preserve: bool) -> &mut CopyOptions {
+        self.preserve_perm = preserve; self
+    }
+
+    /// Sets whether copies get the access and modification times of their
+    /// source, as precisely as `set_file_times` allows. The times of copied
+    /// symlinks are never preserved.
+    pub fn preserve_times(&mut self, preserve: bool) -> &mut CopyOptions {
+        self.preserve_times = preserve; self
+    }
+}
+
+impl<'a> CopyProgress<'a> {
+    /// Returns the source path of the entry just copied.
+    pub fn from(&self) -> &Path { self.from }
+
+    /// Returns the destination path of the entry just copied.
+    pub fn to(&self) -> &Path { self.to }
+
+    /// Returns the number of bytes copied for this entry, which is zero for
+    /// anything but regular files.
+    pub fn bytes(&self) -> u64 { self.bytes }
+
+    /// Returns the number of bytes copied so far, including this entry.
+    pub fn total_bytes(&self) -> u64 { self.total_bytes }
+
+    /// Returns the number of entries copied so far, including this one.
+    pub fn entries(&self) -> u64 { self.entries }
+}
+
+/// Recursively copies the directory `from` and everything in it to `to`,
+/// returning the total number of bytes copied.
+///
+/// `to` is created if it doesn't exist, and its parent must exist. Regular
+/// files, directories and symlinks are copied; other kinds of files cause
+/// an error. Directories get their permissions and times only after their
+/// contents have been copied, so read-only directories can be copied too.
+///
+/// # Errors
+///
+/// Copying stops at the first error, leaving whatever was copied until then
+/// in place. Errors occur if `from` is not a directory, if `to` is inside
+/// `from`, if a destination exists and the policy is `ExistingPolicy::Error`,
+/// or if followed symlinks lead in a loop, as well as on any I/O error.
+pub fn copy_dir(from: &Path, to: &Path, opts: &CopyOptions) -> io::Result<u64> {
+    copy_dir_with_progress(from, to, opts, |_| ())
+}
+
+/// Recursively copies the directory `from` to `to`, as `copy_dir` does,
+/// calling `progress` after each entry has been copied. Directories are
+/// reported once everything in them has been copied.
+pub fn copy_dir_with_progress<F>(from: &Path, to: &Path, opts: &CopyOptions,
+                                 mut progress: F) -> io::Result<u64>
+    where F: FnMut(&CopyProgress)
+{
+    let root = try!(fs::file_attr(from));
+    if !root.is_dir() {
+        return Err(Error::new(ErrorKind::MismatchedFileTypeForOperation,
+                              "the source path is not an existing directory",
+                              None))
+    }
+    // Compare the real paths, so that neither relative paths nor symlinks
+    // hide that `to` is inside `from`.
+    if try!(fs_imp::canonicalize(from)).is_ancestor_of(&try!(real_path(to))) {
+        return Err(Error::new(ErrorKind::InvalidInput,
+                              "cannot copy a directory into itself", None))
+    }
+
+    if !try!(make_dir(to, opts)) {
+        return Ok(0)
+    }
+    let mut copier = Copier {
+        from: from,
+        to: to,
+        opts: opts,
+        progress: &mut progress,
+        total_bytes: 0,
+        entries: 0,
+        made: Vec::new(),
+        skipped: None,
+    };
+    // Directories come after their contents, so that they only become
+    // read-only once nothing more is copied into them. Loops through
+    // followed symlinks are found by the walk.
+    let follow = opts.symlinks == SymlinkPolicy::Follow;
+    let mut walk = WalkDir::new(from).follow_links(follow)
+                                     .contents_first(true);
+    while let Some(entry) = walk.next() {
+        let entry = try!(entry);
+        if !try!(copier.copy_entry(&entry)) {
+            walk.skip_current_dir();
+        }
+    }
+    try!(set_attrs(to, &root, opts));
+    Ok(copier.total_bytes)
+}
+
+// The state of a copy in progress.
+struct Copier<'a, F: 'a> {
+    from: &'a Path,
+    to: &'a Path,
+    opts: &'a CopyOptions,
+    progress: &'a mut F,
+    total_bytes: u64,
+    entries: u64,
+    // The source directories, outermost first, whose destinations have been
+    // made along the path of the last entry.
+    made: Vec<Path>,
+    // A source directory whose destination is kept as it is, so that
+    // nothing in it is copied.
+    skipped: Option<Path>,
+}
+
+impl<'a, F: FnMut(&CopyProgress)> Copier<'a, F> {
+    // Copies one entry of the walk, returning false if it lies in a
+    // directory which is not copied.
+    fn copy_entry(&mut self, entry: &DirEntry) -> io::Result<bool> {
+        let src = entry.path();
+        if let Some(ref skipped) = self.skipped {
+            if skipped.is_ancestor_of(&src) {
+                return Ok(false)
+            }
+        }
+        let file_type = entry.file_type.unwrap();
+        if file_type == FileType::Symlink &&
+           self.opts.symlinks == SymlinkPolicy::Skip {
+            return Ok(true)
+        }
+
+        // Directories only come after their contents, so the destination
+        // directories on the way to the entry, and for an empty directory
+        // the entry itself, are made when first needed.
+        let mut dirs = Vec::new();
+        if file_type == FileType::Dir {
+            dirs.push(src.clone());
+        }
+        let mut dir = src.dir_path();
+        for _ in 1..entry.depth() {
+            dirs.push(dir.clone());
+            dir = dir.dir_path();
+        }
+        dirs.reverse();
+        let common = self.made.iter().zip(dirs.iter())
+                                     .take_while(|&(a, b)| a == b).count();
+        self.made.truncate(common);
+        for dir in dirs.into_iter().skip(common) {
+            if !try!(make_dir(&self.dst(&dir), self.opts)) {
+                self.skipped = Some(dir);
+                return Ok(false)
+            }
+            self.made.push(dir);
+        }
+
+        let dst = self.dst(&src);
+        match file_type {
+            FileType::Dir => {
+                try!(set_attrs(&dst, &try!(fs::file_attr(&src)), self.opts));
+                self.report(&src, &dst, 0);
+            }
+            FileType::File => {
+                if try!(make_room(&dst, self.opts)) {
+                    let attr = try!(fs::file_attr(&src));
+                    let bytes = try!(copy_file(&src, &dst, self.opts));
+                    try!(set_attrs(&dst, &attr, self.opts));
+                    self.report(&src, &dst, bytes);
+                }
+            }
+            FileType::Symlink => {
+                if try!(make_room(&dst, self.opts)) {
+                    try!(fs::sym_link(&try!(fs::read_link(&src)), &dst));
+                    self.report(&src, &dst, 0);
+                }
+            }
+            _ => {
+                return Err(Error::new(ErrorKind::MismatchedFileTypeForOperation,
+                                      "cannot copy special files",
+                                      Some(src.display().to_string())))
+            }
+        }
+        Ok(true)
+    }
+
+    // Returns where the source path `src` is copied to.
+    fn dst(&self, src: &Path) -> Path {
+        self.to.join(src.path_relative_from(self.from).unwrap())
+    }
+
+    fn report(&mut self, src: &Path, dst: &Path, bytes: u64) {
+        self.total_bytes += bytes;
+        self.entries += 1;
+        (*self.progress)(&CopyProgress {
+            from: src, to: dst, bytes: bytes,
+            total_bytes: self.total_bytes, entries: self.entries,
+        });
+    }
+}
+
+// Returns the real path of `path`, which may not exist yet, in which case
+// its parent must.
+fn real_path(path: &Path) -> io::Result<Path> {
+    match fs_imp::canonicalize(path) {
+        Ok(real) => Ok(real),
+        Err(..) => {
+            let parent = try!(fs_imp::canonicalize(&path.dir_path()));
+            Ok(match path.filename() {
+                Some(name) => parent.join(name),
+                None => parent,
+            })
+        }
+    }
+}
+
+fn already_exists(dst: &Path) -> Error {
+    Error::new(ErrorKind::PathAlreadyExists, "destination path already exists",
+               Some(dst.display().to_string()))
+}
+
+// Makes sure there's a directory at `dst`, returning whether the source
+// directory's contents should be copied into it.
+fn make_dir(dst: &Path, opts: &CopyOptions) -> io::Result<bool> {
+    let attr = match fs::symlink_attr(dst) {
+        Ok(attr) => attr,
+        Err(..) => return fs::make_dir(dst).map(|()| true),
+    };
+    match opts.existing {
+        ExistingPolicy::Error => Err(already_exists(dst)),
+        _ if attr.is_dir() => Ok(true),
+        ExistingPolicy::Skip => Ok(false),
+        ExistingPolicy::Overwrite => {
+            try!(fs::remove_file(dst));
+            fs::make_dir(dst).map(|()| true)
+        }
+    }
+}
+
+// Clears the way for a file or symlink at `dst`, returning whether it should
+// be copied at all.
+fn make_room(dst: &Path, opts: &CopyOptions) -> io::Result<bool> {
+    let attr = match fs::symlink_attr(dst) {
+        Ok(attr) => attr,
+        Err(..) => return Ok(true),
+    };
+    match opts.existing {
+        ExistingPolicy::Error => Err(already_exists(dst)),
+        ExistingPolicy::Skip => Ok(false),
+        ExistingPolicy::Overwrite if attr.is_dir() => {
+            Err(Error::new(ErrorKind::MismatchedFileTypeForOperation,
+                           "cannot overwrite a directory with a file",
+                           Some(dst.display().to_string())))
+        }
+        // Remove rather than truncate, so that a symlink at `dst` is
+        // replaced instead of written through.
+        ExistingPolicy::Overwrite => fs::remove_file(dst).map(|()| true),
+    }
+}
+
+fn copy_file(src: &Path, dst: &Path, opts: &CopyOptions) -> io::Result<u64> {
+    let mut reader = try!(File::open(src));
+    let mut open = OpenOptions::new();
+    open.write(true).create(true).truncate(true);
+    // A copy which gets the permissions of its source only gets them once
+    // it is written, so it is kept private until then.
+    if opts.preserve_perm {
+        private(&mut open);
+    }
+    let mut writer = try!(File::open_opts(dst, &open));
+    io::copy(&mut reader, &mut writer)
+}
+
+fn set_attrs(dst: &Path, attr: &FileAttr,
+             opts: &CopyOptions) -> io::Result<()> {
+    if opts.preserve_perm {
+        try!(fs::set_perm(dst, attr.perm()));
+    }
+    if opts.preserve_times {
+        try!(fs::set_file_times(dst, attr.accessed(), attr.modified()));
+    }
+    Ok(())
+}
@@ -0,0 +1,512 @@
+// Copyright 2015 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Matching paths against shell-style wildcard patterns.
+//!
+//! Patterns are made up of path components separated by `/` (or also `\` on
+//! Windows), which may contain:
+//!
+//! * `?`, matching any single character.
+//! * `*`, matching any sequence of characters, including none.
+//! * `[...]`, matching any character listed in the brackets, where `a-z`
+//!   stands for a range of characters. `[!...]` matches any character not
+//!   listed. Special characters can be matched literally by enclosing them
+//!   in brackets, as in `[*]`.
+//! * `{a,b}`, matching either of the comma separated alternatives, which may
+//!   span several components and contain further patterns.
+//!
+//! A component which is just `**` matches any number of components,
+//! including none, so `src/**/*.rs` matches every `.rs` file below `src`.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use io2::fs::glob;
+//!
+//! for entry in glob::glob("src/**/*.rs").unwrap() {
+//!     println!("{}", entry.unwrap().path().display());
+//! }
+//! ```
+
+use prelude::v1::*;
+
+use cmp::Ordering;
+use fs::{self, DirEntry, FileType, WalkDir};
+use io::{self, Error, ErrorKind};
+use path::{Path, GenericPath};
+use usize;
+
+macro_rules! try_opt {
+    ($e:expr) => (match $e { Some(e) => e, None => return None })
+}
+
+/// A compiled wildcard pattern.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Pattern {
+    original: String,
+    alternatives: Vec<Alternative>,
+}
+
+/// Options which configure how patterns are matched.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct MatchOptions {
+    case_sensitive: bool,
+    hidden: bool,
+}
+
+/// An iterator over the filesystem entries which match a pattern, returned
+/// by `glob` and `glob_with`.
+pub struct Glob {
+    walk: Option<WalkDir<'static>>,
+    root: Path,
+    // The alternatives of the pattern, without the components in `root`.
+    alternatives: Vec<Vec<Segment>>,
+    max_depth: usize,
+    opts: MatchOptions,
+}
+
+// One of the patterns that a pattern with braces expands to.
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Alternative {
+    absolute: bool,
+    segments: Vec<Segment>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum Segment {
+    // `**`, matching any number of components
+    AnyComponents,
+    Component(Vec<Token>),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum Token {
+    Char(char),
+    AnyChar,
+    AnySequence,
+    Class(bool, Vec<(char, char)>),
+}
+
+/// Returns an iterator over the entries in the filesystem which match
+/// `pattern`, with the default `MatchOptions`.
+pub fn glob(pattern: &str) -> io::Result<Glob> {
+    glob_with(pattern, &MatchOptions::new())
+}
+
+/// Returns an iterator over the entries in the filesystem which match
+/// `pattern`, in the order of their paths.
+///
+/// The leading components of the pattern without any wildcards name the
+/// directory which is searched, and the search only descends into
+/// directories which can contain matches. Symlinks to directories are
+/// followed.
+///
+/// # Error
+///
+/// This function returns an error if `pattern` is invalid. A directory
+/// which doesn't exist simply has no matches, while other errors met while
+/// searching are yielded by the iterator.
+pub fn glob_with(pattern: &str, opts: &MatchOptions) -> io::Result<Glob> {
+    let pattern = try!(Pattern::new(pattern));
+    let absolute = pattern.alternatives[0].absolute;
+    if pattern.alternatives.iter().any(|a| a.absolute != absolute) {
+        return Err(invalid("pattern mixes absolute and relative paths",
+                           &pattern.original))
+    }
+
+    // Literal components are only taken as they are when matching case
+    // sensitively, otherwise their directories are searched for them too.
+    let mut root = Path::new(if absolute { "/" } else { "." });
+    let mut skip = 0;
+    if opts.case_sensitive {
+        loop {
+            let lit = match pattern.alternatives[0].literal(skip) {
+                Some(lit) => lit,
+                None => break,
+            };
+            let shared = pattern.alternatives.iter().all(|a| {
+                a.literal(skip).as_ref() == Some(&lit)
+            });
+            if !shared { break }
+            root.push(lit);
+            skip += 1;
+        }
+    }
+
+    let alternatives: Vec<Vec<Segment>> = pattern.alternatives.iter().map(|a| {
+        a.segments[skip..].to_vec()
+    }).collect();
+    let recursive = alternatives.iter().any(|segments| {
+        segments.iter().any(|s| *s == Segment::AnyComponents)
+    });
+    let max_depth = if recursive {
+        usize::MAX
+    } else {
+        alternatives.iter().map(|s| s.len()).max().unwrap_or(0)
+    };
+    let is_dir = fs::file_attr(&root).map(|a| a.is_dir()).unwrap_or(false);
+    let walk = if is_dir {
+        Some(WalkDir::new(&root).follow_links(true).max_depth(max_depth)
+                                .sort_by(by_path))
+    } else {
+        None
+    };
+    Ok(Glob {
+        walk: walk,
+        root: root,
+        alternatives: alternatives,
+        max_depth: max_depth,
+        opts: *opts,
+    })
+}
+
+fn by_path(a: &DirEntry, b: &DirEntry) -> Ordering {
+    a.path.as_vec().cmp(b.path.as_vec())
+}
+
+fn invalid(desc: &'static str, pattern: &str) -> Error {
+    Error::new(ErrorKind::InvalidInput, desc, Some(pattern.to_string()))
+}
+
+fn is_sep(c: char) -> bool {
+    c == '/' || (cfg!(windows) && c == '\\')
+}
+
+fn components(path: &str) -> Vec<&str> {
+    path.split(is_sep).filter(|c| !c.is_empty()).collect()
+}
+
+impl Pattern {
+    /// Compiles `pattern`.
+    ///
+    /// # Error
+    ///
+    /// Returns an error of kind `InvalidInput` if a `[` or `{` is not
+    /// closed, if a `[...]` class contains a path separator, or if `**`
+    /// appears in a component with anything else.
+    pub fn new(pattern: &str) -> io::Result<Pattern> {
+        let chars: Vec<char> = pattern.chars().collect();
+        let expanded = match expand_braces(&chars) {
+            Some(expanded) => expanded,
+            None => return Err(invalid("unclosed `[` or `{` in pattern",
+                                       pattern)),
+        };
+        let mut alternatives = Vec::new();
+        for alt in expanded.iter() {
+            match Alternative::parse(alt) {
+                Ok(alt) => alternatives.push(alt),
+                Err(desc) => return Err(invalid(desc, pattern)),
+            }
+        }
+        Ok(Pattern {
+            original: pattern.to_string(),
+            alternatives: alternatives,
+        })
+    }
+
+    /// Returns the string this pattern was compiled from.
+    pub fn as_str(&self) -> &str { &self.original }
+
+    /// Returns whether the path `s` matches this pattern, with the default
+    /// `MatchOptions`.
+    pub fn matches(&self, s: &str) -> bool {
+        self.matches_with(s, &MatchOptions::new())
+    }
+
+    /// Returns whether the path `s` matches this pattern.
+    pub fn matches_with(&self, s: &str, opts: &MatchOptions) -> bool {
+        let absolute = s.chars().next().map_or(false, is_sep);
+        let comps = components(s);
+        self.alternatives.iter().any(|alt| {
+            alt.absolute == absolute &&
+                match_segments(&alt.segments, &comps, opts)
+        })
+    }
+
+    /// Returns whether `path` matches this pattern, with the default
+    /// `MatchOptions`. Paths which aren't valid unicode never match.
+    pub fn matches_path(&self, path: &Path) -> bool {
+        self.matches_path_with(path, &MatchOptions::new())
+    }
+
+    /// Returns whether `path` matches this pattern. Paths which aren't
+    /// valid unicode never match.
+    pub fn matches_path_with(&self, path: &Path, opts: &MatchOptions) -> bool {
+        path.as_str().map_or(false, |s| self.matches_with(s, opts))
+    }
+}
+
+impl MatchOptions {
+    /// Creates the default options: matching is case sensitive, and names
+    /// starting with a `.` are only matched by a literal `.`.
+    pub fn new() -> MatchOptions {
+        MatchOptions { case_sensitive: true, hidden: false }
+    }
+
+    /// Sets whether letters only match letters of the same case.
+    pub fn case_sensitive(&mut self, sensitive: bool) -> &mut MatchOptions {
+        self.case_sensitive = sensitive; self
+    }
+
+    /// Sets whether wildcards and `**` match names starting with a `.`,
+    /// which are hidden files on unix.
+    pub fn hidden(&mut self, hidden: bool) -> &mut MatchOptions {
+        self.hidden = hidden; self
+    }
+}
+
+impl Iterator for Glob {
+    type Item = io::Result<DirEntry>;
+
+    fn next(&mut self) -> Option<io::Result<DirEntry>> {
+        loop {
+            let entry = match self.walk.as_mut().and_then(|w| w.next()) {
+                Some(Ok(entry)) => entry,
+                Some(Err(e)) => return Some(Err(e)),
+                None => return None,
+            };
+            let rel = match entry.path.path_relative_from(&self.root) {
+                Some(rel) => rel,
+                None => continue,
+            };
+            let comps = match rel.as_str() {
+                Some(s) => components(s),
+                None => continue,
+            };
+
+            // Prune directories the walk would descend into when nothing in
+            // them can match.
+            let opts = &self.opts;
+            let alts = &self.alternatives;
+            if entry.depth < self.max_depth &&
+               entry.file_type == Some(FileType::Dir) &&
+               !alts.iter().any(|s| could_contain(s, &comps, opts)) {
+                self.walk.as_mut().unwrap().skip_current_dir();
+            }
+            if alts.iter().any(|s| match_segments(s, &comps, opts)) {
+                return Some(Ok(entry))
+            }
+        }
+    }
+}
+
+impl Alternative {
+    fn parse(pattern: &[char]) -> Result<Alternative, &'static str> {
+        let absolute = pattern.first().map_or(false, |&c| is_sep(c));
+        let mut segments = Vec::new();
+        for comp in pattern.split(|&c| is_sep(c)).filter(|c| !c.is_empty()) {
+            if comp.len() == 2 && comp[0] == '*' && comp[1] == '*' {
+                segments.push(Segment::AnyComponents);
+            } else {
+                let tokens = try!(parse_component(comp));
+                segments.push(Segment::Component(tokens));
+            }
+        }
+        Ok(Alternative { absolute: absolute, segments: segments })
+    }
+
+    // Returns the `n`th component if it has no wildcards and isn't the last.
+    fn literal(&self, n: usize) -> Option<String> {
+        if n + 1 >= self.segments.len() {
+            return None
+        }
+        match self.segments[n] {
+            Segment::Component(ref tokens) => {
+                let mut s = String::new();
+                for token in tokens.iter() {
+                    match *token {
+                        Token::Char(c) => s.push(c),
+                        _ => return None,
+                    }
+                }
+                Some(s)
+            }
+            Segment::AnyComponents => None,
+        }
+    }
+}
+
+// Returns the index just past the `]` closing the class which starts at
+// `pattern[start]`. A `]` right after the `[` or `[!` is part of the class.
+fn class_end(pattern: &[char], start: usize) -> Option<usize> {
+    let mut i = start + 1;
+    if pattern.get(i) == Some(&'!') { i += 1 }
+    if pattern.get(i) == Some(&']') { i += 1 }
+    pattern[i..].iter().position(|&c| c == ']').map(|n| i + n + 1)
+}
+
+// Expands the first `{...}` in `pattern`, recursively, returning `None` if a
+// `[` or `{` isn't closed.
+fn expand_braces(pattern: &[char]) -> Option<Vec<Vec<char>>> {
+    let mut start = 0;
+    loop {
+        match pattern.get(start) {
+            None => return Some(vec![pattern.to_vec()]),
+            Some(&'[') => start = try_opt!(class_end(pattern, start)),
+            Some(&'{') => break,
+            Some(_) => start += 1,
+        }
+    }
+
+    let mut splits = vec![start];
+    let mut depth = 0;
+    let mut i = start + 1;
+    loop {
+        match pattern.get(i) {
+            None => return None,
+            Some(&'[') => { i = try_opt!(class_end(pattern, i)); continue }
+            Some(&'{') => depth += 1,
+            Some(&'}') if depth == 0 => break,
+            Some(&'}') => depth -= 1,
+            Some(&',') if depth == 0 => splits.push(i),
+            Some(_) => {}
+        }
+        i += 1;
+    }
+    splits.push(i);
+
+    let mut ret = Vec::new();
+    for pair in splits.windows(2) {
+        let mut alt = pattern[..start].to_vec();
+        alt.push_all(&pattern[pair[0] + 1..pair[1]]);
+        alt.push_all(&pattern[i + 1..]);
+        ret.extend(try_opt!(expand_braces(&alt)).into_iter());
+    }
+    Some(ret)
+}
+
+// Parses a single component, which may not contain `**` and must close all
+// of its classes.
+fn parse_component(comp: &[char]) -> Result<Vec<Token>, &'static str> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < comp.len() {
+        match comp[i] {
+            '*' if comp.get(i + 1) == Some(&'*') => {
+                return Err("`**` must be a whole component")
+            }
+            '*' => tokens.push(Token::AnySequence),
+            '?' => tokens.push(Token::AnyChar),
+            '[' => {
+                // `expand_braces` only checked that classes are closed
+                // somewhere, which may be past a separator
+                let end = match class_end(comp, i) {
+                    Some(end) => end,
+                    None => {
+                        return Err("character class spans a path separator")
+                    }
+                };
+                let mut body = &comp[i + 1..end - 1];
+                let negated = body.first() == Some(&'!');
+                if negated { body = &body[1..] }
+                let mut ranges = Vec::new();
+                let mut j = 0;
+                while j < body.len() {
+                    if j + 2 < body.len() && body[j + 1] == '-' {
+                        ranges.push((body[j], body[j + 2]));
+                        j += 3;
+                    } else {
+                        ranges.push((body[j], body[j]));
+                        j += 1;
+                    }
+                }
+                tokens.push(Token::Class(negated, ranges));
+                i = end;
+                continue
+            }
+            c => tokens.push(Token::Char(c)),
+        }
+        i += 1;
+    }
+    Ok(tokens)
+}
+
+fn visible(name: &str, opts: &MatchOptions) -> bool {
+    opts.hidden || !name.starts_with(".")
+}
+
+fn match_segments(segments: &[Segment], comps: &[&str],
+                  opts: &MatchOptions) -> bool {
+    match segments.first() {
+        None => comps.is_empty(),
+        Some(&Segment::AnyComponents) => {
+            match_segments(&segments[1..], comps, opts) ||
+                (!comps.is_empty() && visible(comps[0], opts) &&
+                 match_segments(segments, &comps[1..], opts))
+        }
+        Some(&Segment::Component(ref tokens)) => {
+            !comps.is_empty() && match_component(tokens, comps[0], opts) &&
+                match_segments(&segments[1..], &comps[1..], opts)
+        }
+    }
+}
+
+// Returns whether anything below the directory `comps` could match.
+fn could_contain(segments: &[Segment], comps: &[&str],
+                 opts: &MatchOptions) -> bool {
+    if comps.is_empty() {
+        return !segments.is_empty()
+    }
+    match segments.first() {
+        None => false,
+        Some(&Segment::AnyComponents) => {
+            could_contain(&segments[1..], comps, opts) ||
+                (visible(comps[0], opts) &&
+                 could_contain(segments, &comps[1..], opts))
+        }
+        Some(&Segment::Component(ref tokens)) => {
+            match_component(tokens, comps[0], opts) &&
+                could_contain(&segments[1..], &comps[1..], opts)
+        }
+    }
+}
+
+fn match_component(tokens: &[Token], name: &str, opts: &MatchOptions) -> bool {
+    // A leading `.` can only be matched by a literal `.` unless hidden
+    // names are allowed.
+    if !visible(name, opts) && tokens.first() != Some(&Token::Char('.')) {
+        return false
+    }
+    let name: Vec<char> = name.chars().collect();
+    match_tokens(tokens, &name, opts)
+}
+
+fn match_tokens(tokens: &[Token], name: &[char], opts: &MatchOptions) -> bool {
+    let rest = if tokens.is_empty() { tokens } else { &tokens[1..] };
+    match tokens.first() {
+        None => name.is_empty(),
+        Some(&Token::AnySequence) => {
+            (0..name.len() + 1).any(|i| match_tokens(rest, &name[i..], opts))
+        }
+        Some(token) => {
+            let matched = match (token, name.first()) {
+                (_, None) => false,
+                (&Token::AnyChar, Some(_)) => true,
+                (&Token::Char(c), Some(&n)) => chars_eq(c, n, opts),
+                (&Token::Class(negated, ref ranges), Some(&n)) => {
+                    in_class(ranges, n, opts) != negated
+                }
+                (&Token::AnySequence, Some(_)) => unreachable!(),
+            };
+            matched && match_tokens(rest, &name[1..], opts)
+        }
+    }
+}
+
+fn chars_eq(a: char, b: char, opts: &MatchOptions) -> bool {
+    a == b || (!opts.case_sensitive && a.to_lowercase() == b.to_lowercase())
+}
+
+fn in_class(ranges: &[(char, char)], c: char, opts: &MatchOptions) -> bool {
+    let in_range = |c: char| ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi);
+    in_range(c) ||
+        (!opts.case_sensitive &&
+         (in_range(c.to_lowercase()) || in_range(c.to_uppercase())))
+}
@@ -0,0 +1,1144 @@
+// Copyright 2013-2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use core::prelude::*;
+
+use io::{self, Error, ErrorKind, Read, Write, WriteExt, Seek, SeekPos};
+use path::{Path, GenericPath};
+use sync::atomic::{AtomicUsize, ATOMIC_USIZE_INIT, Ordering};
+#[cfg(unix)] use sys::fd::FileDesc;
+use sys::fs as fs_imp;
+use sys::os as os_imp;
+use sys_common::{AsInner, AsInnerMut, FromInner, IntoInner};
+
+pub use self::copy_dir::{copy_dir, copy_dir_with_progress};
+pub use self::copy_dir::{CopyOptions, CopyProgress};
+pub use self::copy_dir::{ExistingPolicy, SymlinkPolicy};
+pub use self::tempdir::{TempDir, NamedTempFile, PersistError};
+pub use self::tempdir::{tempfile, tempfile_in};
+pub use self::walk::{WalkDir, walk_dir};
+
+pub mod glob;
+
+mod copy_dir;
+mod tempdir;
+mod walk;
+
+/// Unconstrained file access type that exposes read and write operations
+///
+/// Can be constructed via `File::open()`, `File::create()`, and
+/// `File::open_opts()`.
+///
+/// # Error
+///
+/// This type will return errors as an `io::Result<T>` if operations are
+/// attempted against it for which its underlying file descriptor was not
+/// configured at creation time, via the `FileAccess` parameter to
+/// `File::open_mode()`.
+pub struct File {
+    inner: fs_imp::File,
+    path: Path,
+}
+
+pub struct FileAttr(fs_imp::FileAttr);
+pub struct ReadDir(fs_imp::ReadDir);
+#[derive(Clone)]
+pub struct DirEntry {
+    path: Path,
+    depth: usize,
+    // The type of the entry, if a walk has already looked it up.
+    file_type: Option<FileType>,
+}
+#[derive(Clone)]
+pub struct OpenOptions(fs_imp::OpenOptions);
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct FilePermission(fs_imp::FilePermission);
+
+/// A file which atomically replaces the file at its target path once all of
+/// its contents have been written.
+///
+/// The contents are written to a uniquely named temporary file next to the
+/// target, which takes the target's place only when `commit` is called, so
+/// readers see either the old contents or the new ones but never a mix of
+/// the two, even if the process dies part of the way through. The temporary
+/// file is removed if the `AtomicFile` is dropped without being committed.
+///
+/// # Example
+///
+/// ```no_run
+/// use io2::fs::AtomicFile;
+/// use io2::io::prelude::*;
+///
+/// let mut file = AtomicFile::create(&Path::new("app.conf")).unwrap();
+/// file.preserve_perm(true);
+/// file.write_all(b"verbose = true\n").unwrap();
+/// file.commit().unwrap();
+/// ```
+pub struct AtomicFile {
+    file: Option<File>,
+    temp: Path,
+    target: Path,
+    preserve_perm: bool,
+    committed: bool,
+}
+
+/// The type of an entry in the filesystem.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum FileType {
+    /// A regular file.
+    File,
+    /// A directory.
+    Dir,
+    /// A symbolic link.
+    Symlink,
+    /// A named pipe.
+    Fifo,
+    /// A unix domain socket.
+    Socket,
+    /// A block device.
+    BlockDevice,
+    /// A character device.
+    CharDevice,
+    /// Anything else the platform reports.
+    Unknown,
+}
+
+/// A timestamp of a filesystem entry, measured relative to the unix epoch.
+///
+/// Timestamps before the epoch have a negative number of seconds, but the
+/// nanoseconds are always positive and less than one second.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct FileTime {
+    secs: i64,
+    nanos: u32,
+}
+
+/// The kind of advisory lock to take on a range of a file.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum LockKind {
+    /// Any number of shared locks may be held at once, as long as nobody
+    /// holds an exclusive lock.
+    Shared,
+    /// An exclusive lock may only be held while nobody else holds any lock.
+    Exclusive,
+}
+
+impl File {
+    /// Open a file at `path` in the mode specified by the `mode` and `access`
+    /// arguments
+    ///
+    /// # Example
+    ///
+    /// ```rust,should_fail
+    /// use std::old_io::{File, Open, ReadWrite};
+    ///
+    /// let p = Path::new("/some/file/path.txt");
+    ///
+    /// let file = match File::open_mode(&p, Open, ReadWrite) {
+    ///     Ok(f) => f,
+    ///     Err(e) => panic!("file error: {}", e),
+    /// };
+    /// // do some stuff with that file
+    ///
+    /// // the file will be closed at the end of this block
+    /// ```
+    ///
+    /// `FileMode` and `FileAccess` provide information about the permissions
+    /// context in which a given stream is created. More information about them
+    /// can be found in `std::io`'s docs. If a file is opened with `Write`
+    /// or `ReadWrite` access, then it will be created if it does not already
+    /// exist.
+    ///
+    /// Note that, with this function, a `File` is returned regardless of the
+    /// access-limitations indicated by `FileAccess` (e.g. calling `write` on a
+    /// `File` opened as `Read` will return an error at runtime).
+    ///
+    /// # Error
+    ///
+    /// This function will return an error under a number of different
+    /// circumstances, to include but not limited to:
+    ///
+    /// * Opening a file that does not exist with `Read` access.
+    /// * Attempting to open a file with a `FileAccess` that the user lacks
+    ///   permissions for
+    /// * Filesystem-level errors (full disk, etc)
+    pub fn open_opts(path: &Path,
+                     opts: &OpenOptions) -> io::Result<File> {
+        let inner = try!(fs_imp::File::open(path, &opts.0));
+
+        // On *BSD systems, we can open a directory as a file and read from
+        // it: fd=open("/tmp", O_RDONLY); read(fd, buf, N); due to an old
+        // tradition before the introduction of opendir(3).  We explicitly
+        // reject it because there are few use cases.
+        if cfg!(not(any(target_os = "linux", target_os = "android"))) &&
+           try!(inner.file_attr()).is_dir() {
+            Err(Error::new(ErrorKind::InvalidInput, "is a directory", None))
+        } else {
+            Ok(File { path: path.clone(), inner: inner })
+        }
+    }
+
+    /// Attempts to open a file in read-only mode. This function is equivalent to
+    /// `File::open_mode(path, Open, Read)`, and will raise all of the same
+    /// errors that `File::open_mode` does.
+    ///
+    /// For more information, see the `File::open_mode` function.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::old_io::File;
+    ///
+    /// let contents = File::open(&Path::new("foo.txt")).read_to_end();
+    /// ```
+    pub fn open(path: &Path) -> io::Result<File> {
+        File::open_opts(path, OpenOptions::new().read(true))
+    }
+
+    /// Attempts to create a file in write-only mode. This function is
+    /// equivalent to `File::open_mode(path, Truncate, Write)`, and will
+    /// raise all of the same errors that `File::open_mode` does.
+    ///
+    /// For more information, see the `File::open_mode` function.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # #![allow(unused_must_use)]
+    /// use std::old_io::File;
+    ///
+    /// let mut f = File::create(&Path::new("foo.txt"));
+    /// f.write(b"This is a sample file");
+    /// # drop(f);
+    /// # ::std::old_io::fs::unlink(&Path::new("foo.txt"));
+    /// ```
+    pub fn create(path: &Path) -> io::Result<File> {
+        File::open_opts(path, OpenOptions::new().write(true).create(true)
+                                                .truncate(true))
+    }
+
+    /// Returns the original path that was used to open this file.
+    pub fn path<'a>(&'a self) -> &'a Path {
+        &self.path
+    }
+
+    /// This function is similar to `flush`, except that it may not synchronize
+    /// file metadata to the filesystem. This is intended for use cases that
+    /// must synchronize content, but don't need the metadata on disk. The goal
+    /// of this method is to reduce disk operations.
+    pub fn flush_data(&mut self) -> io::Result<()> {
+        self.inner.datasync()
+    }
+
+    /// Changes the permissions of this file, like `fs::set_perm` but
+    /// without looking the file up by path again.
+    pub fn set_perm(&self, perm: FilePermission) -> io::Result<()> {
+        self.inner.set_perm(perm.0)
+    }
+
+    /// Either truncates or extends the underlying file, updating the size of
+    /// this file to become `size`. This is equivalent to unix's `truncate`
+    /// function.
+    ///
+    /// If the `size` is less than the current file's size, then the file will
+    /// be shrunk. If it is greater than the current file's size, then the file
+    /// will be extended to `size` and have all of the intermediate data filled
+    /// in with 0s.
+    pub fn truncate(&mut self, size: u64) -> io::Result<()> {
+        self.inner.truncate(size)
+    }
+
+    /// Queries information about the underlying file.
+    pub fn file_attr(&self) -> io::Result<FileAttr> {
+        self.inner.file_attr().map(FileAttr)
+    }
+
+    /// Moves this file into or out of non-blocking mode.
+    ///
+    /// While in non-blocking mode, reads and writes which cannot complete
+    /// immediately fail with an error of kind `ErrorKind::WouldBlock` instead
+    /// of blocking. Regular files are always ready, so this is only
+    /// meaningful for files such as FIFOs and terminals.
+    ///
+    /// # Errors
+    ///
+    /// This is not supported on Windows and always returns an error there.
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.inner.set_nonblocking(nonblocking)
+    }
+
+    /// Reads some bytes starting at `offset` in the file into `buf`,
+    /// returning how many bytes were read.
+    ///
+    /// This is like `read`, except that the data comes from the given
+    /// position rather than the cursor. It only needs a shared reference, so
+    /// many threads can read different parts of the same file at once. A
+    /// return value of 0 means `offset` is at or beyond the end of the file.
+    ///
+    /// On unix the cursor is not moved. On Windows it is left after the data
+    /// read, so positional and cursor-based I/O shouldn't be mixed there.
+    pub fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        self.inner.read_at(buf, offset)
+    }
+
+    /// Writes some bytes from `buf` starting at `offset` in the file,
+    /// returning how many bytes were written.
+    ///
+    /// This is like `write`, except that the data goes to the given position
+    /// rather than the cursor, extending the file if needed. It has the same
+    /// cursor caveats as `read_at`. Note that on unix a file opened in append
+    /// mode may ignore `offset` and append the data instead.
+    pub fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        self.inner.write_at(buf, offset)
+    }
+
+    /// Reads exactly `buf.len()` bytes starting at `offset`, calling
+    /// `read_at` as many times as needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind `ErrorKind::EndOfFile` if the end of the file
+    /// is reached first, in which case the contents of `buf` are unspecified.
+    /// Any error from `read_at` is returned immediately.
+    pub fn read_exact_at(&self, mut buf: &mut [u8],
+                         mut offset: u64) -> io::Result<()> {
+        while buf.len() > 0 {
+            let n = try!(self.read_at(buf, offset));
+            if n == 0 {
+                return Err(Error::new(ErrorKind::EndOfFile,
+                                      "failed to fill whole buffer", None))
+            }
+            let tmp = buf;
+            buf = &mut tmp[n..];
+            offset += n as u64;
+        }
+        Ok(())
+    }
+
+    /// Writes all of `buf` starting at `offset`, calling `write_at` as many
+    /// times as needed.
+    ///
+    /// # Errors
+    ///
+    /// Any error from `write_at` is returned immediately.
+    pub fn write_all_at(&self, mut buf: &[u8],
+                        mut offset: u64) -> io::Result<()> {
+        while buf.len() > 0 {
+            let n = try!(self.write_at(buf, offset));
+            if n == 0 {
+                return Err(Error::new(ErrorKind::EndOfFile,
+                                      "failed to write whole buffer", None))
+            }
+            buf = &buf[n..];
+            offset += n as u64;
+        }
+        Ok(())
+    }
+
+    /// Acquires a shared lock on the whole file, blocking until any exclusive
+    /// lock held elsewhere is released.
+    ///
+    /// Locks are advisory: they only exclude others who also lock the file,
+    /// and don't stop anyone from reading or writing it. A lock belongs to
+    /// this `File` (and handles duplicated from it) and is released by
+    /// `unlock` or when the `File` is dropped. On unix, locking a file which
+    /// is already locked through this `File` converts the existing lock to
+    /// the new kind.
+    ///
+    /// On unix this uses `flock`, whose locks are independent of the
+    /// byte-range locks of `lock_range` on some platforms, so the two
+    /// shouldn't be mixed on the same file.
+    pub fn lock_shared(&self) -> io::Result<()> {
+        self.inner.lock(false)
+    }
+
+    /// Acquires an exclusive lock on the whole file, blocking until all locks
+    /// held elsewhere are released.
+    ///
+    /// See `lock_shared` for how locks behave.
+    pub fn lock_exclusive(&self) -> io::Result<()> {
+        self.inner.lock(true)
+    }
+
+    /// Attempts to acquire a shared lock on the whole file without blocking.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind `ErrorKind::Locked` if an exclusive lock is
+    /// held elsewhere.
+    pub fn try_lock_shared(&self) -> io::Result<()> {
+        self.inner.try_lock(false)
+    }
+
+    /// Attempts to acquire an exclusive lock on the whole file without
+    /// blocking.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind `ErrorKind::Locked` if any lock is held
+    /// elsewhere.
+    pub fn try_lock_exclusive(&self) -> io::Result<()> {
+        self.inner.try_lock(true)
+    }
+
+    /// Releases the lock on the whole file taken by one of the `lock_*`
+    /// methods.
+    pub fn unlock(&self) -> io::Result<()> {
+        self.inner.unlock()
+    }
+
+    /// Acquires a lock on `len` bytes of the file starting at `start`,
+    /// blocking until conflicting locks held elsewhere are released.
+    ///
+    /// A `len` of 0 locks everything from `start` onwards, however large the
+    /// file grows. The range may extend past the end of the file.
+    ///
+    /// Like whole-file locks these are advisory and released when the `File`
+    /// is dropped. On Linux they are open file description locks, so they
+    /// conflict with locks taken through other `File`s of the same process;
+    /// on other unix platforms they are classic POSIX record locks, which
+    /// belong to the process as a whole and are all released as soon as any
+    /// `File` for the same file is closed. Old Linux kernels without open
+    /// file description locks fall back to POSIX record locks too.
+    ///
+    /// # Errors
+    ///
+    /// On unix, returns an error of kind `ErrorKind::InvalidInput` if the
+    /// range ends past `i64::MAX`.
+    pub fn lock_range(&self, kind: LockKind, start: u64,
+                      len: u64) -> io::Result<()> {
+        self.inner.lock_range(kind == LockKind::Exclusive, start, len, true)
+    }
+
+    /// Attempts to acquire a lock on a range of the file without blocking.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind `ErrorKind::Locked` if a conflicting lock on
+    /// an overlapping range is held elsewhere.
+    pub fn try_lock_range(&self, kind: LockKind, start: u64,
+                          len: u64) -> io::Result<()> {
+        self.inner.lock_range(kind == LockKind::Exclusive, start, len, false)
+    }
+
+    /// Releases a lock on a range of the file taken by `lock_range` or
+    /// `try_lock_range`.
+    ///
+    /// On Windows the range must be exactly one which was locked.
+    pub fn unlock_range(&self, start: u64, len: u64) -> io::Result<()> {
+        self.inner.unlock_range(start, len)
+    }
+
+    /// Returns whether this file refers to a terminal.
+    pub fn is_terminal(&self) -> bool {
+        self.inner.is_terminal()
+    }
+}
+
+impl AsInner<fs_imp::File> for File {
+    fn as_inner(&self) -> &fs_imp::File { &self.inner }
+}
+
+#[cfg(unix)]
+impl AsInner<FileDesc> for File {
+    fn as_inner(&self) -> &FileDesc { self.inner.fd() }
+}
+
+// A file created from a bare descriptor has no known path, so `path` returns
+// an empty one.
+#[cfg(unix)]
+impl FromInner<FileDesc> for File {
+    fn from_inner(fd: FileDesc) -> File {
+        File { inner: FromInner::from_inner(fd), path: Path::new("") }
+    }
+}
+
+#[cfg(unix)]
+impl IntoInner<FileDesc> for File {
+    fn into_inner(self) -> FileDesc { self.inner.into_fd() }
+}
+
+impl Read for File {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+    fn read_vectored(&mut self, bufs: &mut [&mut [u8]]) -> io::Result<usize> {
+        self.inner.read_vectored(bufs)
+    }
+}
+impl Write for File {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> io::Result<usize> {
+        self.inner.write_vectored(bufs)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.fsync()
+    }
+}
+impl Seek for File {
+    fn seek(&mut self, pos: SeekPos) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+impl AtomicFile {
+    /// Creates a new temporary file which will replace the file at `path`
+    /// when committed.
+    ///
+    /// `path` itself is not touched until then, and need not exist.
+    pub fn create(path: &Path) -> io::Result<AtomicFile> {
+        static COUNTER: AtomicUsize = ATOMIC_USIZE_INIT;
+
+        let name = match path.filename() {
+            Some(name) => name,
+            None => return Err(Error::new(ErrorKind::InvalidInput,
+                                          "path has no file name", None)),
+        };
+        let dir = path.dir_path();
+        let pid = os_imp::getpid();
+        let mut opts = OpenOptions::new();
+        opts.write(true).exclusive(true);
+        // The new contents may be meant for a private file, so nobody else
+        // may read them before `commit` has settled the permissions.
+        private(&mut opts);
+
+        // Names may be left behind by a process which died with the same pid
+        // as ours, so move on to the next name until one is free.
+        loop {
+            let n = COUNTER.fetch_add(1, Ordering::SeqCst);
+            let mut temp = vec![b'.'];
+            temp.push_all(name);
+            temp.push_all(format!(".{}.{}.tmp", pid, n).as_bytes());
+            let temp = dir.join(temp);
+            match File::open_opts(&temp, &opts) {
+                Ok(file) => return Ok(AtomicFile {
+                    file: Some(file),
+                    temp: temp,
+                    target: path.clone(),
+                    preserve_perm: false,
+                    committed: false,
+                }),
+                Err(ref e) if e.kind() == ErrorKind::PathAlreadyExists => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Sets whether the permissions of the file being replaced are copied
+    /// onto the new contents when committed.
+    ///
+    /// Otherwise the target ends up readable and writable by its owner only.
+    /// Nothing is copied if there is no file to replace.
+    pub fn preserve_perm(&mut self, preserve: bool) -> &mut AtomicFile {
+        self.preserve_perm = preserve; self
+    }
+
+    /// Returns the path of the file which will be replaced.
+    pub fn path(&self) -> &Path { &self.target }
+
+    /// Replaces the target with the contents written so far.
+    ///
+    /// The contents are synced to disk, the temporary file is renamed over
+    /// the target, and then the directory containing them is synced so the
+    /// rename itself survives a crash.
+    pub fn commit(mut self) -> io::Result<()> {
+        {
+            let file = self.file.as_mut().unwrap();
+            if self.preserve_perm {
+                match file_attr(&self.target) {
+                    Ok(attr) => try!(file.set_perm(attr.perm())),
+                    Err(ref e) if e.kind() == ErrorKind::FileNotFound => {}
+                    Err(e) => return Err(e),
+                }
+            }
+            try!(file.flush_data());
+        }
+
+        // Windows can't rename a file which is still open
+        drop(self.file.take());
+        try!(rename(&self.temp, &self.target));
+        self.committed = true;
+        fs_imp::sync_dir(&self.target.dir_path())
+    }
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.as_mut().unwrap().write(buf)
+    }
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> io::Result<usize> {
+        self.file.as_mut().unwrap().write_vectored(bufs)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.as_mut().unwrap().flush()
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if !self.committed {
+            drop(self.file.take());
+            let _ = remove_file(&self.temp);
+        }
+    }
+}
+
+// Keeps other users from reading a file which these options create.
+#[cfg(unix)]
+fn private(opts: &mut OpenOptions) {
+    use os::unix::fs::OpenOptionsExt;
+    opts.mode(0o600);
+}
+
+#[cfg(windows)]
+fn private(_opts: &mut OpenOptions) {}
+
+impl OpenOptions {
+    pub fn new() -> OpenOptions {
+        OpenOptions(fs_imp::OpenOptions::new())
+    }
+
+    pub fn read(&mut self, read: bool) -> &mut OpenOptions {
+        self.0.read(read); self
+    }
+
+    pub fn write(&mut self, write: bool) -> &mut OpenOptions {
+        self.0.write(write); self
+    }
+
+    pub fn append(&mut self, append: bool) -> &mut OpenOptions {
+        self.0.append(append); self
+    }
+
+    pub fn truncate(&mut self, truncate: bool) -> &mut OpenOptions {
+        self.0.truncate(truncate); self
+    }
+
+    pub fn create(&mut self, create: bool) -> &mut OpenOptions {
+        self.0.create(create); self
+    }
+
+    /// Sets the option to always create a new file, failing with
+    /// `ErrorKind::PathAlreadyExists` if one already exists at the path.
+    ///
+    /// The check and the creation happen atomically, so of several racing
+    /// openers exactly one succeeds. This implies `create(true)`.
+    pub fn exclusive(&mut self, exclusive: bool) -> &mut OpenOptions {
+        self.0.exclusive(exclusive); self
+    }
+}
+
+impl FileAttr {
+    pub fn is_dir(&self) -> bool { self.0.is_dir() }
+    pub fn is_file(&self) -> bool { self.0.is_file() }
+    pub fn size(&self) -> u64 { self.0.size() }
+    pub fn perm(&self) -> FilePermission { FilePermission(self.0.perm()) }
+
+    /// Returns the type of this entry.
+    pub fn file_type(&self) -> FileType { self.0.file_type() }
+
+    /// Returns whether this entry is a symbolic link.
+    pub fn is_symlink(&self) -> bool {
+        self.file_type() == FileType::Symlink
+    }
+
+    /// Returns the size of this entry in bytes, the same as `size`.
+    pub fn len(&self) -> u64 { self.0.size() }
+
+    /// Returns the time this entry was last accessed.
+    #[unstable = "return type may change (as well as name)"]
+    pub fn accessed(&self) -> FileTime { FileTime::new(self.0.accessed()) }
+
+    /// Returns the time the contents of this entry were last modified.
+    #[unstable = "return type may change (as well as name)"]
+    pub fn modified(&self) -> FileTime { FileTime::new(self.0.modified()) }
+
+    /// Returns the time this entry was created, or `None` if the platform or
+    /// filesystem doesn't record it.
+    #[unstable = "return type may change (as well as name)"]
+    pub fn created(&self) -> Option<FileTime> {
+        self.0.created().map(FileTime::new)
+    }
+}
+
+/// Attributes only available from a unix `stat` structure.
+#[cfg(unix)]
+impl FileAttr {
+    /// Returns the time the status of this entry (its contents, permissions,
+    /// ownership, links, ...) last changed.
+    #[unstable = "return type may change (as well as name)"]
+    pub fn changed(&self) -> FileTime { FileTime::new(self.0.changed()) }
+
+    /// Returns the ID of the device containing this entry.
+    pub fn dev(&self) -> u64 { self.0.dev() }
+    /// Returns the inode number of this entry.
+    pub fn ino(&self) -> u64 { self.0.ino() }
+    /// Returns the number of hard links to this entry.
+    pub fn nlink(&self) -> u64 { self.0.nlink() }
+    /// Returns the user ID of the owner of this entry.
+    pub fn uid(&self) -> u32 { self.0.uid() }
+    /// Returns the group ID of the owner of this entry.
+    pub fn gid(&self) -> u32 { self.0.gid() }
+    /// Returns the number of 512-byte blocks allocated to this entry.
+    pub fn blocks(&self) -> u64 { self.0.blocks() }
+    /// Returns the preferred block size for I/O on this entry.
+    pub fn blksize(&self) -> u64 { self.0.blksize() }
+}
+
+impl FileTime {
+    fn new((secs, nanos): (i64, u32)) -> FileTime {
+        FileTime { secs: secs, nanos: nanos }
+    }
+
+    /// Creates a timestamp `secs` seconds and `nanos` nanoseconds after the
+    /// unix epoch.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `nanos` is not less than one second.
+    pub fn from_parts(secs: i64, nanos: u32) -> FileTime {
+        assert!(nanos < 1_000_000_000, "nanoseconds must be below a second");
+        FileTime::new((secs, nanos))
+    }
+
+    /// Returns the whole number of seconds since the unix epoch.
+    pub fn seconds(&self) -> i64 { self.secs }
+
+    /// Returns the fractional part of this timestamp in nanoseconds.
+    pub fn nanoseconds(&self) -> u32 { self.nanos }
+
+    /// Returns the number of milliseconds since the unix epoch, truncating
+    /// anything finer.
+    pub fn milliseconds(&self) -> i64 {
+        self.secs * 1000 + (self.nanos / 1_000_000) as i64
+    }
+}
+
+impl FilePermission {
+    pub fn readonly(&self) -> bool { self.0.readonly() }
+    pub fn set_readonly(&mut self, readonly: bool) {
+        self.0.set_readonly(readonly)
+    }
+}
+
+impl AsInnerMut<fs_imp::OpenOptions> for OpenOptions {
+    fn as_inner_mut(&mut self) -> &mut fs_imp::OpenOptions { &mut self.0 }
+}
+
+impl AsInner<fs_imp::FilePermission> for FilePermission {
+    fn as_inner(&self) -> &fs_imp::FilePermission { &self.0 }
+}
+
+impl AsInnerMut<fs_imp::FilePermission> for FilePermission {
+    fn as_inner_mut(&mut self) -> &mut fs_imp::FilePermission { &mut self.0 }
+}
+
+impl FromInner<fs_imp::FilePermission> for FilePermission {
+    fn from_inner(perm: fs_imp::FilePermission) -> FilePermission {
+        FilePermission(perm)
+    }
+}
+
+impl Iterator for ReadDir {
+    type Item = io::Result<DirEntry>;
+
+    fn next(&mut self) -> Option<io::Result<DirEntry>> {
+        self.0.next().map(|entry| entry.map(|entry| {
+            DirEntry { path: entry.path(), depth: 1, file_type: None }
+        }))
+    }
+}
+
+impl DirEntry {
+    pub fn path(&self) -> Path { self.path.clone() }
+
+    /// Returns how many directories deep this entry is below the directory
+    /// being read or walked, which itself has depth 0. Entries from
+    /// `read_dir` always have depth 1.
+    pub fn depth(&self) -> usize { self.depth }
+}
+
+/// Unlink a file from the underlying filesystem.
+///
+/// # Example
+///
+/// ```rust
+/// # #![allow(unused_must_use)]
+/// use std::old_io::fs;
+///
+/// let p = Path::new("/some/file/path.txt");
+/// fs::unlink(&p);
+/// ```
+///
+/// Note that, just because an unlink call was successful, it is not
+/// guaranteed that a file is immediately deleted (e.g. depending on
+/// platform, other open file descriptors may prevent immediate removal)
+///
+/// # Error
+///
+/// This function will return an error if `path` points to a directory, if the
+/// user lacks permissions to remove the file, or if some other filesystem-level
+/// error occurs.
+pub fn remove_file(path: &Path) -> io::Result<()> {
+    fs_imp::unlink(path)
+}
+
+/// Given a path, query the file system to get information about a file,
+/// directory, etc. This function will traverse symlinks to query
+/// information about the destination file.
+///
+/// # Example
+///
+/// ```rust
+/// use std::old_io::fs;
+///
+/// let p = Path::new("/some/file/path.txt");
+/// match fs::stat(&p) {
+///     Ok(stat) => { /* ... */ }
+///     Err(e) => { /* handle error */ }
+/// }
+/// ```
+///
+/// # Error
+///
+/// This function will return an error if the user lacks the requisite permissions
+/// to perform a `stat` call on the given `path` or if there is no entry in the
+/// filesystem at the provided path.
+pub fn file_attr(path: &Path) -> io::Result<FileAttr> {
+    fs_imp::stat(path).map(FileAttr)
+}
+
+/// Query the file system for information about `path` without following a
+/// symlink at `path` itself, the equivalent of `lstat`.
+///
+/// If `path` is a symlink then the returned attributes describe the link, and
+/// `is_symlink` on them returns true. Otherwise this is the same as
+/// `file_attr`.
+///
+/// On windows this is currently the same as `file_attr`, which does not
+/// follow symlinks there either.
+pub fn symlink_attr(path: &Path) -> io::Result<FileAttr> {
+    return lstat(path).map(FileAttr);
+
+    #[cfg(unix)]
+    fn lstat(path: &Path) -> io::Result<fs_imp::FileAttr> { fs_imp::lstat(path) }
+    #[cfg(windows)]
+    fn lstat(path: &Path) -> io::Result<fs_imp::FileAttr> { fs_imp::stat(path) }
+}
+
+/// Atomically replaces the contents of the file at `path` with `contents`,
+/// keeping its permissions.
+///
+/// Either the old contents or the new ones are visible at `path` at all
+/// times, even if the process dies part of the way through. See `AtomicFile`
+/// for details.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut file = try!(AtomicFile::create(path));
+    file.preserve_perm(true);
+    try!(file.write_all(contents));
+    file.commit()
+}
+
+/// Rename a file or directory to a new name.
+///
+/// # Example
+///
+/// ```rust
+/// # #![allow(unused_must_use)]
+/// use std::old_io::fs;
+///
+/// fs::rename(&Path::new("foo"), &Path::new("bar"));
+/// ```
+///
+/// # Error
+///
+/// This function will return an error if the provided `from` doesn't exist, if
+/// the process lacks permissions to view the contents, or if some other
+/// intermittent I/O error occurs.
+pub fn rename(from: &Path, to: &Path) -> io::Result<()> {
+    fs_imp::rename(from, to)
+}
+
+/// Copies the contents of one file to another. This function will also
+/// copy the permission bits of the original file to the destination file.
+///
+/// Note that if `from` and `to` both point to the same file, then the file
+/// will likely get truncated by this operation.
+///
+/// Directories can be copied recursively with `copy_dir`.
+///
+/// # Example
+///
+/// ```rust
+/// # #![allow(unused_must_use)]
+/// use std::old_io::fs;
+///
+/// fs::copy(&Path::new("foo.txt"), &Path::new("bar.txt"));
+/// ```
+///
+/// # Error
+///
+/// This function will return an error in the following situations, but is not
+/// limited to just these cases:
+///
+/// * The `from` path is not a file
+/// * The `from` file does not exist
+/// * The current process does not have the permission rights to access
+///   `from` or write `to`
+///
+/// Note that this copy is not atomic in that once the destination is
+/// ensured to not exist, there is nothing preventing the destination from
+/// being created and then destroyed by this operation.
+pub fn copy(from: &Path, to: &Path) -> io::Result<u64> {
+    if !from.is_file() {
+        return Err(Error::new(ErrorKind::MismatchedFileTypeForOperation,
+                              "the source path is not an existing file",
+                              None))
+    }
+
+    let mut reader = try!(File::open(from));
+    // The copy only gets the permissions of `from` at the end, so it is kept
+    // private until then.
+    let mut opts = OpenOptions::new();
+    opts.write(true).create(true).truncate(true);
+    private(&mut opts);
+    let mut writer = try!(File::open_opts(to, &opts));
+    let perm = try!(reader.file_attr()).perm();
+
+    let ret = try!(io::copy(&mut reader, &mut writer));
+    try!(set_perm(to, perm));
+    Ok(ret)
+}
+
+/// Creates a new hard link on the filesystem. The `dst` path will be a
+/// link pointing to the `src` path. Note that systems often require these
+/// two paths to both be located on the same filesystem.
+pub fn hard_link(src: &Path, dst: &Path) -> io::Result<()> {
+    fs_imp::link(src, dst)
+}
+
+/// Creates a new symbolic link on the filesystem. The `dst` path will be a
+/// symlink pointing to the `src` path.
+pub fn sym_link(src: &Path, dst: &Path) -> io::Result<()> {
+    fs_imp::symlink(src, dst)
+}
+
+/// Reads a symlink, returning the file that the symlink points to.
+///
+/// # Error
+///
+/// This function will return an error on failure. Failure conditions include
+/// reading a file that does not exist or reading a file that is not a symlink.
+pub fn read_link(path: &Path) -> io::Result<Path> {
+    fs_imp::readlink(path)
+}
+
+/// Create a new, empty directory at the provided path
+///
+/// # Example
+///
+/// ```rust
+/// # #![allow(unused_must_use)]
+/// use std::old_io;
+/// use std::old_io::fs;
+///
+/// let p = Path::new("/some/dir");
+/// fs::make_dir(&p);
+/// ```
+///
+/// # Error
+///
+/// This function will return an error if the user lacks permissions to make a
+/// new directory at the provided `path`, or if the directory already exists.
+pub fn make_dir(path: &Path) -> io::Result<()> {
+    fs_imp::mkdir(path)
+}
+
+/// Recursively create a directory and all of its parent components if they
+/// are missing.
+///
+/// # Error
+///
+/// See `fs::mkdir`.
+pub fn make_dir_all(path: &Path) -> io::Result<()> {
+    if path.is_dir() { return Ok(()) }
+    try!(make_dir_all(&path.dir_path()));
+    make_dir(path)
+}
+
+/// Remove an existing, empty directory
+///
+/// # Example
+///
+/// ```rust
+/// # #![allow(unused_must_use)]
+/// use std::old_io::fs;
+///
+/// let p = Path::new("/some/dir");
+/// fs::rmdir(&p);
+/// ```
+///
+/// # Error
+///
+/// This function will return an error if the user lacks permissions to remove
+/// the directory at the provided `path`, or if the directory isn't empty.
+pub fn remove_dir(path: &Path) -> io::Result<()> {
+    fs_imp::rmdir(path)
+}
+
+/// Removes a directory at this path, after removing all its contents. Use
+/// carefully!
+///
+/// # Error
+///
+/// See `file::unlink` and `fs::readdir`
+pub fn remove_dir_all(path: &Path) -> io::Result<()> {
+    for child in try!(read_dir(path)) {
+        let child = try!(child).path();
+        let stat = try!(symlink_attr(&child));
+        if stat.is_dir() {
+            try!(remove_dir_all(&child));
+        } else {
+            try!(remove_file(&child));
+        }
+    }
+    remove_dir(path)
+}
+
+/// Retrieve a vector containing all entries within a provided directory
+///
+/// # Example
+///
+/// ```rust
+/// use std::old_io::fs::PathExtensions;
+/// use std::old_io::fs;
+/// use std::old_io;
+///
+/// // one possible implementation of fs::walk_dir only visiting files
+/// fn visit_dirs<F>(dir: &Path, cb: &mut F) -> io::Result<()> where
+///     F: FnMut(&Path),
+/// {
+///     if dir.is_dir() {
+///         let contents = try!(fs::readdir(dir));
+///         for entry in contents.iter() {
+///             if entry.is_dir() {
+///                 try!(visit_dirs(entry, cb));
+///             } else {
+///                 (*cb)(entry);
+///             }
+///         }
+///         Ok(())
+///     } else {
+///         Err(old_io::standard_error(old_io::InvalidInput))
+///     }
+/// }
+/// ```
+///
+/// # Error
+///
+/// This function will return an error if the provided `path` doesn't exist, if
+/// the process lacks permissions to view the contents or if the `path` points
+/// at a non-directory file
+pub fn read_dir(path: &Path) -> io::Result<ReadDir> {
+    fs_imp::readdir(path).map(ReadDir)
+}
+
+/// Utility methods for paths.
+pub trait PathExt {
+    /// Get information on the file, directory, etc at this path.
+    ///
+    /// Consult the `fs::stat` documentation for more info.
+    ///
+    /// This call preserves identical runtime/error semantics with `file::stat`.
+    fn file_attr(&self) -> io::Result<FileAttr>;
+
+    /// Boolean value indicator whether the underlying file exists on the local
+    /// filesystem. Returns false in exactly the cases where `fs::stat` fails.
+    fn exists(&self) -> bool;
+
+    /// Whether the underlying implementation (be it a file path, or something
+    /// else) points at a "regular file" on the FS. Will return false for paths
+    /// to non-existent locations or directories or other non-regular files
+    /// (named pipes, etc). Follows links when making this determination.
+    fn is_file(&self) -> bool;
+
+    /// Whether the underlying implementation (be it a file path, or something
+    /// else) is pointing at a directory in the underlying FS. Will return
+    /// false for paths to non-existent locations or if the item is not a
+    /// directory (eg files, named pipes, etc). Follows links when making this
+    /// determination.
+    fn is_dir(&self) -> bool;
+}
+
+impl PathExt for Path {
+    fn file_attr(&self) -> io::Result<FileAttr> { file_attr(self) }
+
+    fn exists(&self) -> bool { file_attr(self).is_ok() }
+
+    fn is_file(&self) -> bool {
+        file_attr(self).map(|s| s.is_file()).unwrap_or(false)
+    }
+    fn is_dir(&self) -> bool {
+        file_attr(self).map(|s| s.is_dir()).unwrap_or(false)
+    }
+}
+
+/// Changes the timestamps for a file's last modification and access time.
+/// The file at the path specified will have its last access time set to
+/// `atime` and its modification time set to `mtime`. The times specified should
+/// be in milliseconds.
+///
+/// This is a shorthand for `set_file_times`.
+#[unstable = "argument types and argument counts may change"]
+pub fn change_file_times(path: &Path, atime: u64, mtime: u64) -> io::Result<()> {
+    fn from_ms(ms: u64) -> FileTime {
+        FileTime::new(((ms / 1000) as i64, (ms % 1000) as u32 * 1_000_000))
+    }
+    set_file_times(path, from_ms(atime), from_ms(mtime))
+}
+
+/// Sets the last access time of the file at `path` to `atime` and its last
+/// modification time to `mtime`, following symlinks.
+///
+/// The times are kept to the precision of the filesystem, which may be as
+/// fine as a nanosecond. Some platforms only take microseconds, and Windows
+/// takes units of 100 nanoseconds.
+pub fn set_file_times(path: &Path, atime: FileTime,
+                      mtime: FileTime) -> io::Result<()> {
+    fs_imp::set_times(path, (atime.secs, atime.nanos),
+                      (mtime.secs, mtime.nanos))
+}
+
+/// Changes the permission mode bits found on a file or a directory. This
+/// function takes a mask from the `io` module
+///
+/// # Example
+///
+/// ```rust
+/// # #![allow(unused_must_use)]
+/// use std::old_io;
+/// use std::old_io::fs;
+///
+/// fs::chmod(&Path::new("file.txt"), old_io::USER_FILE);
+/// fs::chmod(&Path::new("file.txt"), old_io::USER_READ | old_io::USER_WRITE);
+/// fs::chmod(&Path::new("dir"),      old_io::USER_DIR);
+/// fs::chmod(&Path::new("file.exe"), old_io::USER_EXEC);
+/// ```
+///
+/// # Error
+///
+/// This function will return an error if the provided `path` doesn't exist, if
+/// the process lacks permissions to change the attributes of the file, or if
+/// some other I/O error is encountered.
+pub fn set_perm(path: &Path, perm: FilePermission) -> io::Result<()> {
+    fs_imp::set_perm(path, perm.0)
+}
@@ -0,0 +1,271 @@
+// Copyright 2015 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Temporary files and directories which are removed when dropped.
+
+use prelude::v1::*;
+
+use env;
+use error::{Error as StdError, FromError};
+use fmt;
+use fs::{self, File, OpenOptions, private};
+use io::{self, Error, ErrorKind, Read, Write, Seek, SeekPos};
+use path::{Path, GenericPath};
+use std::rand::{self, Rng};
+use sys::fs as fs_imp;
+
+// How many names are tried before giving up, and how many random characters
+// each one has.
+const NUM_RETRIES: u32 = 1 << 16;
+const NUM_RAND_CHARS: usize = 12;
+
+/// A directory in the filesystem which is recursively removed, along with
+/// all of its contents, when dropped.
+///
+/// # Example
+///
+/// ```no_run
+/// use io2::fs::{File, TempDir};
+///
+/// let dir = TempDir::new("example").unwrap();
+/// let file = File::create(&dir.path().join("scratch.txt")).unwrap();
+/// // `dir` and everything in it is removed at the end of this scope
+/// ```
+pub struct TempDir {
+    path: Option<Path>,
+}
+
+/// A named file in the filesystem which is removed when dropped.
+///
+/// Unlike the unnamed files from `tempfile`, these can be reopened by name or
+/// handed to other processes, and can be kept with `persist`.
+pub struct NamedTempFile {
+    file: Option<File>,
+    path: Path,
+}
+
+/// An error returned by `NamedTempFile::persist`, which gives back the
+/// temporary file along with the error.
+#[derive(Debug)]
+pub struct PersistError {
+    error: Error,
+    file: NamedTempFile,
+}
+
+/// Creates a new temporary file in the system's temporary directory, which
+/// is removed once the returned `File` is closed.
+///
+/// Where the platform supports it (`O_TMPFILE` on Linux) the file never has
+/// a name at all. Otherwise it is created under a random name which is
+/// removed again right away, so it can't be opened by name either way.
+pub fn tempfile() -> io::Result<File> {
+    tempfile_in(&env::temp_dir())
+}
+
+/// Creates a new temporary file in `dir`, as `tempfile` does.
+pub fn tempfile_in(dir: &Path) -> io::Result<File> {
+    if let Some(inner) = try!(fs_imp::tmpfile(dir)) {
+        return Ok(File { inner: inner, path: Path::new("") })
+    }
+    let mut named = try!(NamedTempFile::new_in(dir));
+    try!(fs::remove_file(&named.path));
+    let mut file = named.file.take().unwrap();
+    file.path = Path::new("");
+    Ok(file)
+}
+
+// Calls `f` with random paths in `dir` starting with `prefix` until it
+// manages to create something at one of them.
+fn create_helper<T, F>(dir: &Path, prefix: &str, mut f: F) -> io::Result<T>
+    where F: FnMut(Path) -> io::Result<T>
+{
+    let mut rng = rand::thread_rng();
+    for _ in 0..NUM_RETRIES {
+        let suffix: String = rng.gen_ascii_chars().take(NUM_RAND_CHARS)
+                                .collect();
+        let path = dir.join(format!("{}{}", prefix, suffix));
+        match f(path) {
+            Err(ref e) if e.kind() == ErrorKind::PathAlreadyExists => {}
+            result => return result,
+        }
+    }
+    Err(Error::new(ErrorKind::PathAlreadyExists,
+                   "too many temporary files exist", None))
+}
+
+impl TempDir {
+    /// Creates a new directory in the system's temporary directory, whose
+    /// name starts with `prefix` followed by random characters.
+    pub fn new(prefix: &str) -> io::Result<TempDir> {
+        TempDir::new_in(&env::temp_dir(), prefix)
+    }
+
+    /// Creates a new directory in `dir`, whose name starts with `prefix`
+    /// followed by random characters.
+    ///
+    /// A relative `dir` is taken relative to the current directory at the
+    /// time of this call, so later changes of directory don't matter.
+    pub fn new_in(dir: &Path, prefix: &str) -> io::Result<TempDir> {
+        let dir = if dir.is_relative() {
+            try!(env::current_dir()).join(dir)
+        } else {
+            dir.clone()
+        };
+        create_helper(&dir, prefix, |path| {
+            make_private_dir(&path).map(|()| TempDir { path: Some(path) })
+        })
+    }
+
+    /// Returns the path of this directory.
+    pub fn path(&self) -> &Path {
+        self.path.as_ref().unwrap()
+    }
+
+    /// Consumes this `TempDir` without removing the directory, returning
+    /// its path.
+    pub fn into_path(mut self) -> Path {
+        self.path.take().unwrap()
+    }
+
+    /// Removes the directory and its contents now, reporting any error
+    /// which would otherwise be ignored when it is dropped.
+    pub fn close(mut self) -> io::Result<()> {
+        fs::remove_dir_all(&self.path.take().unwrap())
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        if let Some(ref path) = self.path {
+            let _ = fs::remove_dir_all(path);
+        }
+    }
+}
+
+impl NamedTempFile {
+    /// Creates a new file in the system's temporary directory, opened for
+    /// reading and writing.
+    pub fn new() -> io::Result<NamedTempFile> {
+        NamedTempFile::new_in(&env::temp_dir())
+    }
+
+    /// Creates a new file in `dir`, opened for reading and writing.
+    ///
+    /// The file is created only if nothing exists under its random name, so
+    /// a file or symlink planted there by somebody else is never opened.
+    pub fn new_in(dir: &Path) -> io::Result<NamedTempFile> {
+        let mut opts = OpenOptions::new();
+        opts.read(true).write(true).exclusive(true);
+        private(&mut opts);
+        create_helper(dir, ".tmp", |path| {
+            File::open_opts(&path, &opts).map(|file| {
+                NamedTempFile { file: Some(file), path: path }
+            })
+        })
+    }
+
+    /// Returns the path of this file.
+    pub fn path(&self) -> &Path { &self.path }
+
+    /// Returns a reference to the open file.
+    pub fn as_file(&self) -> &File { self.file.as_ref().unwrap() }
+
+    /// Returns a mutable reference to the open file.
+    pub fn as_file_mut(&mut self) -> &mut File {
+        self.file.as_mut().unwrap()
+    }
+
+    /// Moves this file to `new_path` so that it is no longer removed,
+    /// returning the open file.
+    ///
+    /// `new_path` is replaced if it exists, and must be on the same
+    /// filesystem as the temporary file. On failure the temporary file is
+    /// left as it was and handed back in the error, so that it can be
+    /// persisted some other way.
+    pub fn persist(mut self, new_path: &Path) -> Result<File, PersistError> {
+        if let Err(e) = fs::rename(&self.path, new_path) {
+            return Err(PersistError { error: e, file: self })
+        }
+        let mut file = self.file.take().unwrap();
+        file.path = new_path.clone();
+        Ok(file)
+    }
+}
+
+impl Read for NamedTempFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.as_file_mut().read(buf)
+    }
+}
+
+impl Write for NamedTempFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.as_file_mut().write(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.as_file_mut().flush()
+    }
+}
+
+impl Seek for NamedTempFile {
+    fn seek(&mut self, pos: SeekPos) -> io::Result<u64> {
+        self.as_file_mut().seek(pos)
+    }
+}
+
+impl Drop for NamedTempFile {
+    fn drop(&mut self) {
+        // Only remove the file if it hasn't been persisted, and close it
+        // first as Windows won't remove files which are open.
+        if let Some(file) = self.file.take() {
+            drop(file);
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+impl fmt::Debug for NamedTempFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "NamedTempFile({})", self.path.display())
+    }
+}
+
+impl PersistError {
+    /// Returns the error which made the rename fail.
+    pub fn error(&self) -> &Error { &self.error }
+
+    /// Returns the temporary file, which still exists under its old name.
+    pub fn into_file(self) -> NamedTempFile { self.file }
+}
+
+impl FromError<PersistError> for Error {
+    fn from_error(err: PersistError) -> Error { err.error }
+}
+
+impl StdError for PersistError {
+    fn description(&self) -> &str { self.error.description() }
+}
+
+impl fmt::Display for PersistError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+// Keeps other users from listing or entering a new temporary directory.
+#[cfg(unix)]
+fn make_private_dir(path: &Path) -> io::Result<()> {
+    fs_imp::mkdir_mode(path, 0o700)
+}
+
+#[cfg(windows)]
+fn make_private_dir(path: &Path) -> io::Result<()> {
+    fs::make_dir(path)
+}
@@ -0,0 +1,316 @@
+// Copyright 2015 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Recursive iteration over directory trees.
+
+use prelude::v1::*;
+
+use cmp::Ordering;
+use fs::{self, DirEntry, FileType, ReadDir};
+use io::{self, Error, ErrorKind};
+use path::{Path, GenericPath};
+use sys::fs as fs_imp;
+use usize;
+use vec;
+
+/// Returns an iterator that will recursively walk the directory structure
+/// rooted at `path`, with the default options of `WalkDir::new`.
+///
+/// # Error
+///
+/// Unlike `WalkDir::new`, this function returns an error right away if
+/// `path` can't be read as a directory.
+pub fn walk_dir(path: &Path) -> io::Result<WalkDir<'static>> {
+    try!(fs::read_dir(path));
+    Ok(WalkDir::new(path))
+}
+
+/// An iterator over the entries of a directory tree, which is also the
+/// builder used to configure the walk.
+///
+/// The walk is depth first, and each directory is yielded before its
+/// contents unless `contents_first` is set. Errors from reading entries are
+/// yielded as they happen and the walk carries on afterwards.
+///
+/// # Example
+///
+/// ```no_run
+/// use io2::fs::WalkDir;
+///
+/// let mut walk = WalkDir::new(&Path::new("src")).max_depth(2);
+/// while let Some(entry) = walk.next() {
+///     let entry = entry.unwrap();
+///     if entry.path().filename_str() == Some("target") {
+///         walk.skip_current_dir();
+///     }
+/// }
+/// ```
+pub struct WalkDir<'a> {
+    root: Option<Path>,
+    min_depth: usize,
+    max_depth: usize,
+    follow_links: bool,
+    same_file_system: bool,
+    contents_first: bool,
+    sorter: Option<Box<FnMut(&DirEntry, &DirEntry) -> Ordering + 'a>>,
+
+    // The device of the root, when staying on one file system.
+    root_dev: Option<u64>,
+    // One level per directory being read, innermost last.
+    stack: Vec<Level>,
+    // A directory which was just yielded and is descended into on the next
+    // call to `next`, unless `skip_current_dir` is called first.
+    pending: Option<DirEntry>,
+    // Whether the entry just yielded is a directory which is either not
+    // descended into or already finished, leaving nothing to skip.
+    finished_dir: bool,
+}
+
+struct Level {
+    entries: Entries,
+    // The depth of the entries in this directory.
+    depth: usize,
+    // The directory being read, if it is yielded after its contents.
+    dir: Option<DirEntry>,
+    // The (device, inode) pair of the directory, when following links.
+    id: Option<(u64, u64)>,
+}
+
+enum Entries {
+    Unsorted(ReadDir),
+    Sorted(vec::IntoIter<io::Result<DirEntry>>),
+}
+
+impl<'a> WalkDir<'a> {
+    /// Creates a walk over the tree rooted at `root`.
+    ///
+    /// By default every entry below `root` is yielded, but not `root`
+    /// itself, symlinks are not followed, and entries come in the order the
+    /// filesystem returns them. Nothing is read until the first call to
+    /// `next`, so errors with `root` are yielded from there.
+    pub fn new(root: &Path) -> WalkDir<'a> {
+        WalkDir {
+            root: Some(root.clone()),
+            min_depth: 1,
+            max_depth: usize::MAX,
+            follow_links: false,
+            same_file_system: false,
+            contents_first: false,
+            sorter: None,
+            root_dev: None,
+            stack: Vec::new(),
+            pending: None,
+            finished_dir: false,
+        }
+    }
+
+    /// Sets the smallest depth of entries which are yielded. The root has
+    /// depth 0, so setting this to 0 yields the root too.
+    pub fn min_depth(mut self, depth: usize) -> WalkDir<'a> {
+        self.min_depth = depth; self
+    }
+
+    /// Sets the largest depth of entries which are yielded. Directories at
+    /// this depth are yielded but not descended into.
+    pub fn max_depth(mut self, depth: usize) -> WalkDir<'a> {
+        self.max_depth = depth; self
+    }
+
+    /// Sets whether symlinks to directories are descended into.
+    ///
+    /// When following links, a symlink which leads back to one of its own
+    /// parent directories is yielded as an error instead of being walked
+    /// again. The root is always followed if it is a symlink.
+    pub fn follow_links(mut self, follow: bool) -> WalkDir<'a> {
+        self.follow_links = follow; self
+    }
+
+    /// Sets whether directories on file systems other than the root's are
+    /// descended into. Such directories are still yielded themselves.
+    pub fn same_file_system(mut self, same: bool) -> WalkDir<'a> {
+        self.same_file_system = same; self
+    }
+
+    /// Sets whether each directory is yielded after its contents rather
+    /// than before them.
+    pub fn contents_first(mut self, contents_first: bool) -> WalkDir<'a> {
+        self.contents_first = contents_first; self
+    }
+
+    /// Yields the entries of each directory in the order given by `cmp`.
+    ///
+    /// Each directory is read in full before any of its entries are
+    /// yielded, and errors from reading it come first.
+    pub fn sort_by<F>(mut self, cmp: F) -> WalkDir<'a>
+        where F: FnMut(&DirEntry, &DirEntry) -> Ordering + 'a
+    {
+        self.sorter = Some(Box::new(cmp)); self
+    }
+
+    /// Stops walking the most recently yielded directory, or if that was
+    /// not a directory, the directory it was in. Nothing happens if the
+    /// directory wouldn't be walked anyway, such as one at `max_depth`.
+    ///
+    /// With `contents_first` the directory itself is still yielded, right
+    /// after this call.
+    pub fn skip_current_dir(&mut self) {
+        if self.pending.take().is_some() || self.finished_dir {
+            return
+        }
+        if self.contents_first {
+            if let Some(level) = self.stack.last_mut() {
+                level.entries = Entries::Sorted(Vec::new().into_iter());
+            }
+        } else {
+            self.stack.pop();
+        }
+    }
+
+    // Decides what to do with an entry which has just been found, returning
+    // what to yield for it, if anything.
+    fn visit(&mut self, mut entry: DirEntry) -> Option<io::Result<DirEntry>> {
+        let file_type = match self.file_type(&entry) {
+            Ok(file_type) => file_type,
+            Err(e) => return Some(Err(e)),
+        };
+        entry.file_type = Some(file_type);
+        let is_dir = file_type == FileType::Dir;
+        let yielded = entry.depth >= self.min_depth;
+        if !is_dir || entry.depth >= self.max_depth {
+            self.finished_dir = is_dir;
+            return if yielded { Some(Ok(entry)) } else { None }
+        }
+        if self.contents_first || !yielded {
+            return self.push(entry).err().map(Err)
+        }
+        self.pending = Some(entry.clone());
+        Some(Ok(entry))
+    }
+
+    // Returns the type of an entry, looking through symlinks only if they
+    // are followed.
+    fn file_type(&self, entry: &DirEntry) -> io::Result<FileType> {
+        if self.follow_links || entry.depth == 0 {
+            fs::file_attr(&entry.path).map(|attr| attr.file_type())
+        } else {
+            fs::symlink_attr(&entry.path).map(|attr| attr.file_type())
+        }
+    }
+
+    // Starts reading the directory `dir`. A directory on another file
+    // system gets no entries, but is still yielded with `contents_first`.
+    fn push(&mut self, dir: DirEntry) -> io::Result<()> {
+        let id = if self.follow_links || self.same_file_system {
+            Some(try!(fs_imp::file_id(&dir.path)))
+        } else {
+            None
+        };
+        let mut skip = false;
+        if let (true, Some((dev, _))) = (self.same_file_system, id) {
+            if dir.depth == 0 {
+                self.root_dev = Some(dev);
+            }
+            skip = self.root_dev != Some(dev);
+        }
+        if self.follow_links && self.stack.iter().any(|l| l.id == id) {
+            return Err(Error::new(ErrorKind::Other, "filesystem loop found",
+                                  Some(dir.path.display().to_string())))
+        }
+
+        let entries = if skip {
+            Entries::Sorted(Vec::new().into_iter())
+        } else {
+            let read = try!(fs::read_dir(&dir.path));
+            match self.sorter {
+                Some(ref mut cmp) => {
+                    let mut results = Vec::new();
+                    let mut entries = Vec::new();
+                    for entry in read {
+                        match entry {
+                            Ok(entry) => entries.push(entry),
+                            Err(e) => results.push(Err(e)),
+                        }
+                    }
+                    entries.sort_by(|a, b| (*cmp)(a, b));
+                    results.extend(entries.into_iter().map(Ok));
+                    Entries::Sorted(results.into_iter())
+                }
+                None => Entries::Unsorted(read),
+            }
+        };
+        if skip && !self.contents_first {
+            return Ok(())
+        }
+        let depth = dir.depth + 1;
+        let dir = if self.contents_first && dir.depth >= self.min_depth {
+            Some(dir)
+        } else {
+            None
+        };
+        self.stack.push(Level {
+            entries: entries,
+            depth: depth,
+            dir: dir,
+            id: id,
+        });
+        Ok(())
+    }
+}
+
+impl<'a> Iterator for WalkDir<'a> {
+    type Item = io::Result<DirEntry>;
+
+    fn next(&mut self) -> Option<io::Result<DirEntry>> {
+        self.finished_dir = false;
+        if let Some(root) = self.root.take() {
+            let entry = DirEntry { path: root, depth: 0, file_type: None };
+            if let Some(ret) = self.visit(entry) {
+                return Some(ret)
+            }
+        }
+        loop {
+            if let Some(dir) = self.pending.take() {
+                if let Err(e) = self.push(dir) {
+                    return Some(Err(e))
+                }
+            }
+            let (next, depth) = match self.stack.last_mut() {
+                Some(level) => (level.entries.next(), level.depth),
+                None => return None,
+            };
+            match next {
+                Some(Ok(mut entry)) => {
+                    entry.depth = depth;
+                    if let Some(ret) = self.visit(entry) {
+                        return Some(ret)
+                    }
+                }
+                Some(Err(e)) => return Some(Err(e)),
+                None => {
+                    if let Some(dir) = self.stack.pop().unwrap().dir {
+                        self.finished_dir = true;
+                        return Some(Ok(dir))
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Iterator for Entries {
+    type Item = io::Result<DirEntry>;
+
+    fn next(&mut self) -> Option<io::Result<DirEntry>> {
+        match *self {
+            Entries::Unsorted(ref mut read) => read.next(),
+            Entries::Sorted(ref mut entries) => entries.next(),
+        }
+    }
+}
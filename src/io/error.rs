@@ -76,6 +76,12 @@ pub enum ErrorKind {
     /// The operation temporarily failed (for example, because a signal was
     /// received), and retrying may succeed.
     ResourceUnavailable,
+    /// The operation needs to block to complete, but the I/O object was
+    /// placed in non-blocking mode.
+    WouldBlock,
+    /// A lock could not be acquired without blocking because a conflicting
+    /// lock is held by someone else.
+    Locked,
     /// A parameter was incorrect in a way that caused an I/O error not part of
     /// this list.
     InvalidInput,
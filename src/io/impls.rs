@@ -22,20 +22,41 @@ use vec::Vec;
 
 impl<'a, R: Read> Read for ByRef<'a, R> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> { self.inner.read(buf) }
+    fn read_vectored(&mut self, bufs: &mut [&mut [u8]]) -> io::Result<usize> {
+        self.inner.read_vectored(bufs)
+    }
 }
 impl<'a, R: Read> Read for &'a mut R {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> { (**self).read(buf) }
+    fn read_vectored(&mut self, bufs: &mut [&mut [u8]]) -> io::Result<usize> {
+        (**self).read_vectored(bufs)
+    }
 }
 
 impl<'a, W: Write> Write for ByRef<'a, W> {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> { self.inner.write(buf) }
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> io::Result<usize> {
+        self.inner.write_vectored(bufs)
+    }
     fn flush(&mut self) -> io::Result<()> { self.inner.flush() }
 }
 impl<'a, W: Write> Write for &'a mut W {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> { (**self).write(buf) }
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> io::Result<usize> {
+        (**self).write_vectored(bufs)
+    }
     fn flush(&mut self) -> io::Result<()> { (**self).flush() }
 }
 
+impl<'a, B: BufferedRead> BufferedRead for ByRef<'a, B> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> { self.inner.fill_buf() }
+    fn consume(&mut self, amt: usize) { self.inner.consume(amt) }
+}
+impl<'a, B: BufferedRead> BufferedRead for &'a mut B {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> { (**self).fill_buf() }
+    fn consume(&mut self, amt: usize) { (**self).consume(amt) }
+}
+
 impl<'a, S: Seek> Seek for ByRef<'a, S> {
     fn seek(&mut self, pos: SeekPos) -> io::Result<u64> { self.inner.seek(pos) }
 }
@@ -78,5 +99,14 @@ impl Write for Vec<u8> {
         self.push_all(buf);
         Ok(buf.len())
     }
+
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> io::Result<usize> {
+        let len = bufs.iter().fold(0, |n, b| n + b.len());
+        self.reserve(len);
+        for buf in bufs.iter() {
+            self.push_all(*buf);
+        }
+        Ok(len)
+    }
 }
 
@@ -0,0 +1,66 @@
+// Copyright 2015 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use prelude::v1::*;
+
+use cell::UnsafeCell;
+use mem;
+use rt;
+use sync::{StaticMutex, Arc};
+
+/// A lazily initialized, process-global `Arc<T>`.
+///
+/// The value is created on first access and torn down when the runtime shuts
+/// down. Accesses after shutdown return `None`.
+pub struct Lazy<T> {
+    pub lock: StaticMutex,
+    pub ptr: UnsafeCell<*mut Arc<T>>,
+    pub init: fn() -> Arc<T>,
+}
+
+unsafe impl<T> Sync for Lazy<T> {}
+
+macro_rules! lazy_init {
+    ($init:expr) => (::io::lazy::Lazy {
+        lock: ::sync::MUTEX_INIT,
+        ptr: ::cell::UnsafeCell { value: 0 as *mut _ },
+        init: $init,
+    })
+}
+
+impl<T: Send + Sync + 'static> Lazy<T> {
+    pub fn get(&'static self) -> Option<Arc<T>> {
+        let _g = self.lock.lock();
+        unsafe {
+            let ptr = *self.ptr.get();
+            if ptr.is_null() {
+                Some(self.init())
+            } else if ptr as usize == 1 {
+                None
+            } else {
+                Some((*ptr).clone())
+            }
+        }
+    }
+
+    // Must be called with `self.lock` held
+    unsafe fn init(&'static self) -> Arc<T> {
+        let ret = (self.init)();
+        *self.ptr.get() = mem::transmute(Box::new(ret.clone()));
+        rt::at_exit(move || {
+            let g = self.lock.lock();
+            let ptr = *self.ptr.get();
+            *self.ptr.get() = 1 as *mut _;
+            drop(g);
+            let _: Box<Arc<T>> = mem::transmute(ptr);
+        });
+        ret
+    }
+}
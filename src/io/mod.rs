@@ -31,16 +31,32 @@ use result::Result::{Ok, Err};
 use result;
 use slice::{self, SliceExt};
 use str::{self, StrExt};
+use string::String;
 use vec::Vec;
 
+pub use self::buffered::{BufReader, BufWriter, LineWriter, IntoInnerError};
+pub use self::stdio::{stdin, stdout, stderr, Stdin, Stdout, Stderr};
+pub use self::stdio::{StdinLock, StdoutLock, StderrLock};
 pub use self::util::{copy, sink, Sink, empty, Empty, repeat, Repeat};
 pub use self::mem::Cursor;
 pub use self::error::{Result, Error, ErrorKind};
+#[cfg(unix)]
+pub use self::pipe::{pipe, PipeReader, PipeWriter};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use self::poll::{Poll, Token, Interest, Trigger, Events, Event, EventsIter};
+
+#[macro_use] mod lazy;
 
 pub mod prelude;
+mod buffered;
 mod error;
 mod impls;
 mod mem;
+#[cfg(unix)]
+mod pipe;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod poll;
+mod stdio;
 mod util;
 
 const DEFAULT_BUF_SIZE: usize = 64 * 1024;
@@ -78,6 +94,23 @@ pub trait Read {
     /// variant will be returned. If an error is returned then it is guaranteed
     /// that no bytes were read successfully.
     fn read(&mut self, buf: &mut [u8]) -> Result<usize>;
+
+    /// Like `read`, except that it reads into a sequence of buffers.
+    ///
+    /// Data is copied to fill each buffer in order, with the final buffer
+    /// written to possibly being only partially filled. This method must
+    /// behave as a single call to `read` with the buffers concatenated would.
+    ///
+    /// The default implementation calls `read` with the first nonempty
+    /// buffer provided, or returns `Ok(0)` if there is none. Objects such as
+    /// files and sockets override this to fill all of the buffers with a
+    /// single system call.
+    fn read_vectored(&mut self, bufs: &mut [&mut [u8]]) -> Result<usize> {
+        match bufs.iter_mut().find(|b| !b.is_empty()) {
+            Some(buf) => self.read(*buf),
+            None => Ok(0),
+        }
+    }
 }
 
 /// Extension methods for all instances of `Read`, typically imported through
@@ -234,6 +267,24 @@ pub trait Write {
     /// written to this writer.
     fn write(&mut self, buf: &[u8]) -> Result<usize>;
 
+    /// Like `write`, except that it writes from a sequence of buffers.
+    ///
+    /// Data is copied from each buffer in order, with the final buffer read
+    /// from possibly being only partially consumed. This method must behave
+    /// as a call to `write` with the buffers concatenated would.
+    ///
+    /// The default implementation calls `write` with the first nonempty
+    /// buffer provided, or returns `Ok(0)` if there is none. Objects such as
+    /// files and sockets override this to write all of the buffers with a
+    /// single system call, which avoids either copying a header and a body
+    /// into one buffer or sending them in separate calls.
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> Result<usize> {
+        match bufs.iter().find(|b| !b.is_empty()) {
+            Some(buf) => self.write(*buf),
+            None => Ok(0),
+        }
+    }
+
     /// Flush this output stream, ensuring that all intermediately buffered
     /// contents reach their destination.
     ///
@@ -269,6 +320,42 @@ pub trait WriteExt: Write + Sized {
         Ok(())
     }
 
+    /// Attempts to write the contents of a sequence of buffers into this
+    /// writer, as if they were one concatenated buffer.
+    ///
+    /// This method will continuously call `write_vectored` until all of the
+    /// data has been written or an error occurs. The first error generated
+    /// from this method will be returned.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the first error that `write_vectored`
+    /// returns.
+    #[unstable = "this function loses information about intermediate writes"]
+    fn write_all_vectored(&mut self, bufs: &[&[u8]]) -> Result<()> {
+        let mut bufs = bufs.iter().map(|b| *b).filter(|b| !b.is_empty())
+                           .collect::<Vec<&[u8]>>();
+        let mut start = 0;
+        while start < bufs.len() {
+            let mut n = try!(self.write_vectored(&bufs[start..]));
+            if n == 0 {
+                return Err(Error::new(ErrorKind::EndOfFile,
+                                      "failed to write whole buffer: eof reached",
+                                      None))
+            }
+            // Skip the buffers which were written entirely, then trim the
+            // one which was written partially (if any).
+            while start < bufs.len() && n >= bufs[start].len() {
+                n -= bufs[start].len();
+                start += 1;
+            }
+            if n > 0 {
+                bufs[start] = &bufs[start][n..];
+            }
+        }
+        Ok(())
+    }
+
     /// Writes a formatted string into this writer, returning any error
     /// encountered.
     ///
@@ -408,6 +495,140 @@ pub trait BufferedRead: Read {
     fn consume(&mut self, amt: usize);
 }
 
+/// Extension methods for all instances of `BufferedRead`, typically imported
+/// through `std::io::prelude::*`.
+pub trait BufferedReadExt: BufferedRead + Sized {
+    /// Read all bytes until the delimiter `byte` is reached.
+    ///
+    /// This function will continue to read (and buffer) bytes from the
+    /// underlying stream until the delimiter or EOF is found. Once found, all
+    /// bytes up to, and including, the delimiter (if found) will be appended
+    /// to `buf`.
+    ///
+    /// If this buffered reader is currently at EOF, then this function will
+    /// not place any more bytes into `buf` and will return `Ok(0)`. Otherwise
+    /// the number of bytes appended to `buf` is returned.
+    ///
+    /// # Errors
+    ///
+    /// This function will ignore all instances of `ErrorKind::Interrupted` and
+    /// will otherwise return any errors returned by `fill_buf`.
+    ///
+    /// If an I/O error is encountered then all bytes read so far will be
+    /// present in `buf` and its length will have been adjusted appropriately.
+    fn read_until(&mut self, byte: u8, buf: &mut Vec<u8>) -> Result<usize> {
+        read_until(self, byte, buf)
+    }
+
+    /// Read all bytes until a newline byte (the 0xA byte) is reached.
+    ///
+    /// This function will continue to read (and buffer) bytes from the
+    /// underlying stream until the newline delimiter (the 0xA byte) or EOF is
+    /// found. Once found, all bytes up to, and including, the delimiter (if
+    /// found) will be appended to `buf`.
+    ///
+    /// If this reader is currently at EOF then this function will not place
+    /// any more bytes into `buf` and will return `Ok(0)`. Otherwise the number
+    /// of bytes appended to `buf` is returned.
+    ///
+    /// # Errors
+    ///
+    /// This function has the same error semantics as `read_until` and will
+    /// also return an error if the read bytes are not valid UTF-8. If the
+    /// bytes are not valid UTF-8 then `buf` is left untouched. If an I/O error
+    /// is encountered then `buf` will contain all of the data read so far as
+    /// long as that data was valid UTF-8.
+    fn read_line(&mut self, buf: &mut String) -> Result<usize> {
+        append_to_string(buf, |b| read_until(self, b'\n', b))
+    }
+
+    /// Returns an iterator over the contents of this reader split on the byte
+    /// `byte`.
+    ///
+    /// The iterator returned from this function will return instances of
+    /// `io::Result<Vec<u8>>`. Each vector returned will *not* have the
+    /// delimiter byte at the end.
+    ///
+    /// This function will yield errors whenever `read_until` would have also
+    /// yielded an error.
+    fn split(self, byte: u8) -> Split<Self> {
+        Split { buf: self, delim: byte }
+    }
+
+    /// Returns an iterator over the lines of this reader.
+    ///
+    /// The iterator returned from this function will yield instances of
+    /// `io::Result<String>`. Each string returned will *not* have a newline
+    /// byte (the 0xA byte) or a carriage return followed by a newline at the
+    /// end.
+    ///
+    /// This function will yield errors whenever `read_line` would have also
+    /// yielded an error.
+    fn lines(self) -> Lines<Self> {
+        Lines { buf: self }
+    }
+}
+
+impl<T: BufferedRead> BufferedReadExt for T {}
+
+fn read_until<R: BufferedRead + ?Sized>(r: &mut R, delim: u8,
+                                        buf: &mut Vec<u8>) -> Result<usize> {
+    let mut read = 0;
+    loop {
+        let (done, used) = {
+            let available = match r.fill_buf() {
+                Ok(n) => n,
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e)
+            };
+            match available.iter().position(|b| *b == delim) {
+                Some(i) => {
+                    buf.push_all(&available[..i + 1]);
+                    (true, i + 1)
+                }
+                None => {
+                    buf.push_all(available);
+                    (false, available.len())
+                }
+            }
+        };
+        r.consume(used);
+        read += used;
+        if done || used == 0 {
+            return Ok(read);
+        }
+    }
+}
+
+// Appends the bytes produced by `f` onto `buf`, validating that they're valid
+// UTF-8. If validation fails then `buf` is restored to its original length so
+// it never contains invalid UTF-8, even if `f` panics.
+fn append_to_string<F>(buf: &mut String, f: F) -> Result<usize>
+    where F: FnOnce(&mut Vec<u8>) -> Result<usize>
+{
+    struct Guard<'a> { s: &'a mut Vec<u8>, len: usize }
+    #[unsafe_destructor]
+    impl<'a> Drop for Guard<'a> {
+        fn drop(&mut self) {
+            unsafe { self.s.set_len(self.len); }
+        }
+    }
+
+    unsafe {
+        let mut g = Guard { len: buf.len(), s: buf.as_mut_vec() };
+        let ret = f(g.s);
+        if str::from_utf8(&g.s[g.len..]).is_err() {
+            ret.and_then(|_| {
+                Err(Error::new(ErrorKind::InvalidInput,
+                               "stream did not contain valid UTF-8", None))
+            })
+        } else {
+            g.len = g.s.len();
+            ret
+        }
+    }
+}
+
 /// A `Write` adaptor which will write data to multiple locations.
 ///
 /// For more information, see `WriteExt::broadcast`.
@@ -424,6 +645,21 @@ impl<T: Write, U: Write> Write for Broadcast<T, U> {
         Ok(n)
     }
 
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> Result<usize> {
+        let n = try!(self.first.write_vectored(bufs));
+        // TODO: what if the write fails? (we wrote something)
+        let mut left = n;
+        let mut written = Vec::new();
+        for buf in bufs.iter() {
+            if left == 0 { break }
+            let amt = cmp::min(left, buf.len());
+            written.push(&buf[..amt]);
+            left -= amt;
+        }
+        try!(self.second.write_all_vectored(&written));
+        Ok(n)
+    }
+
     fn flush(&mut self) -> Result<()> {
         self.first.flush().and(self.second.flush())
     }
@@ -448,6 +684,18 @@ impl<T: Read, U: Read> Read for Chain<T, U> {
         }
         self.second.read(buf)
     }
+
+    fn read_vectored(&mut self, bufs: &mut [&mut [u8]]) -> Result<usize> {
+        if !self.done_first {
+            match try!(self.first.read_vectored(bufs)) {
+                0 if bufs.iter().any(|b| !b.is_empty()) => {
+                    self.done_first = true;
+                }
+                n => return Ok(n),
+            }
+        }
+        self.second.read_vectored(bufs)
+    }
 }
 
 /// Reader adaptor which limits the bytes read from an underlying reader.
@@ -465,6 +713,24 @@ impl<T: Read> Read for Take<T> {
         self.limit -= n as u64;
         Ok(n)
     }
+
+    fn read_vectored(&mut self, bufs: &mut [&mut [u8]]) -> Result<usize> {
+        let n = {
+            // Only hand down as many buffers (and bytes of the last one) as
+            // fit in the remaining limit.
+            let mut left = self.limit;
+            let mut limited = Vec::new();
+            for buf in bufs.iter_mut() {
+                if left == 0 { break }
+                let amt = cmp::min(left, buf.len() as u64) as usize;
+                limited.push(&mut buf[..amt]);
+                left -= amt as u64;
+            }
+            try!(self.inner.read_vectored(&mut limited))
+        };
+        self.limit -= n as u64;
+        Ok(n)
+    }
 }
 
 /// An adaptor which will emit all read data to a specified writer as well.
@@ -580,3 +846,59 @@ impl fmt::Display for CharsError {
         }
     }
 }
+
+/// An iterator over the contents of an instance of `BufferedRead` split on a
+/// particular byte.
+///
+/// See `BufferedReadExt::split` for more information.
+pub struct Split<B> {
+    buf: B,
+    delim: u8,
+}
+
+impl<B: BufferedRead> Iterator for Split<B> {
+    type Item = Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Result<Vec<u8>>> {
+        let mut buf = Vec::new();
+        match read_until(&mut self.buf, self.delim, &mut buf) {
+            Ok(0) => None,
+            Ok(..) => {
+                if buf[buf.len() - 1] == self.delim {
+                    buf.pop();
+                }
+                Some(Ok(buf))
+            }
+            Err(e) => Some(Err(e))
+        }
+    }
+}
+
+/// An iterator over the lines of an instance of `BufferedRead` split on a
+/// newline byte.
+///
+/// See `BufferedReadExt::lines` for more information.
+pub struct Lines<B> {
+    buf: B,
+}
+
+impl<B: BufferedRead> Iterator for Lines<B> {
+    type Item = Result<String>;
+
+    fn next(&mut self) -> Option<Result<String>> {
+        let mut buf = String::new();
+        match self.buf.read_line(&mut buf) {
+            Ok(0) => None,
+            Ok(..) => {
+                if buf.ends_with("\n") {
+                    buf.pop();
+                    if buf.ends_with("\r") {
+                        buf.pop();
+                    }
+                }
+                Some(Ok(buf))
+            }
+            Err(e) => Some(Err(e))
+        }
+    }
+}
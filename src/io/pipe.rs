@@ -0,0 +1,128 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Anonymous pipes.
+
+use prelude::v1::*;
+use io::prelude::*;
+
+use io;
+use sys::fd::FileDesc;
+use sys::process::{self, AnonPipe};
+use sys_common::{AsInner, FromInner, IntoInner};
+
+/// The reading end of an anonymous pipe, created by `pipe`.
+pub struct PipeReader(AnonPipe);
+
+/// The writing end of an anonymous pipe, created by `pipe`.
+pub struct PipeWriter(AnonPipe);
+
+/// Creates a new anonymous pipe, returning its reading and writing ends.
+///
+/// Data written to the `PipeWriter` can be read back from the `PipeReader`.
+/// Once every handle to the writing end has been dropped, reads see the end
+/// of the stream; once every handle to the reading end has been dropped,
+/// writes fail with `ErrorKind::BrokenPipe`.
+///
+/// Both ends are close-on-exec, so they are not inherited by child processes
+/// unless explicitly arranged.
+///
+/// # Example
+///
+/// ```
+/// use io2::io::prelude::*;
+/// use io2::io;
+///
+/// let (mut reader, mut writer) = io::pipe().unwrap();
+/// writer.write_all(b"hello").unwrap();
+/// drop(writer);
+///
+/// let mut s = String::new();
+/// reader.read_to_string(&mut s).unwrap();
+/// assert_eq!(s, "hello");
+/// ```
+pub fn pipe() -> io::Result<(PipeReader, PipeWriter)> {
+    let (reader, writer) = try!(unsafe { process::anon_pipe() });
+    Ok((PipeReader(reader), PipeWriter(writer)))
+}
+
+impl PipeReader {
+    /// Creates a new handle to the same reading end of the pipe.
+    ///
+    /// The returned handle is also close-on-exec. The pipe is not closed
+    /// until all handles to it have been dropped.
+    pub fn try_clone(&self) -> io::Result<PipeReader> {
+        self.0.duplicate().map(PipeReader)
+    }
+
+    /// Moves this end of the pipe into or out of non-blocking mode.
+    ///
+    /// While in non-blocking mode, a read with no data available fails with
+    /// an error of kind `ErrorKind::WouldBlock` instead of blocking. The mode
+    /// is shared with all handles created by `try_clone`.
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.0.set_nonblocking(nonblocking)
+    }
+}
+
+impl PipeWriter {
+    /// Creates a new handle to the same writing end of the pipe.
+    ///
+    /// The returned handle is also close-on-exec. The pipe is not closed
+    /// until all handles to it have been dropped.
+    pub fn try_clone(&self) -> io::Result<PipeWriter> {
+        self.0.duplicate().map(PipeWriter)
+    }
+
+    /// Moves this end of the pipe into or out of non-blocking mode.
+    ///
+    /// While in non-blocking mode, a write to a full pipe fails with an
+    /// error of kind `ErrorKind::WouldBlock` instead of blocking. The mode
+    /// is shared with all handles created by `try_clone`.
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.0.set_nonblocking(nonblocking)
+    }
+}
+
+impl Read for PipeReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl Write for PipeWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+}
+
+impl AsInner<FileDesc> for PipeReader {
+    fn as_inner(&self) -> &FileDesc { self.0.fd() }
+}
+
+impl FromInner<FileDesc> for PipeReader {
+    fn from_inner(fd: FileDesc) -> PipeReader { PipeReader(FromInner::from_inner(fd)) }
+}
+
+impl IntoInner<FileDesc> for PipeReader {
+    fn into_inner(self) -> FileDesc { self.0.into_fd() }
+}
+
+impl AsInner<FileDesc> for PipeWriter {
+    fn as_inner(&self) -> &FileDesc { self.0.fd() }
+}
+
+impl FromInner<FileDesc> for PipeWriter {
+    fn from_inner(fd: FileDesc) -> PipeWriter { PipeWriter(FromInner::from_inner(fd)) }
+}
+
+impl IntoInner<FileDesc> for PipeWriter {
+    fn into_inner(self) -> FileDesc { self.0.into_fd() }
+}
@@ -0,0 +1,196 @@
+// Copyright 2015 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Readiness notifications for many I/O objects at once, built on `epoll`.
+
+use prelude::v1::*;
+
+use fmt;
+use io;
+use os::unix::AsRawFd;
+use sys::poll as imp;
+use time::Duration;
+
+/// A queue of readiness notifications for a set of registered I/O objects.
+///
+/// Objects are registered along with a `Token` of the caller's choosing and
+/// the kind of readiness they are interested in. A call to `poll` then blocks
+/// until at least one of them is ready, and reports which ones are by their
+/// tokens.
+///
+/// Registered objects are best placed in non-blocking mode so that acting on
+/// a spurious or stale notification cannot block the thread.
+pub struct Poll(imp::Poll);
+
+/// An identifier associated with a registered I/O object and handed back in
+/// each `Event` for it.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Token(pub usize);
+
+/// The kind of readiness an I/O object is registered for.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Interest {
+    /// Notify when the object is readable, or the peer hung up.
+    Readable,
+    /// Notify when the object is writable.
+    Writable,
+    /// Notify when the object is either readable or writable.
+    Both,
+}
+
+/// How often readiness of a registered object is reported.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Trigger {
+    /// Report the object on every call to `poll` for as long as it is ready.
+    Level,
+    /// Report the object only when it becomes ready. After a notification it
+    /// is not reported again until it has been drained (e.g. a read returned
+    /// `ErrorKind::WouldBlock`) and becomes ready once more.
+    Edge,
+}
+
+/// A buffer of events filled in by `Poll::poll`.
+pub struct Events(imp::Events);
+
+/// An iterator over the events in an `Events` buffer.
+pub struct EventsIter<'a> {
+    events: &'a Events,
+    pos: usize,
+}
+
+/// A readiness notification for a registered I/O object.
+#[derive(Copy, Clone)]
+pub struct Event(imp::Event);
+
+impl Poll {
+    /// Creates a new queue with nothing registered.
+    pub fn new() -> io::Result<Poll> {
+        imp::Poll::new().map(Poll)
+    }
+
+    /// Starts watching `io` for the readiness described by `interest`.
+    ///
+    /// Any object with a file descriptor can be registered, including ones
+    /// from outside this crate.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `io` is already registered with this queue.
+    pub fn register<E>(&self, io: &E, token: Token, interest: Interest,
+                       trigger: Trigger) -> io::Result<()>
+        where E: AsRawFd
+    {
+        self.0.register(io.as_raw_fd(), token.0, interest, trigger)
+    }
+
+    /// Changes the token, interest or trigger of an already registered `io`.
+    pub fn reregister<E>(&self, io: &E, token: Token, interest: Interest,
+                         trigger: Trigger) -> io::Result<()>
+        where E: AsRawFd
+    {
+        self.0.reregister(io.as_raw_fd(), token.0, interest, trigger)
+    }
+
+    /// Stops watching `io`.
+    ///
+    /// Objects are also removed from every queue automatically when they are
+    /// closed, unless the underlying descriptor was duplicated.
+    pub fn deregister<E>(&self, io: &E) -> io::Result<()>
+        where E: AsRawFd
+    {
+        self.0.deregister(io.as_raw_fd())
+    }
+
+    /// Waits for registered objects to become ready, filling `events` with
+    /// notifications for them.
+    ///
+    /// At most `events.capacity()` notifications are returned at once. A
+    /// `timeout` of `None` waits forever, otherwise this returns after at
+    /// most `timeout` even if nothing became ready. The number of events
+    /// received is returned, which may be zero on a timeout or if a signal
+    /// interrupted the wait.
+    pub fn poll(&self, events: &mut Events,
+                timeout: Option<Duration>) -> io::Result<usize> {
+        self.0.wait(&mut events.0, timeout)
+    }
+}
+
+impl Events {
+    /// Creates a buffer which can hold up to `capacity` events.
+    ///
+    /// A `capacity` of zero is rounded up to one, as `poll` needs room for at
+    /// least one event.
+    pub fn with_capacity(capacity: usize) -> Events {
+        Events(imp::Events::with_capacity(capacity))
+    }
+
+    /// Returns the maximum number of events this buffer can hold.
+    pub fn capacity(&self) -> usize { self.0.capacity() }
+
+    /// Returns the number of events received by the last call to `poll`.
+    pub fn len(&self) -> usize { self.0.len() }
+
+    /// Returns whether the last call to `poll` received no events.
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    /// Returns the `idx`th event received, if any.
+    pub fn get(&self, idx: usize) -> Option<Event> {
+        self.0.get(idx).map(Event)
+    }
+
+    /// Returns an iterator over the received events.
+    pub fn iter(&self) -> EventsIter {
+        EventsIter { events: self, pos: 0 }
+    }
+}
+
+impl<'a> Iterator for EventsIter<'a> {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Event> {
+        let ret = self.events.get(self.pos);
+        if ret.is_some() {
+            self.pos += 1;
+        }
+        ret
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.events.len() - self.pos;
+        (remaining, Some(remaining))
+    }
+}
+
+impl Event {
+    /// Returns the token the object was registered with.
+    pub fn token(&self) -> Token { Token(self.0.token()) }
+
+    /// Returns whether the object is readable.
+    pub fn is_readable(&self) -> bool { self.0.is_readable() }
+
+    /// Returns whether the object is writable.
+    pub fn is_writable(&self) -> bool { self.0.is_writable() }
+
+    /// Returns whether the peer hung up, in which case a read will see the
+    /// end of the stream.
+    pub fn is_hangup(&self) -> bool { self.0.is_hangup() }
+
+    /// Returns whether an error is pending on the object.
+    pub fn is_error(&self) -> bool { self.0.is_error() }
+}
+
+impl fmt::Debug for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Event {{ token: {:?}, readable: {}, writable: {}, \
+                   hangup: {}, error: {} }}",
+               self.token(), self.is_readable(), self.is_writable(),
+               self.is_hangup(), self.is_error())
+    }
+}
@@ -21,6 +21,6 @@
 //! `Write`, `ReadExt`, and `WriteExt`. Structures and functions are not
 //! contained in this module.
 
-pub use super::{Read, Write, ReadExt, WriteExt, BufferedRead};
+pub use super::{Read, Write, ReadExt, WriteExt, BufferedRead, BufferedReadExt};
 pub use super::Seek as NewSeek;
 pub use fs::PathExt;
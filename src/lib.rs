@@ -1,6 +1,6 @@
-#![feature(libc, std_misc, core, path, unicode, collections, hash)]
+#![feature(libc, std_misc, core, path, unicode, collections, hash, rand)]
 #![no_std]
-#![feature(asm)]
+#![feature(asm, unsafe_destructor)]
 
 #[macro_use]
 extern crate std;
@@ -10,7 +10,7 @@ extern crate unicode;
 
 pub use std::{slice, ptr, cmp, vec, iter, marker, mem, str, collections, path};
 pub use std::{string, prelude, result, option, boxed, clone, error, fmt};
-pub use std::{num, rc, sync, rt, hash, ops, ffi, usize};
+pub use std::{num, rc, sync, rt, hash, ops, ffi, usize, cell, thread};
 
 mod borrow {
     use marker::Sized;
@@ -21,9 +21,13 @@ mod borrow {
 
 pub mod io;
 pub mod fs;
-// pub mod env;
+pub mod memory_map;
+pub mod env;
 pub mod os;
 pub mod net;
+#[cfg(unix)] pub mod process;
+pub mod time;
+#[cfg(unix)] pub mod terminal;
 
 #[cfg(unix)]    #[path = "sys/unix/mod.rs"]    mod sys;
 #[cfg(windows)] #[path = "sys/windows/mod.rs"] mod sys;
@@ -1,385 +1,178 @@
+// Copyright 2015 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
 
-// /// A memory mapped file or chunk of memory. This is a very system-specific
-// /// interface to the OS's memory mapping facilities (`mmap` on POSIX,
-// /// `VirtualAlloc`/`CreateFileMapping` on Windows). It makes no attempt at
-// /// abstracting platform differences, besides in error values returned. Consider
-// /// yourself warned.
-// ///
-// /// The memory map is released (unmapped) when the destructor is run, so don't
-// /// let it leave scope by accident if you want it to stick around.
-// #[allow(missing_copy_implementations)]
-// pub struct MemoryMap {
-//     data: *mut u8,
-//     len: uint,
-//     kind: MemoryMapKind,
-// }
-//
-// /// Type of memory map
-// pub enum MemoryMapKind {
-//     /// Virtual memory map. Usually used to change the permissions of a given
-//     /// chunk of memory.  Corresponds to `VirtualAlloc` on Windows.
-//     MapFile(*const u8),
-//     /// Virtual memory map. Usually used to change the permissions of a given
-//     /// chunk of memory, or for allocation. Corresponds to `VirtualAlloc` on
-//     /// Windows.
-//     MapVirtual
-// }
-//
-// impl Copy for MemoryMapKind {}
-//
-// /// Options the memory map is created with
-// pub enum MapOption {
-//     /// The memory should be readable
-//     MapReadable,
-//     /// The memory should be writable
-//     MapWritable,
-//     /// The memory should be executable
-//     MapExecutable,
-//     /// Create a map for a specific address range. Corresponds to `MAP_FIXED` on
-//     /// POSIX.
-//     MapAddr(*const u8),
-//     /// Create a memory mapping for a file with a given HANDLE.
-//     #[cfg(windows)]
-//     MapFd(libc::HANDLE),
-//     /// Create a memory mapping for a file with a given fd.
-//     #[cfg(not(windows))]
-//     MapFd(c_int),
-//     /// When using `MapFd`, the start of the map is `uint` bytes from the start
-//     /// of the file.
-//     MapOffset(uint),
-//     /// On POSIX, this can be used to specify the default flags passed to
-//     /// `mmap`. By default it uses `MAP_PRIVATE` and, if not using `MapFd`,
-//     /// `MAP_ANON`. This will override both of those. This is platform-specific
-//     /// (the exact values used) and ignored on Windows.
-//     MapNonStandardFlags(c_int),
-// }
-//
-// impl Copy for MapOption {}
-//
-// /// Possible errors when creating a map.
-// #[derive(Copy, Show)]
-// pub enum MapError {
-//     /// # The following are POSIX-specific
-//     ///
-//     /// fd was not open for reading or, if using `MapWritable`, was not open for
-//     /// writing.
-//     ErrFdNotAvail,
-//     /// fd was not valid
-//     ErrInvalidFd,
-//     /// Either the address given by `MapAddr` or offset given by `MapOffset` was
-//     /// not a multiple of `MemoryMap::granularity` (unaligned to page size).
-//     ErrUnaligned,
-//     /// With `MapFd`, the fd does not support mapping.
-//     ErrNoMapSupport,
-//     /// If using `MapAddr`, the address + `min_len` was outside of the process's
-//     /// address space. If using `MapFd`, the target of the fd didn't have enough
-//     /// resources to fulfill the request.
-//     ErrNoMem,
-//     /// A zero-length map was requested. This is invalid according to
-//     /// [POSIX](http://pubs.opengroup.org/onlinepubs/9699919799/functions/mmap.html).
-//     /// Not all platforms obey this, but this wrapper does.
-//     ErrZeroLength,
-//     /// Unrecognized error. The inner value is the unrecognized errno.
-//     ErrUnknown(int),
-//     /// # The following are Windows-specific
-//     ///
-//     /// Unsupported combination of protection flags
-//     /// (`MapReadable`/`MapWritable`/`MapExecutable`).
-//     ErrUnsupProt,
-//     /// When using `MapFd`, `MapOffset` was given (Windows does not support this
-//     /// at all)
-//     ErrUnsupOffset,
-//     /// When using `MapFd`, there was already a mapping to the file.
-//     ErrAlreadyExists,
-//     /// Unrecognized error from `VirtualAlloc`. The inner value is the return
-//     /// value of GetLastError.
-//     ErrVirtualAlloc(uint),
-//     /// Unrecognized error from `CreateFileMapping`. The inner value is the
-//     /// return value of `GetLastError`.
-//     ErrCreateFileMappingW(uint),
-//     /// Unrecognized error from `MapViewOfFile`. The inner value is the return
-//     /// value of `GetLastError`.
-//     ErrMapViewOfFile(uint)
-// }
-//
-// #[stable]
-// impl fmt::Display for MapError {
-//     fn fmt(&self, out: &mut fmt::Formatter) -> fmt::Result {
-//         let str = match *self {
-//             ErrFdNotAvail => "fd not available for reading or writing",
-//             ErrInvalidFd => "Invalid fd",
-//             ErrUnaligned => {
-//                 "Unaligned address, invalid flags, negative length or \
-//                  unaligned offset"
-//             }
-//             ErrNoMapSupport=> "File doesn't support mapping",
-//             ErrNoMem => "Invalid address, or not enough available memory",
-//             ErrUnsupProt => "Protection mode unsupported",
-//             ErrUnsupOffset => "Offset in virtual memory mode is unsupported",
-//             ErrAlreadyExists => "File mapping for specified file already exists",
-//             ErrZeroLength => "Zero-length mapping not allowed",
-//             ErrUnknown(code) => {
-//                 return write!(out, "Unknown error = {}", code)
-//             },
-//             ErrVirtualAlloc(code) => {
-//                 return write!(out, "VirtualAlloc failure = {}", code)
-//             },
-//             ErrCreateFileMappingW(code) => {
-//                 return write!(out, "CreateFileMappingW failure = {}", code)
-//             },
-//             ErrMapViewOfFile(code) => {
-//                 return write!(out, "MapViewOfFile failure = {}", code)
-//             }
-//         };
-//         write!(out, "{}", str)
-//     }
-// }
-//
-// impl Error for MapError {
-//     fn description(&self) -> &str { "memory map error" }
-// }
-//
-// // Round up `from` to be divisible by `to`
-// fn round_up(from: uint, to: uint) -> uint {
-//     let r = if from % to == 0 {
-//         from
-//     } else {
-//         from + to - (from % to)
-//     };
-//     if r == 0 {
-//         to
-//     } else {
-//         r
-//     }
-// }
-//
-// #[cfg(unix)]
-// impl MemoryMap {
-//     /// Create a new mapping with the given `options`, at least `min_len` bytes
-//     /// long. `min_len` must be greater than zero; see the note on
-//     /// `ErrZeroLength`.
-//     pub fn new(min_len: uint, options: &[MapOption]) -> Result<MemoryMap, MapError> {
-//         use libc::off_t;
-//
-//         if min_len == 0 {
-//             return Err(ErrZeroLength)
-//         }
-//         let mut addr: *const u8 = ptr::null();
-//         let mut prot = 0;
-//         let mut flags = libc::MAP_PRIVATE;
-//         let mut fd = -1;
-//         let mut offset = 0;
-//         let mut custom_flags = false;
-//         let len = round_up(min_len, page_size());
-//
-//         for &o in options.iter() {
-//             match o {
-//                 MapReadable => { prot |= libc::PROT_READ; },
-//                 MapWritable => { prot |= libc::PROT_WRITE; },
-//                 MapExecutable => { prot |= libc::PROT_EXEC; },
-//                 MapAddr(addr_) => {
-//                     flags |= libc::MAP_FIXED;
-//                     addr = addr_;
-//                 },
-//                 MapFd(fd_) => {
-//                     flags |= libc::MAP_FILE;
-//                     fd = fd_;
-//                 },
-//                 MapOffset(offset_) => { offset = offset_ as off_t; },
-//                 MapNonStandardFlags(f) => { custom_flags = true; flags = f },
-//             }
-//         }
-//         if fd == -1 && !custom_flags { flags |= libc::MAP_ANON; }
-//
-//         let r = unsafe {
-//             libc::mmap(addr as *mut c_void, len as libc::size_t, prot, flags,
-//                        fd, offset)
-//         };
-//         if r == libc::MAP_FAILED {
-//             Err(match errno() as c_int {
-//                 libc::EACCES => ErrFdNotAvail,
-//                 libc::EBADF => ErrInvalidFd,
-//                 libc::EINVAL => ErrUnaligned,
-//                 libc::ENODEV => ErrNoMapSupport,
-//                 libc::ENOMEM => ErrNoMem,
-//                 code => ErrUnknown(code as int)
-//             })
-//         } else {
-//             Ok(MemoryMap {
-//                data: r as *mut u8,
-//                len: len,
-//                kind: if fd == -1 {
-//                    MapVirtual
-//                } else {
-//                    MapFile(ptr::null())
-//                }
-//             })
-//         }
-//     }
-//
-//     /// Granularity that the offset or address must be for `MapOffset` and
-//     /// `MapAddr` respectively.
-//     pub fn granularity() -> uint {
-//         page_size()
-//     }
-// }
-//
-// #[cfg(unix)]
-// impl Drop for MemoryMap {
-//     /// Unmap the mapping. Panics the task if `munmap` panics.
-//     fn drop(&mut self) {
-//         if self.len == 0 { /* workaround for dummy_stack */ return; }
-//
-//         unsafe {
-//             // `munmap` only panics due to logic errors
-//             libc::munmap(self.data as *mut c_void, self.len as libc::size_t);
-//         }
-//     }
-// }
-//
-// #[cfg(windows)]
-// impl MemoryMap {
-//     /// Create a new mapping with the given `options`, at least `min_len` bytes long.
-//     pub fn new(min_len: uint, options: &[MapOption]) -> Result<MemoryMap, MapError> {
-//         use libc::types::os::arch::extra::{LPVOID, DWORD, SIZE_T, HANDLE};
-//
-//         let mut lpAddress: LPVOID = ptr::null_mut();
-//         let mut readable = false;
-//         let mut writable = false;
-//         let mut executable = false;
-//         let mut handle: HANDLE = libc::INVALID_HANDLE_VALUE;
-//         let mut offset: uint = 0;
-//         let len = round_up(min_len, page_size());
-//
-//         for &o in options.iter() {
-//             match o {
-//                 MapReadable => { readable = true; },
-//                 MapWritable => { writable = true; },
-//                 MapExecutable => { executable = true; }
-//                 MapAddr(addr_) => { lpAddress = addr_ as LPVOID; },
-//                 MapFd(handle_) => { handle = handle_; },
-//                 MapOffset(offset_) => { offset = offset_; },
-//                 MapNonStandardFlags(..) => {}
-//             }
-//         }
-//
-//         let flProtect = match (executable, readable, writable) {
-//             (false, false, false) if handle == libc::INVALID_HANDLE_VALUE => libc::PAGE_NOACCESS,
-//             (false, true, false) => libc::PAGE_READONLY,
-//             (false, true, true) => libc::PAGE_READWRITE,
-//             (true, false, false) if handle == libc::INVALID_HANDLE_VALUE => libc::PAGE_EXECUTE,
-//             (true, true, false) => libc::PAGE_EXECUTE_READ,
-//             (true, true, true) => libc::PAGE_EXECUTE_READWRITE,
-//             _ => return Err(ErrUnsupProt)
-//         };
-//
-//         if handle == libc::INVALID_HANDLE_VALUE {
-//             if offset != 0 {
-//                 return Err(ErrUnsupOffset);
-//             }
-//             let r = unsafe {
-//                 libc::VirtualAlloc(lpAddress,
-//                                    len as SIZE_T,
-//                                    libc::MEM_COMMIT | libc::MEM_RESERVE,
-//                                    flProtect)
-//             };
-//             match r as uint {
-//                 0 => Err(ErrVirtualAlloc(errno())),
-//                 _ => Ok(MemoryMap {
-//                    data: r as *mut u8,
-//                    len: len,
-//                    kind: MapVirtual
-//                 })
-//             }
-//         } else {
-//             let dwDesiredAccess = match (executable, readable, writable) {
-//                 (false, true, false) => libc::FILE_MAP_READ,
-//                 (false, true, true) => libc::FILE_MAP_WRITE,
-//                 (true, true, false) => libc::FILE_MAP_READ | libc::FILE_MAP_EXECUTE,
-//                 (true, true, true) => libc::FILE_MAP_WRITE | libc::FILE_MAP_EXECUTE,
-//                 _ => return Err(ErrUnsupProt) // Actually, because of the check above,
-//                                               // we should never get here.
-//             };
-//             unsafe {
-//                 let hFile = handle;
-//                 let mapping = libc::CreateFileMappingW(hFile,
-//                                                        ptr::null_mut(),
-//                                                        flProtect,
-//                                                        0,
-//                                                        0,
-//                                                        ptr::null());
-//                 if mapping == ptr::null_mut() {
-//                     return Err(ErrCreateFileMappingW(errno()));
-//                 }
-//                 if errno() as c_int == libc::ERROR_ALREADY_EXISTS {
-//                     return Err(ErrAlreadyExists);
-//                 }
-//                 let r = libc::MapViewOfFile(mapping,
-//                                             dwDesiredAccess,
-//                                             ((len as u64) >> 32) as DWORD,
-//                                             (offset & 0xffff_ffff) as DWORD,
-//                                             0);
-//                 match r as uint {
-//                     0 => Err(ErrMapViewOfFile(errno())),
-//                     _ => Ok(MemoryMap {
-//                        data: r as *mut u8,
-//                        len: len,
-//                        kind: MapFile(mapping as *const u8)
-//                     })
-//                 }
-//             }
-//         }
-//     }
-//
-//     /// Granularity of MapAddr() and MapOffset() parameter values.
-//     /// This may be greater than the value returned by page_size().
-//     pub fn granularity() -> uint {
-//         use mem;
-//         unsafe {
-//             let mut info = mem::zeroed();
-//             libc::GetSystemInfo(&mut info);
-//
-//             return info.dwAllocationGranularity as uint;
-//         }
-//     }
-// }
-//
-// #[cfg(windows)]
-// impl Drop for MemoryMap {
-//     /// Unmap the mapping. Panics the task if any of `VirtualFree`,
-//     /// `UnmapViewOfFile`, or `CloseHandle` fail.
-//     fn drop(&mut self) {
-//         use libc::types::os::arch::extra::{LPCVOID, HANDLE};
-//         use libc::consts::os::extra::FALSE;
-//         if self.len == 0 { return }
-//
-//         unsafe {
-//             match self.kind {
-//                 MapVirtual => {
-//                     if libc::VirtualFree(self.data as *mut c_void, 0,
-//                                          libc::MEM_RELEASE) == 0 {
-//                         println!("VirtualFree failed: {}", errno());
-//                     }
-//                 },
-//                 MapFile(mapping) => {
-//                     if libc::UnmapViewOfFile(self.data as LPCVOID) == FALSE {
-//                         println!("UnmapViewOfFile failed: {}", errno());
-//                     }
-//                     if libc::CloseHandle(mapping as HANDLE) == FALSE {
-//                         println!("CloseHandle failed: {}", errno());
-//                     }
-//                 }
-//             }
-//         }
-//     }
-// }
-//
-// impl MemoryMap {
-//     /// Returns the pointer to the memory created or modified by this map.
-//     pub fn data(&self) -> *mut u8 { self.data }
-//     /// Returns the number of bytes this map applies to.
-//     pub fn len(&self) -> uint { self.len }
-//     /// Returns the type of mapping this represents.
-//     pub fn kind(&self) -> MemoryMapKind { self.kind }
-// }
+//! Memory mapped files
+//!
+//! A `MemoryMap` maps a range of an open `File` into the address space of the
+//! process (`mmap` on POSIX, `CreateFileMapping`/`MapViewOfFile` on Windows),
+//! and then acts as a byte slice of that range. Maps which are not read-only
+//! can also be written through `as_mut_slice`.
+//!
+//! Note that the contents of a map are only as stable as the file behind it:
+//! if the file is truncated or modified by another process while it is mapped
+//! then reads may observe the change or, if the mapped range no longer exists,
+//! fault.
+
+use prelude::v1::*;
+
+use fmt;
+use fs::File;
+use io::{self, Error, ErrorKind};
+use mem;
+use ops::Deref;
+use slice;
+use sys::memory_map as imp;
+use sys_common::AsInner;
+use usize;
+
+/// The access a memory map is created with.
+///
+/// The file being mapped must have been opened with at least the same access,
+/// e.g. a `ReadWrite` map requires a file opened for both reading and
+/// writing.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Protection {
+    /// The map can only be read from.
+    Read,
+    /// The map can be read from and written to, and writes are carried
+    /// through to the underlying file.
+    ReadWrite,
+    /// The map can be read from and written to, but writes are private to the
+    /// map and never reach the underlying file (copy-on-write).
+    ReadCopy,
+}
+
+/// A memory mapped range of a file.
+///
+/// The range is unmapped when the `MemoryMap` is dropped.
+pub struct MemoryMap {
+    inner: imp::MemoryMap,
+    // distance from the aligned start of `inner` to the requested offset
+    offset: usize,
+    len: usize,
+    prot: Protection,
+}
+
+impl MemoryMap {
+    /// Maps the entire contents of `file` with the given protection.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file is empty, as zero-length maps are not
+    /// allowed.
+    pub fn open(file: &File, prot: Protection) -> io::Result<MemoryMap> {
+        let size = try!(file.file_attr()).size();
+        if size > usize::MAX as u64 {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                  "file is too large to map", None))
+        }
+        MemoryMap::open_range(file, prot, 0, size as usize)
+    }
+
+    /// Maps `len` bytes of `file` starting at byte `offset`.
+    ///
+    /// The offset does not need to be aligned to any particular boundary.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `len` is zero or if the range extends past the end
+    /// of the file.
+    pub fn open_range(file: &File, prot: Protection, offset: u64, len: usize)
+                      -> io::Result<MemoryMap> {
+        if len == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                  "memory map must have a non-zero length",
+                                  None))
+        }
+        let size = try!(file.file_attr()).size();
+        if offset > size || (len as u64) > size - offset {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                  "memory map extends past the end of the file",
+                                  None))
+        }
+
+        // The OS requires the offset to be a multiple of the granularity, so
+        // map a little extra at the front and hide it.
+        let alignment = (offset % imp::MemoryMap::granularity() as u64) as usize;
+        let inner = try!(imp::MemoryMap::new(file.as_inner(), prot,
+                                             offset - alignment as u64,
+                                             len + alignment));
+        Ok(MemoryMap {
+            inner: inner,
+            offset: alignment,
+            len: len,
+            prot: prot,
+        })
+    }
+
+    /// Returns the number of bytes in this map.
+    pub fn len(&self) -> usize { self.len }
+
+    /// Returns the protection this map was created with.
+    pub fn protection(&self) -> Protection { self.prot }
+
+    /// Returns the contents of the map for writing.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the map was created with `Protection::Read`.
+    pub fn as_mut_slice(&mut self) -> io::Result<&mut [u8]> {
+        if self.prot == Protection::Read {
+            return Err(Error::new(ErrorKind::PermissionDenied,
+                                  "cannot write to a read-only memory map",
+                                  None))
+        }
+        unsafe {
+            let mut ptr = self.inner.ptr().offset(self.offset as isize);
+            Ok(slice::from_raw_mut_buf(mem::copy_mut_lifetime(self, &mut ptr),
+                                       self.len))
+        }
+    }
+
+    /// Synchronously writes any modifications of the map back to the file.
+    ///
+    /// For `Read` and `ReadCopy` maps this is a no-op as there are no
+    /// modifications to write back.
+    pub fn flush(&self) -> io::Result<()> {
+        self.flush_range(0, self.len)
+    }
+
+    /// Synchronously writes modifications of `len` bytes of the map starting
+    /// at `offset` back to the file.
+    pub fn flush_range(&self, offset: usize, len: usize) -> io::Result<()> {
+        if offset > self.len || len > self.len - offset {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                  "flush range is outside of the memory map",
+                                  None))
+        }
+        if self.prot != Protection::ReadWrite {
+            return Ok(())
+        }
+        self.inner.flush(self.offset + offset, len)
+    }
+}
+
+impl Deref for MemoryMap {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe {
+            let ptr = self.inner.ptr().offset(self.offset as isize) as *const u8;
+            slice::from_raw_buf(mem::copy_lifetime(self, &ptr), self.len)
+        }
+    }
+}
 
+impl fmt::Debug for MemoryMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "MemoryMap {{ len: {}, protection: {:?} }}",
+               self.len, self.prot)
+    }
+}
@@ -13,6 +13,7 @@ mod addr;
 mod tcp;
 mod udp;
 mod parser;
+#[cfg(unix)] pub mod unix;
 
 #[derive(Copy, Clone, PartialEq)]
 pub enum Shutdown {
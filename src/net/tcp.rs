@@ -3,7 +3,11 @@ use io::prelude::*;
 
 use io;
 use net::{ToSocketAddrs, SocketAddr, Shutdown};
+#[cfg(unix)] use sys::fd::FileDesc;
 use sys_common::net as net_imp;
+#[cfg(unix)] use sys::net::Socket;
+#[cfg(unix)] use sys_common::{AsInner, FromInner, IntoInner};
+use time::Duration;
 
 pub struct TcpStream(net_imp::TcpStream);
 pub struct TcpListener(net_imp::TcpListener);
@@ -14,6 +18,11 @@ impl TcpStream {
         super::each_addr(addr, net_imp::TcpStream::connect).map(TcpStream)
     }
 
+    pub fn connect_timeout(addr: &SocketAddr,
+                           timeout: Duration) -> io::Result<TcpStream> {
+        net_imp::TcpStream::connect_timeout(addr, timeout).map(TcpStream)
+    }
+
     pub fn peer_addr(&self) -> io::Result<SocketAddr> {
         self.0.peer_addr()
     }
@@ -37,19 +46,69 @@ impl TcpStream {
     pub fn set_keepalive(&self, seconds: Option<u32>) -> io::Result<()> {
         self.0.set_keepalive(seconds)
     }
+
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_read_timeout(dur)
+    }
+
+    pub fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_write_timeout(dur)
+    }
+
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.read_timeout()
+    }
+
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.write_timeout()
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.0.set_nonblocking(nonblocking)
+    }
+}
+
+#[cfg(unix)]
+impl AsInner<FileDesc> for TcpStream {
+    fn as_inner(&self) -> &FileDesc { self.0.socket().fd() }
+}
+
+#[cfg(unix)]
+impl FromInner<FileDesc> for TcpStream {
+    fn from_inner(fd: FileDesc) -> TcpStream {
+        let socket: Socket = FromInner::from_inner(fd);
+        TcpStream(FromInner::from_inner(socket))
+    }
+}
+
+#[cfg(unix)]
+impl IntoInner<FileDesc> for TcpStream {
+    fn into_inner(self) -> FileDesc { self.0.into_socket().into_fd() }
 }
 
 impl Read for TcpStream {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> { self.0.read(buf) }
+    fn read_vectored(&mut self, bufs: &mut [&mut [u8]]) -> io::Result<usize> {
+        self.0.read_vectored(bufs)
+    }
 }
 impl Write for TcpStream {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> { self.0.write(buf) }
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> io::Result<usize> {
+        self.0.write_vectored(bufs)
+    }
 }
 impl<'a> Read for &'a TcpStream {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> { self.0.read(buf) }
+    fn read_vectored(&mut self, bufs: &mut [&mut [u8]]) -> io::Result<usize> {
+        self.0.read_vectored(bufs)
+    }
 }
 impl<'a> Write for &'a TcpStream {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> { self.0.write(buf) }
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> io::Result<usize> {
+        self.0.write_vectored(bufs)
+    }
 }
 
 impl TcpListener {
@@ -72,8 +131,29 @@ impl TcpListener {
     pub fn incoming(&self) -> Incoming {
         Incoming { listener: self }
     }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.0.set_nonblocking(nonblocking)
+    }
+}
+
+#[cfg(unix)]
+impl AsInner<FileDesc> for TcpListener {
+    fn as_inner(&self) -> &FileDesc { self.0.socket().fd() }
 }
 
+#[cfg(unix)]
+impl FromInner<FileDesc> for TcpListener {
+    fn from_inner(fd: FileDesc) -> TcpListener {
+        let socket: Socket = FromInner::from_inner(fd);
+        TcpListener(FromInner::from_inner(socket))
+    }
+}
+
+#[cfg(unix)]
+impl IntoInner<FileDesc> for TcpListener {
+    fn into_inner(self) -> FileDesc { self.0.into_socket().into_fd() }
+}
 
 impl<'a> Iterator for Incoming<'a> {
     type Item = io::Result<TcpStream>;
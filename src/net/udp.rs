@@ -2,7 +2,10 @@ use prelude::v1::*;
 
 use io::{self, Error, ErrorKind};
 use net::{ToSocketAddrs, SocketAddr, IpAddr};
+#[cfg(unix)] use sys::fd::FileDesc;
 use sys_common::net as net_imp;
+#[cfg(unix)] use sys::net::Socket;
+#[cfg(unix)] use sys_common::{AsInner, FromInner, IntoInner};
 
 pub struct UdpSocket(net_imp::UdpSocket);
 
@@ -56,4 +59,26 @@ impl UdpSocket {
     pub fn time_to_live(&self, ttl: i32) -> io::Result<()> {
         self.0.time_to_live(ttl)
     }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.0.set_nonblocking(nonblocking)
+    }
+}
+
+#[cfg(unix)]
+impl AsInner<FileDesc> for UdpSocket {
+    fn as_inner(&self) -> &FileDesc { self.0.socket().fd() }
+}
+
+#[cfg(unix)]
+impl FromInner<FileDesc> for UdpSocket {
+    fn from_inner(fd: FileDesc) -> UdpSocket {
+        let socket: Socket = FromInner::from_inner(fd);
+        UdpSocket(FromInner::from_inner(socket))
+    }
+}
+
+#[cfg(unix)]
+impl IntoInner<FileDesc> for UdpSocket {
+    fn into_inner(self) -> FileDesc { self.0.into_socket().into_fd() }
 }
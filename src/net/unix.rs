@@ -0,0 +1,263 @@
+use prelude::v1::*;
+use io::prelude::*;
+
+use fmt;
+use io;
+use net::Shutdown;
+use path::{Path, GenericPath};
+use sys::fd::FileDesc;
+use sys::pipe as pipe_imp;
+use sys::net::Socket;
+use sys_common::{AsInner, FromInner, IntoInner};
+
+pub struct SocketAddr(pipe_imp::SocketAddr);
+pub struct UnixStream(pipe_imp::UnixStream);
+pub struct UnixListener(pipe_imp::UnixListener);
+pub struct UnixDatagram(pipe_imp::UnixDatagram);
+pub struct Incoming<'a> { listener: &'a UnixListener }
+
+impl SocketAddr {
+    pub fn from_pathname(path: &Path) -> io::Result<SocketAddr> {
+        pipe_imp::SocketAddr::from_pathname(path.as_vec()).map(SocketAddr)
+    }
+
+    /// Creates an address in the linux abstract socket namespace.
+    ///
+    /// Abstract names are not visible in the filesystem and may contain
+    /// arbitrary bytes, including nul bytes.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn from_abstract_name(name: &[u8]) -> io::Result<SocketAddr> {
+        pipe_imp::SocketAddr::from_abstract(name).map(SocketAddr)
+    }
+
+    pub fn is_unnamed(&self) -> bool {
+        match self.0.kind() {
+            pipe_imp::AddressKind::Unnamed => true,
+            _ => false,
+        }
+    }
+
+    pub fn as_pathname(&self) -> Option<Path> {
+        match self.0.kind() {
+            pipe_imp::AddressKind::Pathname(p) => Some(Path::new(p)),
+            _ => None,
+        }
+    }
+
+    pub fn as_abstract_name(&self) -> Option<&[u8]> {
+        match self.0.kind() {
+            pipe_imp::AddressKind::Abstract(name) => Some(name),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Debug for SocketAddr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0.kind() {
+            pipe_imp::AddressKind::Unnamed => write!(f, "(unnamed)"),
+            pipe_imp::AddressKind::Pathname(p) => {
+                write!(f, "\"{}\" (pathname)", Path::new(p).display())
+            }
+            pipe_imp::AddressKind::Abstract(name) => {
+                write!(f, "{:?} (abstract)", String::from_utf8_lossy(name))
+            }
+        }
+    }
+}
+
+impl UnixStream {
+    pub fn connect(path: &Path) -> io::Result<UnixStream> {
+        UnixStream::connect_addr(&try!(SocketAddr::from_pathname(path)))
+    }
+
+    pub fn connect_addr(addr: &SocketAddr) -> io::Result<UnixStream> {
+        pipe_imp::UnixStream::connect(&addr.0).map(UnixStream)
+    }
+
+    /// Creates an unnamed pair of connected sockets.
+    pub fn pair() -> io::Result<(UnixStream, UnixStream)> {
+        pipe_imp::UnixStream::pair().map(|(a, b)| {
+            (UnixStream(a), UnixStream(b))
+        })
+    }
+
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.0.peer_addr().map(SocketAddr)
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.0.local_addr().map(SocketAddr)
+    }
+
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        self.0.shutdown(how)
+    }
+
+    pub fn duplicate(&self) -> io::Result<UnixStream> {
+        self.0.duplicate().map(UnixStream)
+    }
+}
+
+impl AsInner<FileDesc> for UnixStream {
+    fn as_inner(&self) -> &FileDesc { self.0.socket().fd() }
+}
+
+impl FromInner<FileDesc> for UnixStream {
+    fn from_inner(fd: FileDesc) -> UnixStream {
+        let socket: Socket = FromInner::from_inner(fd);
+        UnixStream(FromInner::from_inner(socket))
+    }
+}
+
+impl IntoInner<FileDesc> for UnixStream {
+    fn into_inner(self) -> FileDesc { self.0.into_socket().into_fd() }
+}
+
+impl Read for UnixStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> { self.0.read(buf) }
+}
+impl Write for UnixStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> { self.0.write(buf) }
+}
+impl<'a> Read for &'a UnixStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> { self.0.read(buf) }
+}
+impl<'a> Write for &'a UnixStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> { self.0.write(buf) }
+}
+
+impl UnixListener {
+    /// Binds a new listener to the specified path.
+    ///
+    /// The socket file is not removed when the listener is dropped.
+    pub fn bind(path: &Path) -> io::Result<UnixListener> {
+        UnixListener::bind_addr(&try!(SocketAddr::from_pathname(path)))
+    }
+
+    pub fn bind_addr(addr: &SocketAddr) -> io::Result<UnixListener> {
+        pipe_imp::UnixListener::bind(&addr.0).map(UnixListener)
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.0.local_addr().map(SocketAddr)
+    }
+
+    pub fn duplicate(&self) -> io::Result<UnixListener> {
+        self.0.duplicate().map(UnixListener)
+    }
+
+    pub fn accept(&self) -> io::Result<(UnixStream, SocketAddr)> {
+        self.0.accept().map(|(a, b)| (UnixStream(a), SocketAddr(b)))
+    }
+
+    pub fn incoming(&self) -> Incoming {
+        Incoming { listener: self }
+    }
+}
+
+impl AsInner<FileDesc> for UnixListener {
+    fn as_inner(&self) -> &FileDesc { self.0.socket().fd() }
+}
+
+impl FromInner<FileDesc> for UnixListener {
+    fn from_inner(fd: FileDesc) -> UnixListener {
+        let socket: Socket = FromInner::from_inner(fd);
+        UnixListener(FromInner::from_inner(socket))
+    }
+}
+
+impl IntoInner<FileDesc> for UnixListener {
+    fn into_inner(self) -> FileDesc { self.0.into_socket().into_fd() }
+}
+
+impl<'a> Iterator for Incoming<'a> {
+    type Item = io::Result<UnixStream>;
+    fn next(&mut self) -> Option<io::Result<UnixStream>> {
+        Some(self.listener.accept().map(|p| p.0))
+    }
+}
+
+impl UnixDatagram {
+    pub fn bind(path: &Path) -> io::Result<UnixDatagram> {
+        UnixDatagram::bind_addr(&try!(SocketAddr::from_pathname(path)))
+    }
+
+    pub fn bind_addr(addr: &SocketAddr) -> io::Result<UnixDatagram> {
+        pipe_imp::UnixDatagram::bind(&addr.0).map(UnixDatagram)
+    }
+
+    /// Creates a datagram socket which is not bound to any address.
+    pub fn unbound() -> io::Result<UnixDatagram> {
+        pipe_imp::UnixDatagram::unbound().map(UnixDatagram)
+    }
+
+    /// Creates an unnamed pair of connected sockets.
+    pub fn pair() -> io::Result<(UnixDatagram, UnixDatagram)> {
+        pipe_imp::UnixDatagram::pair().map(|(a, b)| {
+            (UnixDatagram(a), UnixDatagram(b))
+        })
+    }
+
+    /// Connects the socket to the specified path, after which `send` and
+    /// `recv` may be used and datagrams from other peers are discarded.
+    pub fn connect(&self, path: &Path) -> io::Result<()> {
+        self.connect_addr(&try!(SocketAddr::from_pathname(path)))
+    }
+
+    pub fn connect_addr(&self, addr: &SocketAddr) -> io::Result<()> {
+        self.0.connect(&addr.0)
+    }
+
+    pub fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        self.0.recv_from(buf).map(|(n, addr)| (n, SocketAddr(addr)))
+    }
+
+    pub fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.recv(buf)
+    }
+
+    pub fn send_to(&self, buf: &[u8], path: &Path) -> io::Result<usize> {
+        self.send_to_addr(buf, &try!(SocketAddr::from_pathname(path)))
+    }
+
+    pub fn send_to_addr(&self, buf: &[u8], addr: &SocketAddr)
+                        -> io::Result<usize> {
+        self.0.send_to(buf, &addr.0)
+    }
+
+    pub fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.0.send(buf)
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.0.local_addr().map(SocketAddr)
+    }
+
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.0.peer_addr().map(SocketAddr)
+    }
+
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        self.0.shutdown(how)
+    }
+
+    pub fn duplicate(&self) -> io::Result<UnixDatagram> {
+        self.0.duplicate().map(UnixDatagram)
+    }
+}
+
+impl AsInner<FileDesc> for UnixDatagram {
+    fn as_inner(&self) -> &FileDesc { self.0.socket().fd() }
+}
+
+impl FromInner<FileDesc> for UnixDatagram {
+    fn from_inner(fd: FileDesc) -> UnixDatagram {
+        let socket: Socket = FromInner::from_inner(fd);
+        UnixDatagram(FromInner::from_inner(socket))
+    }
+}
+
+impl IntoInner<FileDesc> for UnixDatagram {
+    fn into_inner(self) -> FileDesc { self.0.into_socket().into_fd() }
+}
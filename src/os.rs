@@ -10,7 +10,7 @@
 
 #[cfg(unix)]
 pub mod unix {
-    pub use std::os::unix::*;
+    pub use sys::ext::*;
 }
 
 #[cfg(windows)]
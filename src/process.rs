@@ -0,0 +1,462 @@
+// Copyright 2015 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Working with processes.
+
+use prelude::v1::*;
+use io::prelude::*;
+
+use ffi::AsOsStr;
+use fmt;
+use io::{self, Error, ErrorKind};
+use path::Path;
+use sys::fd::FileDesc;
+use sys::process::{self, AnonPipe};
+use sys_common::{AsInner, FromInner, IntoInner};
+use thread::Thread;
+
+/// Representation of a running or exited child process.
+///
+/// The child is not killed or waited on when this structure is dropped, it
+/// will continue to run even after the `Child` handle has gone out of scope.
+pub struct Child {
+    handle: process::Process,
+
+    /// None until wait() or try_wait() have observed the exit status
+    status: Option<process::ExitStatus>,
+
+    /// The handle for writing to the child's stdin, if it has been captured
+    pub stdin: Option<ChildStdin>,
+
+    /// The handle for reading from the child's stdout, if it has been captured
+    pub stdout: Option<ChildStdout>,
+
+    /// The handle for reading from the child's stderr, if it has been captured
+    pub stderr: Option<ChildStderr>,
+}
+
+/// A handle to a child process's stdin.
+pub struct ChildStdin {
+    inner: AnonPipe
+}
+
+impl Write for ChildStdin {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+}
+
+impl AsInner<FileDesc> for ChildStdin {
+    fn as_inner(&self) -> &FileDesc { self.inner.fd() }
+}
+
+impl FromInner<FileDesc> for ChildStdin {
+    fn from_inner(fd: FileDesc) -> ChildStdin {
+        ChildStdin { inner: FromInner::from_inner(fd) }
+    }
+}
+
+impl IntoInner<FileDesc> for ChildStdin {
+    fn into_inner(self) -> FileDesc { self.inner.into_fd() }
+}
+
+/// A handle to a child process's stdout.
+pub struct ChildStdout {
+    inner: AnonPipe
+}
+
+impl Read for ChildStdout {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl AsInner<FileDesc> for ChildStdout {
+    fn as_inner(&self) -> &FileDesc { self.inner.fd() }
+}
+
+impl FromInner<FileDesc> for ChildStdout {
+    fn from_inner(fd: FileDesc) -> ChildStdout {
+        ChildStdout { inner: FromInner::from_inner(fd) }
+    }
+}
+
+impl IntoInner<FileDesc> for ChildStdout {
+    fn into_inner(self) -> FileDesc { self.inner.into_fd() }
+}
+
+/// A handle to a child process's stderr.
+pub struct ChildStderr {
+    inner: AnonPipe
+}
+
+impl Read for ChildStderr {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl AsInner<FileDesc> for ChildStderr {
+    fn as_inner(&self) -> &FileDesc { self.inner.fd() }
+}
+
+impl FromInner<FileDesc> for ChildStderr {
+    fn from_inner(fd: FileDesc) -> ChildStderr {
+        ChildStderr { inner: FromInner::from_inner(fd) }
+    }
+}
+
+impl IntoInner<FileDesc> for ChildStderr {
+    fn into_inner(self) -> FileDesc { self.inner.into_fd() }
+}
+
+/// The `Command` type acts as a process builder, providing fine-grained
+/// control over how a new process should be spawned.
+///
+/// A default configuration can be generated using `Command::new(program)`,
+/// where `program` gives a path to the program to be executed. Additional
+/// builder methods allow the configuration to be changed (for example, by
+/// adding arguments) prior to spawning.
+pub struct Command {
+    inner: process::Command,
+
+    // Details explained in the builder methods
+    stdin: Option<StdioImp>,
+    stdout: Option<StdioImp>,
+    stderr: Option<StdioImp>,
+}
+
+impl Command {
+    /// Constructs a new `Command` for launching the program at path
+    /// `program`, with the following default configuration:
+    ///
+    /// * No arguments to the program
+    /// * Inherit the current process's environment
+    /// * Inherit the current process's working directory
+    /// * Inherit stdin/stdout/stderr for `spawn` or `status`, but create
+    ///   pipes for `output`
+    pub fn new<S: AsOsStr + ?Sized>(program: &S) -> Command {
+        Command {
+            inner: process::Command::new(program.as_os_str()),
+            stdin: None,
+            stdout: None,
+            stderr: None,
+        }
+    }
+
+    /// Add an argument to pass to the program.
+    pub fn arg<S: AsOsStr + ?Sized>(&mut self, arg: &S) -> &mut Command {
+        self.inner.arg(arg.as_os_str());
+        self
+    }
+
+    /// Add multiple arguments to pass to the program.
+    pub fn args<S: AsOsStr>(&mut self, args: &[S]) -> &mut Command {
+        self.inner.args(args.iter().map(AsOsStr::as_os_str));
+        self
+    }
+
+    /// Inserts or updates an environment variable mapping.
+    pub fn env<K: ?Sized, V: ?Sized>(&mut self, key: &K, val: &V) -> &mut Command
+        where K: AsOsStr, V: AsOsStr
+    {
+        self.inner.env(key.as_os_str(), val.as_os_str());
+        self
+    }
+
+    /// Removes an environment variable mapping.
+    pub fn env_remove<K: ?Sized + AsOsStr>(&mut self, key: &K) -> &mut Command {
+        self.inner.env_remove(key.as_os_str());
+        self
+    }
+
+    /// Clears the entire environment map for the child process.
+    pub fn env_clear(&mut self) -> &mut Command {
+        self.inner.env_clear();
+        self
+    }
+
+    /// Set the working directory for the child process.
+    pub fn current_dir(&mut self, dir: &Path) -> &mut Command {
+        self.inner.cwd(dir);
+        self
+    }
+
+    /// Configuration for the child process's stdin handle (file descriptor 0).
+    pub fn stdin(&mut self, cfg: Stdio) -> &mut Command {
+        self.stdin = Some(cfg.0);
+        self
+    }
+
+    /// Configuration for the child process's stdout handle (file descriptor 1).
+    pub fn stdout(&mut self, cfg: Stdio) -> &mut Command {
+        self.stdout = Some(cfg.0);
+        self
+    }
+
+    /// Configuration for the child process's stderr handle (file descriptor 2).
+    pub fn stderr(&mut self, cfg: Stdio) -> &mut Command {
+        self.stderr = Some(cfg.0);
+        self
+    }
+
+    fn spawn_inner(&self, default_io: StdioImp,
+                   default_stdin: StdioImp) -> io::Result<Child> {
+        let (their_stdin, our_stdin) = try!(
+            setup_io(self.stdin.unwrap_or(default_stdin), true)
+        );
+        let (their_stdout, our_stdout) = try!(
+            setup_io(self.stdout.unwrap_or(default_io), false)
+        );
+        let (their_stderr, our_stderr) = try!(
+            setup_io(self.stderr.unwrap_or(default_io), false)
+        );
+
+        // The child's ends of any pipes are closed in the parent once they
+        // go out of scope at the end of this function.
+        let handle = try!(process::Process::spawn(&self.inner,
+                                                  their_stdin.stdio(),
+                                                  their_stdout.stdio(),
+                                                  their_stderr.stdio()));
+        Ok(Child {
+            handle: handle,
+            status: None,
+            stdin: our_stdin.map(|fd| ChildStdin { inner: fd }),
+            stdout: our_stdout.map(|fd| ChildStdout { inner: fd }),
+            stderr: our_stderr.map(|fd| ChildStderr { inner: fd }),
+        })
+    }
+
+    /// Executes the command as a child process, returning a handle to it.
+    ///
+    /// By default, stdin, stdout and stderr are inherited from the parent.
+    pub fn spawn(&mut self) -> io::Result<Child> {
+        self.spawn_inner(StdioImp::Inherit, StdioImp::Inherit)
+    }
+
+    /// Executes the command as a child process, waiting for it to finish and
+    /// collecting all of its output.
+    ///
+    /// By default, stdout and stderr are captured (and used to provide the
+    /// resulting output) and stdin is connected to `/dev/null`.
+    pub fn output(&mut self) -> io::Result<Output> {
+        self.spawn_inner(StdioImp::Piped, StdioImp::Null).and_then(|p| {
+            p.wait_with_output()
+        })
+    }
+
+    /// Executes a command as a child process, waiting for it to finish and
+    /// collecting its exit status.
+    ///
+    /// By default, stdin, stdout and stderr are inherited from the parent.
+    pub fn status(&mut self) -> io::Result<ExitStatus> {
+        self.spawn().and_then(|mut p| p.wait())
+    }
+}
+
+impl fmt::Debug for Command {
+    /// Format the program and arguments of a Command for display. Any
+    /// non-utf8 data is lossily converted using the utf8 replacement
+    /// character.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "{:?}",
+                    String::from_utf8_lossy(self.inner.program.as_bytes())));
+        for arg in self.inner.args.iter() {
+            try!(write!(f, " {:?}", String::from_utf8_lossy(arg.as_bytes())));
+        }
+        Ok(())
+    }
+}
+
+struct ChildEnd(Option<AnonPipe>, StdioImp);
+
+impl ChildEnd {
+    fn stdio(&self) -> process::Stdio {
+        match (self.0.as_ref(), self.1) {
+            (Some(pipe), _) => process::Stdio::Raw(pipe.raw()),
+            (None, StdioImp::Null) => process::Stdio::Null,
+            (None, _) => process::Stdio::Inherit,
+        }
+    }
+}
+
+fn setup_io(io: StdioImp, readable: bool)
+            -> io::Result<(ChildEnd, Option<AnonPipe>)> {
+    Ok(match io {
+        StdioImp::Piped => {
+            let (reader, writer) = try!(unsafe { process::anon_pipe() });
+            if readable {
+                (ChildEnd(Some(reader), io), Some(writer))
+            } else {
+                (ChildEnd(Some(writer), io), Some(reader))
+            }
+        }
+        StdioImp::Inherit | StdioImp::Null => (ChildEnd(None, io), None),
+    })
+}
+
+/// The output of a finished process.
+#[derive(PartialEq, Eq, Clone)]
+pub struct Output {
+    /// The status (exit code) of the process.
+    pub status: ExitStatus,
+    /// The data that the process wrote to stdout.
+    pub stdout: Vec<u8>,
+    /// The data that the process wrote to stderr.
+    pub stderr: Vec<u8>,
+}
+
+/// Describes what to do with a standard io stream for a child process.
+pub struct Stdio(StdioImp);
+
+// The internal enum for stdio setup; see below for descriptions.
+#[derive(Copy, Clone)]
+enum StdioImp {
+    Piped,
+    Inherit,
+    Null,
+}
+
+impl Stdio {
+    /// A new pipe should be arranged to connect the parent and child processes.
+    pub fn piped() -> Stdio { Stdio(StdioImp::Piped) }
+
+    /// The child inherits from the corresponding parent descriptor.
+    pub fn inherit() -> Stdio { Stdio(StdioImp::Inherit) }
+
+    /// This stream will be ignored. This is the equivalent of attaching the
+    /// stream to `/dev/null`
+    pub fn null() -> Stdio { Stdio(StdioImp::Null) }
+}
+
+/// Describes the result of a process after it has terminated.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct ExitStatus(process::ExitStatus);
+
+impl ExitStatus {
+    /// Was termination successful? Signal termination not considered a
+    /// success, and success is defined as a zero exit status.
+    pub fn success(&self) -> bool {
+        self.0.success()
+    }
+
+    /// Return the exit code of the process, if any.
+    ///
+    /// On Unix, this will return `None` if the process was terminated by a
+    /// signal.
+    pub fn code(&self) -> Option<i32> {
+        self.0.code()
+    }
+
+    /// If the process was terminated by a signal, returns that signal.
+    pub fn signal(&self) -> Option<i32> {
+        self.0.signal()
+    }
+}
+
+impl fmt::Display for ExitStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl Child {
+    /// Forces the child to exit. This is equivalent to sending a
+    /// SIGKILL on unix platforms.
+    pub fn kill(&mut self) -> io::Result<()> {
+        // If we've already waited on this process then the pid can be
+        // recycled and used for another process, and we probably shouldn't be
+        // killing random processes, so just return an error.
+        if self.status.is_some() {
+            Err(Error::new(ErrorKind::InvalidInput,
+                           "invalid argument: can't kill an exited process",
+                           None))
+        } else {
+            unsafe { self.handle.kill() }
+        }
+    }
+
+    /// Returns the OS-assigned process identifier associated with this child.
+    pub fn id(&self) -> u32 {
+        self.handle.id() as u32
+    }
+
+    /// Wait for the child to exit completely, returning the status that it
+    /// exited with. This function will continue to have the same return value
+    /// after it has been called at least once.
+    ///
+    /// The stdin handle to the child process, if any, will be closed
+    /// before waiting. This helps avoid deadlock: it ensures that the
+    /// child does not block waiting for input from the parent, while
+    /// the parent waits for the child to exit.
+    pub fn wait(&mut self) -> io::Result<ExitStatus> {
+        drop(self.stdin.take());
+        match self.status {
+            Some(code) => Ok(ExitStatus(code)),
+            None => {
+                let status = try!(self.handle.wait());
+                self.status = Some(status);
+                Ok(ExitStatus(status))
+            }
+        }
+    }
+
+    /// Check whether the child has exited without blocking, returning its
+    /// exit status if it has.
+    pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
+        if let Some(code) = self.status {
+            return Ok(Some(ExitStatus(code)))
+        }
+        let status = try!(self.handle.try_wait());
+        self.status = status;
+        Ok(status.map(ExitStatus))
+    }
+
+    /// Simultaneously wait for the child to exit and collect all remaining
+    /// output on the stdout/stderr handles, returning a `Output`
+    /// instance.
+    ///
+    /// The stdin handle to the child process, if any, will be closed
+    /// before waiting. Both stdout and stderr are drained concurrently so a
+    /// child filling up one pipe cannot deadlock against the parent blocked
+    /// reading the other.
+    pub fn wait_with_output(mut self) -> io::Result<Output> {
+        drop(self.stdin.take());
+
+        let stderr = self.stderr.take();
+        let stderr = Thread::scoped(move || -> io::Result<Vec<u8>> {
+            let mut ret = Vec::new();
+            if let Some(mut stderr) = stderr {
+                try!(stderr.read_to_end(&mut ret));
+            }
+            Ok(ret)
+        });
+
+        let mut stdout = Vec::new();
+        let res = match self.stdout.take() {
+            Some(mut out) => out.read_to_end(&mut stdout),
+            None => Ok(()),
+        };
+        let stderr = match stderr.join() {
+            Ok(res) => res,
+            Err(..) => Err(Error::new(ErrorKind::Other,
+                                      "thread reading stderr panicked", None)),
+        };
+        try!(res);
+        let stderr = try!(stderr);
+        let status = try!(self.wait());
+
+        Ok(Output {
+            status: status,
+            stdout: stdout,
+            stderr: stderr,
+        })
+    }
+}
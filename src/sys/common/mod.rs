@@ -8,7 +8,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-// pub mod helper_thread;
+pub mod helper_thread;
 pub mod net;
 
 // common error constructors
@@ -42,6 +42,14 @@ pub trait AsInner<Inner> {
     fn as_inner(&self) -> &Inner;
 }
 
+pub trait AsInnerMut<Inner> {
+    fn as_inner_mut(&mut self) -> &mut Inner;
+}
+
+pub trait IntoInner<Inner> {
+    fn into_inner(self) -> Inner;
+}
+
 pub trait FromInner<Inner> {
     fn from_inner(inner: Inner) -> Self;
 }
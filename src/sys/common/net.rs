@@ -18,7 +18,8 @@ use net::{IpAddr, SocketAddr, Shutdown};
 use num::Int;
 use sys::c;
 use sys::net::{cvt, cvt_r, cvt_gai, Socket, init, wrlen_t};
-use sys_common::AsInner;
+use sys_common::{AsInner, FromInner};
+use time::Duration;
 
 ////////////////////////////////////////////////////////////////////////////////
 // sockaddr and misc bindings
@@ -82,7 +83,7 @@ fn addr_to_sockaddr(addr: &SocketAddr,
     }
 }
 
-fn setsockopt<T>(sock: &Socket, opt: c_int, val: c_int,
+pub fn setsockopt<T>(sock: &Socket, opt: c_int, val: c_int,
                      payload: T) -> io::Result<()> {
     unsafe {
         let payload = &payload as *const T as *const c_void;
@@ -92,20 +93,31 @@ fn setsockopt<T>(sock: &Socket, opt: c_int, val: c_int,
     }
 }
 
-#[allow(dead_code)]
-fn getsockopt<T: Copy>(sock: &Socket, opt: c_int,
+pub fn getsockopt<T: Copy>(sock: &Socket, opt: c_int,
                            val: c_int) -> io::Result<T> {
     unsafe {
         let mut slot: T = mem::zeroed();
         let mut len = mem::size_of::<T>() as socklen_t;
-        let ret = try!(cvt(c::getsockopt(*sock.as_inner(), opt, val,
-                                         &mut slot as *mut _ as *mut _,
-                                         &mut len)));
-        assert_eq!(ret as usize, mem::size_of::<T>());
+        try!(cvt(c::getsockopt(*sock.as_inner(), opt, val,
+                               &mut slot as *mut _ as *mut _,
+                               &mut len)));
+        if len as usize != mem::size_of::<T>() {
+            return Err(Error::new(ErrorKind::Other,
+                                  "unexpected getsockopt length", None))
+        }
         Ok(slot)
     }
 }
 
+fn check_timeout(dur: Duration) -> io::Result<()> {
+    if dur <= Duration::zero() {
+        Err(Error::new(ErrorKind::InvalidInput,
+                       "timeout must be a positive duration", None))
+    } else {
+        Ok(())
+    }
+}
+
 fn sockname<F>(f: F) -> io::Result<SocketAddr>
     where F: FnOnce(*mut libc::sockaddr, *mut socklen_t) -> c_int
 {
@@ -266,6 +278,39 @@ impl TcpStream {
         Ok(TcpStream { inner: sock })
     }
 
+    pub fn connect_timeout(addr: &SocketAddr,
+                           timeout: Duration) -> io::Result<TcpStream> {
+        init();
+        try!(check_timeout(timeout));
+
+        let sock = try!(Socket::new(addr, libc::SOCK_STREAM));
+
+        let mut storage = unsafe { mem::zeroed() };
+        let len = addr_to_sockaddr(addr, &mut storage);
+        let addrp = &storage as *const _ as *const libc::sockaddr;
+
+        try!(sock.connect_timeout(addrp, len, timeout));
+        Ok(TcpStream { inner: sock })
+    }
+
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        if let Some(dur) = dur { try!(check_timeout(dur)); }
+        self.inner.set_timeout(dur, c::SO_RCVTIMEO)
+    }
+
+    pub fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        if let Some(dur) = dur { try!(check_timeout(dur)); }
+        self.inner.set_timeout(dur, c::SO_SNDTIMEO)
+    }
+
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        self.inner.timeout(c::SO_RCVTIMEO)
+    }
+
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        self.inner.timeout(c::SO_SNDTIMEO)
+    }
+
     pub fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
         setsockopt(&self.inner, libc::IPPROTO_TCP, libc::TCP_NODELAY,
                    nodelay as c_int)
@@ -299,7 +344,7 @@ impl TcpStream {
     }
 
     pub fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
-        self.inner.read(buf)
+        self.inner.read(buf).map_err(|e| self.timed_out(e, c::SO_RCVTIMEO))
     }
 
     pub fn write(&self, buf: &[u8]) -> io::Result<usize> {
@@ -308,10 +353,42 @@ impl TcpStream {
                        buf.as_ptr() as *const c_void,
                        buf.len() as wrlen_t,
                        0)
-        }));
+        }).map_err(|e| self.timed_out(e, c::SO_SNDTIMEO)));
         Ok(ret as usize)
     }
 
+    pub fn read_vectored(&self, bufs: &mut [&mut [u8]]) -> io::Result<usize> {
+        self.inner.read_vectored(bufs)
+            .map_err(|e| self.timed_out(e, c::SO_RCVTIMEO))
+    }
+
+    pub fn write_vectored(&self, bufs: &[&[u8]]) -> io::Result<usize> {
+        self.inner.write_vectored(bufs)
+            .map_err(|e| self.timed_out(e, c::SO_SNDTIMEO))
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.inner.set_nonblocking(nonblocking)
+    }
+
+    // Unix reports an expired SO_RCVTIMEO/SO_SNDTIMEO as EAGAIN, so if a
+    // blocking socket has a timeout set then that's what the error actually
+    // means.
+    #[cfg(unix)]
+    fn timed_out(&self, err: Error, kind: c_int) -> Error {
+        if err.kind() != ErrorKind::WouldBlock { return err }
+        match (self.inner.is_nonblocking(), self.inner.timeout(kind)) {
+            (Ok(false), Ok(Some(..))) => {
+                Error::new(ErrorKind::TimedOut, "operation timed out", None)
+            }
+            _ => err,
+        }
+    }
+
+    // Windows reports expired timeouts as WSAETIMEDOUT directly.
+    #[cfg(windows)]
+    fn timed_out(&self, err: Error, _kind: c_int) -> Error { err }
+
     pub fn peer_addr(&self) -> io::Result<SocketAddr> {
         sockname(|buf, len| unsafe {
             libc::getpeername(*self.inner.as_inner(), buf, len)
@@ -336,11 +413,21 @@ impl TcpStream {
         Ok(())
     }
 
+    pub fn socket(&self) -> &Socket { &self.inner }
+
+    pub fn into_socket(self) -> Socket { self.inner }
+
     pub fn duplicate(&self) -> io::Result<TcpStream> {
         self.inner.duplicate().map(|s| TcpStream { inner: s })
     }
 }
 
+impl FromInner<Socket> for TcpStream {
+    fn from_inner(socket: Socket) -> TcpStream {
+        TcpStream { inner: socket }
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 // TCP listeners
 ////////////////////////////////////////////////////////////////////////////////
@@ -389,9 +476,23 @@ impl TcpListener {
         Ok((TcpStream { inner: sock, }, addr))
     }
 
+    pub fn socket(&self) -> &Socket { &self.inner }
+
+    pub fn into_socket(self) -> Socket { self.inner }
+
     pub fn duplicate(&self) -> io::Result<TcpListener> {
         self.inner.duplicate().map(|s| TcpListener { inner: s })
     }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.inner.set_nonblocking(nonblocking)
+    }
+}
+
+impl FromInner<Socket> for TcpListener {
+    fn from_inner(socket: Socket) -> TcpListener {
+        TcpListener { inner: socket }
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -507,7 +608,21 @@ impl UdpSocket {
         setsockopt(&self.inner, libc::IPPROTO_IP, libc::IP_TTL, ttl as c_int)
     }
 
+    pub fn socket(&self) -> &Socket { &self.inner }
+
+    pub fn into_socket(self) -> Socket { self.inner }
+
     pub fn duplicate(&self) -> io::Result<UdpSocket> {
         self.inner.duplicate().map(|s| UdpSocket { inner: s })
     }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.inner.set_nonblocking(nonblocking)
+    }
+}
+
+impl FromInner<Socket> for UdpSocket {
+    fn from_inner(socket: Socket) -> UdpSocket {
+        UdpSocket { inner: socket }
+    }
 }
@@ -56,6 +56,46 @@ pub const FIOCLEX: libc::c_ulong = 0x5451;
               target_arch = "powerpc")))]
 pub const FIOCLEX: libc::c_ulong = 0x6601;
 
+// `O_TMPFILE` includes `O_DIRECTORY`, which differs between architectures
+#[cfg(all(any(target_os = "linux", target_os = "android"),
+          any(target_arch = "x86",
+              target_arch = "x86_64")))]
+pub const O_TMPFILE: libc::c_int = 0o20200000;
+#[cfg(all(any(target_os = "linux", target_os = "android"),
+          any(target_arch = "arm",
+              target_arch = "aarch64",
+              target_arch = "powerpc")))]
+pub const O_TMPFILE: libc::c_int = 0o20040000;
+#[cfg(all(any(target_os = "linux", target_os = "android"),
+          any(target_arch = "mips",
+              target_arch = "mipsel")))]
+pub const O_TMPFILE: libc::c_int = 0o40200000;
+
+#[cfg(any(target_os = "macos",
+          target_os = "ios",
+          target_os = "freebsd",
+          target_os = "dragonfly",
+          all(target_os = "linux",
+              any(target_arch = "mips",
+                  target_arch = "mipsel",
+                  target_arch = "powerpc"))))]
+pub const TIOCGWINSZ: libc::c_ulong = 0x40087468;
+#[cfg(any(all(target_os = "linux",
+              any(target_arch = "x86",
+                  target_arch = "x86_64",
+                  target_arch = "arm",
+                  target_arch = "aarch64")),
+          target_os = "android"))]
+pub const TIOCGWINSZ: libc::c_ulong = 0x5413;
+
+#[repr(C)]
+pub struct winsize {
+    pub ws_row: libc::c_ushort,
+    pub ws_col: libc::c_ushort,
+    pub ws_xpixel: libc::c_ushort,
+    pub ws_ypixel: libc::c_ushort,
+}
+
 #[cfg(any(target_os = "macos",
           target_os = "ios",
           target_os = "freebsd",
@@ -66,6 +106,371 @@ pub const MSG_DONTWAIT: libc::c_int = 0x40;
 
 pub const WNOHANG: libc::c_int = 1;
 
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub const CLOCK_MONOTONIC: libc::c_int = 1;
+#[cfg(any(target_os = "freebsd", target_os = "dragonfly"))]
+pub const CLOCK_MONOTONIC: libc::c_int = 4;
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+#[repr(C)]
+pub struct mach_timebase_info {
+    pub numer: u32,
+    pub denom: u32,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub const O_CLOEXEC: libc::c_int = 0o2000000;
+
+#[cfg(any(all(target_os = "linux",
+              any(target_arch = "x86",
+                  target_arch = "x86_64",
+                  target_arch = "arm",
+                  target_arch = "aarch64",
+                  target_arch = "powerpc")),
+          target_os = "android"))]
+pub const O_NOCTTY: libc::c_int = 0o400;
+#[cfg(all(target_os = "linux",
+          any(target_arch = "mips",
+              target_arch = "mipsel")))]
+pub const O_NOCTTY: libc::c_int = 0x800;
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+pub const O_NOCTTY: libc::c_int = 0x20000;
+#[cfg(any(target_os = "freebsd", target_os = "dragonfly"))]
+pub const O_NOCTTY: libc::c_int = 0x8000;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub const F_DUPFD_CLOEXEC: libc::c_int = 1030;
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+pub const F_DUPFD_CLOEXEC: libc::c_int = 67;
+#[cfg(any(target_os = "freebsd", target_os = "dragonfly"))]
+pub const F_DUPFD_CLOEXEC: libc::c_int = 17;
+
+#[cfg(any(all(target_os = "linux",
+              any(target_arch = "x86",
+                  target_arch = "x86_64",
+                  target_arch = "arm",
+                  target_arch = "aarch64")),
+          target_os = "android"))]
+pub const SO_RCVTIMEO: libc::c_int = 20;
+#[cfg(any(all(target_os = "linux",
+              any(target_arch = "x86",
+                  target_arch = "x86_64",
+                  target_arch = "arm",
+                  target_arch = "aarch64")),
+          target_os = "android"))]
+pub const SO_SNDTIMEO: libc::c_int = 21;
+#[cfg(all(target_os = "linux", target_arch = "powerpc"))]
+pub const SO_RCVTIMEO: libc::c_int = 18;
+#[cfg(all(target_os = "linux", target_arch = "powerpc"))]
+pub const SO_SNDTIMEO: libc::c_int = 19;
+#[cfg(any(target_os = "macos",
+          target_os = "ios",
+          target_os = "freebsd",
+          target_os = "dragonfly",
+          all(target_os = "linux",
+              any(target_arch = "mips", target_arch = "mipsel"))))]
+pub const SO_RCVTIMEO: libc::c_int = 0x1006;
+#[cfg(any(target_os = "macos",
+          target_os = "ios",
+          target_os = "freebsd",
+          target_os = "dragonfly",
+          all(target_os = "linux",
+              any(target_arch = "mips", target_arch = "mipsel"))))]
+pub const SO_SNDTIMEO: libc::c_int = 0x1005;
+
+// Not exported by liblibc, but the same value everywhere.
+pub const S_IFSOCK: libc::mode_t = 0o140000;
+
+pub const POLLIN: libc::c_short = 0x1;
+pub const POLLOUT: libc::c_short = 0x4;
+pub const POLLERR: libc::c_short = 0x8;
+pub const POLLHUP: libc::c_short = 0x10;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub type nfds_t = libc::c_ulong;
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub type nfds_t = libc::c_uint;
+
+// The smallest `IOV_MAX` of the supported platforms; longer lists of buffers
+// are truncated to this many.
+pub const IOV_MAX: usize = 1024;
+
+#[repr(C)]
+pub struct iovec {
+    pub iov_base: *mut libc::c_void,
+    pub iov_len: libc::size_t,
+}
+
+#[repr(C)]
+pub struct pollfd {
+    pub fd: libc::c_int,
+    pub events: libc::c_short,
+    pub revents: libc::c_short,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use self::epoll::*;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use self::timerfd::*;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod timerfd {
+    use libc;
+
+    pub const TFD_CLOEXEC: libc::c_int = 0x80000;
+    pub const TFD_NONBLOCK: libc::c_int = 0x800;
+
+    #[repr(C)]
+    pub struct itimerspec {
+        pub it_interval: libc::timespec,
+        pub it_value: libc::timespec,
+    }
+
+    extern {
+        pub fn timerfd_create(clockid: libc::c_int,
+                              flags: libc::c_int) -> libc::c_int;
+        pub fn timerfd_settime(fd: libc::c_int, flags: libc::c_int,
+                               new_value: *const itimerspec,
+                               old_value: *mut itimerspec) -> libc::c_int;
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod epoll {
+    use libc;
+
+    pub const EPOLL_CLOEXEC: libc::c_int = 0x80000;
+
+    pub const EPOLL_CTL_ADD: libc::c_int = 1;
+    pub const EPOLL_CTL_DEL: libc::c_int = 2;
+    pub const EPOLL_CTL_MOD: libc::c_int = 3;
+
+    pub const EPOLLIN: u32 = 0x001;
+    pub const EPOLLOUT: u32 = 0x004;
+    pub const EPOLLERR: u32 = 0x008;
+    pub const EPOLLHUP: u32 = 0x010;
+    pub const EPOLLRDHUP: u32 = 0x2000;
+    pub const EPOLLET: u32 = 1 << 31;
+
+    // The kernel's definition of this structure is packed on x86_64 only.
+    #[repr(C)]
+    #[cfg_attr(target_arch = "x86_64", repr(packed))]
+    #[derive(Copy, Clone)]
+    pub struct epoll_event {
+        pub events: u32,
+        pub data: u64,
+    }
+
+    extern {
+        pub fn epoll_create1(flags: libc::c_int) -> libc::c_int;
+        pub fn epoll_ctl(epfd: libc::c_int, op: libc::c_int, fd: libc::c_int,
+                         event: *mut epoll_event) -> libc::c_int;
+        pub fn epoll_wait(epfd: libc::c_int, events: *mut epoll_event,
+                          maxevents: libc::c_int,
+                          timeout: libc::c_int) -> libc::c_int;
+    }
+}
+
+pub use self::termios::*;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod termios {
+    use libc;
+
+    pub type tcflag_t = libc::c_uint;
+    pub type cc_t = libc::c_uchar;
+    pub type speed_t = libc::c_uint;
+
+    pub const NCCS: usize = 32;
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    pub struct termios {
+        pub c_iflag: tcflag_t,
+        pub c_oflag: tcflag_t,
+        pub c_cflag: tcflag_t,
+        pub c_lflag: tcflag_t,
+        pub c_line: cc_t,
+        pub c_cc: [cc_t; NCCS],
+        pub c_ispeed: speed_t,
+        pub c_ospeed: speed_t,
+    }
+
+    pub const IGNBRK: tcflag_t = 0o000001;
+    pub const BRKINT: tcflag_t = 0o000002;
+    pub const PARMRK: tcflag_t = 0o000010;
+    pub const ISTRIP: tcflag_t = 0o000040;
+    pub const INLCR: tcflag_t = 0o000100;
+    pub const IGNCR: tcflag_t = 0o000200;
+    pub const ICRNL: tcflag_t = 0o000400;
+    pub const IXON: tcflag_t = 0o002000;
+    pub const OPOST: tcflag_t = 0o000001;
+    pub const CSIZE: tcflag_t = 0o000060;
+    pub const CS8: tcflag_t = 0o000060;
+    pub const PARENB: tcflag_t = 0o000400;
+    pub const ISIG: tcflag_t = 0o000001;
+    pub const ICANON: tcflag_t = 0o000002;
+    pub const ECHO: tcflag_t = 0o000010;
+    pub const ECHONL: tcflag_t = 0o000100;
+    pub const IEXTEN: tcflag_t = 0o100000;
+
+    pub const VTIME: usize = 5;
+    pub const VMIN: usize = 6;
+
+    pub const TCSANOW: libc::c_int = 0;
+    pub const TCSAFLUSH: libc::c_int = 2;
+
+    extern {
+        pub fn tcgetattr(fd: libc::c_int, termios: *mut termios) -> libc::c_int;
+        pub fn tcsetattr(fd: libc::c_int, optional_actions: libc::c_int,
+                         termios: *const termios) -> libc::c_int;
+    }
+}
+
+#[cfg(any(target_os = "macos",
+          target_os = "ios",
+          target_os = "freebsd",
+          target_os = "dragonfly"))]
+mod termios {
+    use libc;
+
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    pub type tcflag_t = libc::c_ulong;
+    #[cfg(any(target_os = "freebsd", target_os = "dragonfly"))]
+    pub type tcflag_t = libc::c_uint;
+    pub type cc_t = libc::c_uchar;
+    pub type speed_t = tcflag_t;
+
+    pub const NCCS: usize = 20;
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    pub struct termios {
+        pub c_iflag: tcflag_t,
+        pub c_oflag: tcflag_t,
+        pub c_cflag: tcflag_t,
+        pub c_lflag: tcflag_t,
+        pub c_cc: [cc_t; NCCS],
+        pub c_ispeed: speed_t,
+        pub c_ospeed: speed_t,
+    }
+
+    pub const IGNBRK: tcflag_t = 0x00000001;
+    pub const BRKINT: tcflag_t = 0x00000002;
+    pub const PARMRK: tcflag_t = 0x00000008;
+    pub const ISTRIP: tcflag_t = 0x00000020;
+    pub const INLCR: tcflag_t = 0x00000040;
+    pub const IGNCR: tcflag_t = 0x00000080;
+    pub const ICRNL: tcflag_t = 0x00000100;
+    pub const IXON: tcflag_t = 0x00000200;
+    pub const OPOST: tcflag_t = 0x00000001;
+    pub const CSIZE: tcflag_t = 0x00000300;
+    pub const CS8: tcflag_t = 0x00000300;
+    pub const PARENB: tcflag_t = 0x00001000;
+    pub const ISIG: tcflag_t = 0x00000080;
+    pub const ICANON: tcflag_t = 0x00000100;
+    pub const ECHO: tcflag_t = 0x00000008;
+    pub const ECHONL: tcflag_t = 0x00000010;
+    pub const IEXTEN: tcflag_t = 0x00000400;
+
+    pub const VMIN: usize = 16;
+    pub const VTIME: usize = 17;
+
+    pub const TCSANOW: libc::c_int = 0;
+    pub const TCSAFLUSH: libc::c_int = 2;
+
+    extern {
+        pub fn tcgetattr(fd: libc::c_int, termios: *mut termios) -> libc::c_int;
+        pub fn tcsetattr(fd: libc::c_int, optional_actions: libc::c_int,
+                         termios: *const termios) -> libc::c_int;
+    }
+}
+
+pub const LOCK_SH: libc::c_int = 1;
+pub const LOCK_EX: libc::c_int = 2;
+pub const LOCK_NB: libc::c_int = 4;
+pub const LOCK_UN: libc::c_int = 8;
+
+pub use self::flock::*;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod flock {
+    use libc;
+
+    pub const F_RDLCK: libc::c_short = 0;
+    pub const F_WRLCK: libc::c_short = 1;
+    pub const F_UNLCK: libc::c_short = 2;
+
+    // 32-bit platforms need the 64-bit variants of these to match `flock`
+    // below, which always uses 64-bit offsets.
+    #[cfg(target_pointer_width = "64")]
+    pub const F_SETLK: libc::c_int = 6;
+    #[cfg(target_pointer_width = "64")]
+    pub const F_SETLKW: libc::c_int = 7;
+    #[cfg(target_pointer_width = "32")]
+    pub const F_SETLK: libc::c_int = 13;
+    #[cfg(target_pointer_width = "32")]
+    pub const F_SETLKW: libc::c_int = 14;
+
+    // Open file description locks, available since Linux 3.15
+    pub const F_OFD_GETLK: libc::c_int = 36;
+    pub const F_OFD_SETLK: libc::c_int = 37;
+    pub const F_OFD_SETLKW: libc::c_int = 38;
+
+    #[repr(C)]
+    pub struct flock {
+        pub l_type: libc::c_short,
+        pub l_whence: libc::c_short,
+        pub l_start: i64,
+        pub l_len: i64,
+        pub l_pid: libc::pid_t,
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios", target_os = "dragonfly"))]
+mod flock {
+    use libc;
+
+    pub const F_RDLCK: libc::c_short = 1;
+    pub const F_UNLCK: libc::c_short = 2;
+    pub const F_WRLCK: libc::c_short = 3;
+
+    pub const F_SETLK: libc::c_int = 8;
+    pub const F_SETLKW: libc::c_int = 9;
+
+    #[repr(C)]
+    pub struct flock {
+        pub l_start: i64,
+        pub l_len: i64,
+        pub l_pid: libc::pid_t,
+        pub l_type: libc::c_short,
+        pub l_whence: libc::c_short,
+    }
+}
+
+#[cfg(target_os = "freebsd")]
+mod flock {
+    use libc;
+
+    pub const F_RDLCK: libc::c_short = 1;
+    pub const F_UNLCK: libc::c_short = 2;
+    pub const F_WRLCK: libc::c_short = 3;
+
+    pub const F_SETLK: libc::c_int = 12;
+    pub const F_SETLKW: libc::c_int = 13;
+
+    #[repr(C)]
+    pub struct flock {
+        pub l_start: i64,
+        pub l_len: i64,
+        pub l_pid: libc::pid_t,
+        pub l_type: libc::c_short,
+        pub l_whence: libc::c_short,
+        pub l_sysid: libc::c_int,
+    }
+}
+
 #[cfg(target_os = "linux")]
 pub const _SC_GETPW_R_SIZE_MAX: libc::c_int = 70;
 #[cfg(any(target_os = "macos",
@@ -113,6 +518,29 @@ pub struct passwd {
     pub pw_shell: *mut libc::c_char,
 }
 
+#[cfg(not(any(target_os = "macos", target_os = "ios")))]
+extern {
+    pub fn clock_gettime(clk_id: libc::c_int,
+                         tp: *mut libc::timespec) -> libc::c_int;
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+extern {
+    pub fn mach_absolute_time() -> u64;
+    pub fn mach_timebase_info(info: *mut mach_timebase_info) -> libc::c_int;
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub const AT_FDCWD: libc::c_int = -100;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+extern {
+    pub fn pipe2(fds: *mut libc::c_int, flags: libc::c_int) -> libc::c_int;
+    pub fn utimensat(dirfd: libc::c_int, path: *const libc::c_char,
+                     times: *const libc::timespec,
+                     flags: libc::c_int) -> libc::c_int;
+}
+
 extern {
     pub fn gettimeofday(timeval: *mut libc::timeval,
                         tzp: *mut libc::c_void) -> libc::c_int;
@@ -121,12 +549,33 @@ extern {
                   writefds: *mut fd_set,
                   errorfds: *mut fd_set,
                   timeout: *mut libc::timeval) -> libc::c_int;
+    pub fn poll(fds: *mut pollfd, nfds: nfds_t,
+                timeout: libc::c_int) -> libc::c_int;
     pub fn getsockopt(sockfd: libc::c_int,
                       level: libc::c_int,
                       optname: libc::c_int,
                       optval: *mut libc::c_void,
                       optlen: *mut libc::socklen_t) -> libc::c_int;
     pub fn ioctl(fd: libc::c_int, req: libc::c_ulong, ...) -> libc::c_int;
+    pub fn flock(fd: libc::c_int, operation: libc::c_int) -> libc::c_int;
+    pub fn fchown(fd: libc::c_int, owner: libc::uid_t,
+                  group: libc::gid_t) -> libc::c_int;
+    pub fn lchown(path: *const libc::c_char, owner: libc::uid_t,
+                  group: libc::gid_t) -> libc::c_int;
+    pub fn umask(mask: libc::mode_t) -> libc::mode_t;
+    pub fn realpath(path: *const libc::c_char,
+                    resolved: *mut libc::c_char) -> *mut libc::c_char;
+    pub fn pread(fd: libc::c_int, buf: *mut libc::c_void, count: libc::size_t,
+                 offset: libc::off_t) -> libc::ssize_t;
+    pub fn pwrite(fd: libc::c_int, buf: *const libc::c_void,
+                  count: libc::size_t, offset: libc::off_t) -> libc::ssize_t;
+    pub fn readv(fd: libc::c_int, iov: *const iovec,
+                 iovcnt: libc::c_int) -> libc::ssize_t;
+    pub fn writev(fd: libc::c_int, iov: *const iovec,
+                  iovcnt: libc::c_int) -> libc::ssize_t;
+    pub fn socketpair(domain: libc::c_int, ty: libc::c_int,
+                      protocol: libc::c_int,
+                      sv: *mut libc::c_int) -> libc::c_int;
 
 
     pub fn waitpid(pid: libc::pid_t, status: *mut libc::c_int,
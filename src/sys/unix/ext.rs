@@ -8,18 +8,16 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-//! Experimental extensions to `std` for Unix platforms.
+//! Experimental extensions to `io2` for Unix platforms.
 //!
-//! For now, this module is limited to extracting file descriptors,
-//! but its functionality will grow over time.
+//! This module provides conversions between the I/O types of this crate and
+//! raw file descriptors, along with Unix-specific filesystem functionality.
 //!
 //! # Example
 //!
 //! ```rust,ignore
-//! #![feature(globs)]
-//!
-//! use std::old_io::fs::File;
-//! use std::os::unix::prelude::*;
+//! use io2::fs::File;
+//! use io2::os::unix::prelude::*;
 //!
 //! fn main() {
 //!     let f = File::create(&Path::new("foo.txt")).unwrap();
@@ -31,75 +29,211 @@
 
 #![unstable]
 
-use sys_common::AsInner;
+use io;
 use libc;
+use net;
+use process;
+use sys::fd::FileDesc;
+use sys_common::{AsInner, FromInner, IntoInner};
+use terminal;
 
-// /// Raw file descriptors.
-// pub type Fd = libc::c_int;
-//
-// /// Extract raw file descriptor
-// pub trait AsRawFd {
-//     /// Extract the raw file descriptor, without taking any ownership.
-//     fn as_raw_fd(&self) -> Fd;
-// }
-//
-// impl AsRawFd for old_io::fs::File {
-//     fn as_raw_fd(&self) -> Fd {
-//         self.as_inner().fd()
-//     }
-// }
-//
-// impl AsRawFd for old_io::pipe::PipeStream {
-//     fn as_raw_fd(&self) -> Fd {
-//         self.as_inner().fd()
-//     }
-// }
-//
-// impl AsRawFd for old_io::net::pipe::UnixStream {
-//     fn as_raw_fd(&self) -> Fd {
-//         self.as_inner().fd()
-//     }
-// }
-//
-// impl AsRawFd for old_io::net::pipe::UnixListener {
-//     fn as_raw_fd(&self) -> Fd {
-//         self.as_inner().fd()
-//     }
-// }
-//
-// impl AsRawFd for old_io::net::pipe::UnixAcceptor {
-//     fn as_raw_fd(&self) -> Fd {
-//         self.as_inner().fd()
-//     }
-// }
-//
-// impl AsRawFd for old_io::net::tcp::TcpStream {
-//     fn as_raw_fd(&self) -> Fd {
-//         self.as_inner().fd()
-//     }
-// }
-//
-// impl AsRawFd for old_io::net::tcp::TcpListener {
-//     fn as_raw_fd(&self) -> Fd {
-//         self.as_inner().fd()
-//     }
-// }
-//
-// impl AsRawFd for old_io::net::tcp::TcpAcceptor {
-//     fn as_raw_fd(&self) -> Fd {
-//         self.as_inner().fd()
-//     }
-// }
-//
-// impl AsRawFd for old_io::net::udp::UdpSocket {
-//     fn as_raw_fd(&self) -> Fd {
-//         self.as_inner().fd()
-//     }
-// }
-//
-// /// A prelude for conveniently writing platform-specific code.
-// ///
-// /// Includes all extension traits, and some important type definitions.
-// pub mod prelude {
-//     pub use super::{Fd, AsRawFd};
-// }
+pub use std::os::unix::{OsStrExt, OsStringExt};
+
+/// Raw file descriptors.
+pub type Fd = libc::c_int;
+
+/// Extract raw file descriptor
+pub trait AsRawFd {
+    /// Extract the raw file descriptor, without taking any ownership.
+    ///
+    /// The descriptor is only valid for as long as the object it was
+    /// extracted from is alive.
+    fn as_raw_fd(&self) -> Fd;
+}
+
+/// Construct I/O objects from raw file descriptors.
+pub trait FromRawFd {
+    /// Constructs a new instance of `Self` which takes ownership of `fd`.
+    ///
+    /// The descriptor is closed when the returned object is dropped, except
+    /// for the standard descriptors 0, 1 and 2, which are never closed.
+    ///
+    /// This function is unsafe because nothing checks that `fd` is open,
+    /// refers to the right kind of object, or isn't also owned elsewhere.
+    unsafe fn from_raw_fd(fd: Fd) -> Self;
+}
+
+/// Consume I/O objects, yielding their raw file descriptors.
+pub trait IntoRawFd {
+    /// Consumes this object, returning the raw file descriptor it owned.
+    ///
+    /// The caller is responsible for closing the returned descriptor.
+    fn into_raw_fd(self) -> Fd;
+}
+
+macro_rules! fd_impls {
+    ($($t:ty),*) => ($(
+        impl AsRawFd for $t {
+            fn as_raw_fd(&self) -> Fd {
+                let fd: &FileDesc = self.as_inner();
+                fd.raw()
+            }
+        }
+
+        impl FromRawFd for $t {
+            unsafe fn from_raw_fd(fd: Fd) -> $t {
+                FromInner::from_inner(FileDesc::new(fd))
+            }
+        }
+
+        impl IntoRawFd for $t {
+            fn into_raw_fd(self) -> Fd {
+                let fd: FileDesc = self.into_inner();
+                fd.into_raw()
+            }
+        }
+    )*)
+}
+
+fd_impls! {
+    ::fs::File,
+    io::PipeReader, io::PipeWriter,
+    net::TcpStream, net::TcpListener, net::UdpSocket,
+    net::unix::UnixStream, net::unix::UnixListener, net::unix::UnixDatagram,
+    process::ChildStdin, process::ChildStdout, process::ChildStderr,
+    terminal::Terminal
+}
+
+// The standard streams are shared by the whole process, so they can't be
+// created from or converted into an arbitrary descriptor.
+impl AsRawFd for io::Stdin {
+    fn as_raw_fd(&self) -> Fd { libc::STDIN_FILENO }
+}
+
+impl AsRawFd for io::Stdout {
+    fn as_raw_fd(&self) -> Fd { libc::STDOUT_FILENO }
+}
+
+impl AsRawFd for io::Stderr {
+    fn as_raw_fd(&self) -> Fd { libc::STDERR_FILENO }
+}
+
+/// A prelude for conveniently writing platform-specific code.
+///
+/// Includes all extension traits, and some important type definitions.
+pub mod prelude {
+    pub use super::{Fd, AsRawFd, FromRawFd, IntoRawFd, OsStrExt, OsStringExt};
+    pub use super::fs::{FilePermissionExt, FileExt, OpenOptionsExt};
+}
+
+/// Unix-specific extensions to the primitives in the `io2::fs` module.
+pub mod fs {
+    use fs::{File, FilePermission, OpenOptions};
+    use io;
+    use path::Path;
+    use sync::{StaticMutex, MUTEX_INIT};
+    use sys::fs as imp;
+    use sys_common::{AsInner, AsInnerMut, FromInner};
+
+    /// Unix-specific extensions to `FilePermission`.
+    pub trait FilePermissionExt {
+        /// Returns the full mode bits of these permissions, including the
+        /// setuid (`0o4000`), setgid (`0o2000`) and sticky (`0o1000`) bits.
+        /// The file type bits are not included.
+        fn mode(&self) -> u32;
+
+        /// Sets the full mode bits of these permissions. Any file type bits
+        /// in `mode` are ignored.
+        fn set_mode(&mut self, mode: u32);
+
+        /// Creates permissions from the given mode bits, ignoring any file
+        /// type bits.
+        fn from_mode(mode: u32) -> Self;
+    }
+
+    impl FilePermissionExt for FilePermission {
+        fn mode(&self) -> u32 { self.as_inner().mode() }
+
+        fn set_mode(&mut self, mode: u32) {
+            self.as_inner_mut().set_mode(mode)
+        }
+
+        fn from_mode(mode: u32) -> FilePermission {
+            FromInner::from_inner(imp::FilePermission::from_mode(mode))
+        }
+    }
+
+    /// Unix-specific extensions to `OpenOptions`.
+    pub trait OpenOptionsExt {
+        /// Sets the mode bits that a file created by these options gets,
+        /// before the process's umask is cleared from them. The default is
+        /// `0o666`.
+        fn mode(&mut self, mode: u32) -> &mut Self;
+    }
+
+    impl OpenOptionsExt for OpenOptions {
+        fn mode(&mut self, mode: u32) -> &mut OpenOptions {
+            self.as_inner_mut().mode(mode); self
+        }
+    }
+
+    /// Unix-specific extensions to `File`.
+    pub trait FileExt {
+        /// Changes the owner and group of this file, like `chown` but
+        /// without looking the file up by path again.
+        fn chown(&self, uid: u32, gid: u32) -> io::Result<()>;
+    }
+
+    impl FileExt for File {
+        fn chown(&self, uid: u32, gid: u32) -> io::Result<()> {
+            self.as_inner().chown(uid, gid)
+        }
+    }
+
+    /// Changes the owner and group of the file at `path`, following
+    /// symlinks.
+    ///
+    /// Passing `!0` as either ID leaves it unchanged. Only privileged
+    /// processes may change the owner, and other processes may only change
+    /// the group to one they are a member of.
+    pub fn chown(path: &Path, uid: u32, gid: u32) -> io::Result<()> {
+        imp::chown(path, uid, gid)
+    }
+
+    /// Changes the owner and group of the file at `path`, like `chown`
+    /// except that a symlink at `path` is changed itself.
+    pub fn lchown(path: &Path, uid: u32, gid: u32) -> io::Result<()> {
+        imp::lchown(path, uid, gid)
+    }
+
+    // `umask` can only be read by replacing it, so reads and writes through
+    // this module are serialized to keep `umask()` from clobbering a mask
+    // set concurrently by `set_umask`.
+    static UMASK_LOCK: StaticMutex = MUTEX_INIT;
+
+    /// Returns the file mode creation mask of this process.
+    ///
+    /// Files created by this process, for example by `File::create`, have
+    /// these bits cleared from their mode.
+    ///
+    /// On Linux the mask is read from `/proc/self/status`. Elsewhere, and on
+    /// kernels older than 4.7, it can only be read by replacing it and
+    /// putting it back, so a file which another thread creates in between
+    /// gets no mask at all.
+    pub fn umask() -> u32 {
+        if let Some(mask) = imp::read_umask() {
+            return mask
+        }
+        let _g = UMASK_LOCK.lock();
+        let mask = imp::umask(0);
+        imp::umask(mask);
+        mask
+    }
+
+    /// Sets the file mode creation mask of this process, returning the
+    /// previous mask.
+    pub fn set_umask(mask: u32) -> u32 {
+        let _g = UMASK_LOCK.lock();
+        imp::umask(mask & 0o777)
+    }
+}
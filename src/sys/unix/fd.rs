@@ -14,7 +14,9 @@ use io::prelude::*;
 use io;
 use libc::{self, c_int, size_t, c_void};
 use mem;
+use sys::{self, c};
 use sys_common::AsInner;
+use vec::Vec;
 
 pub struct FileDesc {
     fd: c_int,
@@ -40,6 +42,68 @@ impl FileDesc {
         }));
         Ok(ret as usize)
     }
+
+    pub fn read_vectored(&self, bufs: &mut [&mut [u8]]) -> io::Result<usize> {
+        let iov = bufs.iter_mut().take(c::IOV_MAX).map(|buf| c::iovec {
+            iov_base: buf.as_mut_ptr() as *mut c_void,
+            iov_len: buf.len() as size_t,
+        }).collect::<Vec<_>>();
+        let ret = try!(call!(unsafe {
+            c::readv(self.fd, iov.as_ptr(), iov.len() as c_int)
+        }));
+        Ok(ret as usize)
+    }
+
+    /// Duplicates this descriptor, with close-on-exec set on the new one.
+    pub fn duplicate(&self) -> io::Result<FileDesc> {
+        // Setting the flag in the same call keeps a concurrent fork from
+        // inheriting the new descriptor. Linux only knows `F_DUPFD_CLOEXEC`
+        // since 2.6.24, so older kernels set it afterwards instead.
+        match unsafe { libc::fcntl(self.fd, c::F_DUPFD_CLOEXEC, 0) } {
+            -1 if sys::os::errno() == libc::EINVAL as i32 => {
+                let fd = FileDesc::new(try!(call!(unsafe {
+                    libc::dup(self.fd)
+                })));
+                try!(fd.set_cloexec());
+                Ok(fd)
+            }
+            -1 => Err(io::Error::last_os_error()),
+            fd => Ok(FileDesc::new(fd)),
+        }
+    }
+
+    pub fn set_cloexec(&self) -> io::Result<()> {
+        try!(call!(unsafe { c::ioctl(self.fd, c::FIOCLEX) }));
+        Ok(())
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        sys::set_nonblocking(self.fd, nonblocking)
+    }
+
+    pub fn is_nonblocking(&self) -> io::Result<bool> {
+        sys::is_nonblocking(self.fd)
+    }
+
+    pub fn write(&self, buf: &[u8]) -> io::Result<usize> {
+        let ret = try!(call!(unsafe {
+            libc::write(self.fd,
+                        buf.as_ptr() as *const c_void,
+                        buf.len() as size_t)
+        }));
+        Ok(ret as usize)
+    }
+
+    pub fn write_vectored(&self, bufs: &[&[u8]]) -> io::Result<usize> {
+        let iov = bufs.iter().take(c::IOV_MAX).map(|buf| c::iovec {
+            iov_base: buf.as_ptr() as *mut c_void,
+            iov_len: buf.len() as size_t,
+        }).collect::<Vec<_>>();
+        let ret = try!(call!(unsafe {
+            c::writev(self.fd, iov.as_ptr(), iov.len() as c_int)
+        }));
+        Ok(ret as usize)
+    }
 }
 
 impl AsInner<c_int> for FileDesc {
@@ -55,6 +119,9 @@ impl Read for FileDesc {
         }));
         Ok(ret as usize)
     }
+    fn read_vectored(&mut self, bufs: &mut [&mut [u8]]) -> io::Result<usize> {
+        FileDesc::read_vectored(self, bufs)
+    }
 }
 
 impl Write for FileDesc {
@@ -66,6 +133,9 @@ impl Write for FileDesc {
         }));
         Ok(ret as usize)
     }
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> io::Result<usize> {
+        FileDesc::write_vectored(self, bufs)
+    }
 }
 
 impl Drop for FileDesc {
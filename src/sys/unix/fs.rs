@@ -14,14 +14,18 @@ use core::prelude::*;
 use io::prelude::*;
 
 use ffi::{self, CString};
+use fs::FileType;
 use io::{self, Error, Seek, SeekPos};
 use libc::{self, c_int, c_void, size_t, off_t, c_char, mode_t};
 use mem;
+use num::Int;
 use path::{Path, GenericPath};
 use ptr;
 use rc::Rc;
-use sys::c;
+use sync::atomic::{AtomicUsize, ATOMIC_USIZE_INIT, Ordering};
+use sys::{self, c, cvt_r, os};
 use sys::fd::FileDesc;
+use sys_common::FromInner;
 use vec::Vec;
 
 pub struct File(FileDesc);
@@ -61,63 +65,50 @@ impl FileAttr {
     }
     pub fn size(&self) -> u64 { self.stat.st_size as u64 }
     pub fn perm(&self) -> FilePermission {
-        FilePermission { mode: (self.stat.st_mode as mode_t) & 0o777 }
+        FilePermission { mode: (self.stat.st_mode as mode_t) & 0o7777 }
     }
 
-    pub fn accessed(&self) -> u64 {
-        self.mktime(self.stat.st_atime as u64, self.stat.st_atime_nsec as u64)
-    }
-    pub fn modified(&self) -> u64 {
-        self.mktime(self.stat.st_mtime as u64, self.stat.st_mtime_nsec as u64)
+    pub fn file_type(&self) -> FileType {
+        match (self.stat.st_mode as mode_t) & libc::S_IFMT {
+            libc::S_IFREG => FileType::File,
+            libc::S_IFDIR => FileType::Dir,
+            libc::S_IFLNK => FileType::Symlink,
+            libc::S_IFIFO => FileType::Fifo,
+            libc::S_IFBLK => FileType::BlockDevice,
+            libc::S_IFCHR => FileType::CharDevice,
+            c::S_IFSOCK => FileType::Socket,
+            _ => FileType::Unknown,
+        }
     }
 
-    // times are in milliseconds (currently)
-    fn mktime(&self, secs: u64, nsecs: u64) -> u64 {
-        secs * 1000 + nsecs / 1000000
+    pub fn accessed(&self) -> (i64, u32) {
+        (self.stat.st_atime as i64, self.stat.st_atime_nsec as u32)
     }
-
-// fn mkstat(stat: &libc::stat) -> FileStat {
-//     // FileStat times are in milliseconds
-//     fn mktime(secs: u64, nsecs: u64) -> u64 { secs * 1000 + nsecs / 1000000 }
-//
-//     #[cfg(not(any(target_os = "linux", target_os = "android")))]
-//     fn flags(stat: &libc::stat) -> u64 { stat.st_flags as u64 }
-//     #[cfg(any(target_os = "linux", target_os = "android"))]
-//     fn flags(_stat: &libc::stat) -> u64 { 0 }
-//
-//     #[cfg(not(any(target_os = "linux", target_os = "android")))]
-//     fn gen(stat: &libc::stat) -> u64 { stat.st_gen as u64 }
-//     #[cfg(any(target_os = "linux", target_os = "android"))]
-//     fn gen(_stat: &libc::stat) -> u64 { 0 }
-//
-//     FileStat {
-//         size: stat.st_size as u64,
-//         kind: match (stat.st_mode as libc::mode_t) & libc::S_IFMT {
-//             libc::S_IFREG => old_io::FileType::RegularFile,
-//             libc::S_IFDIR => old_io::FileType::Directory,
-//             libc::S_IFIFO => old_io::FileType::NamedPipe,
-//             libc::S_IFBLK => old_io::FileType::BlockSpecial,
-//             libc::S_IFLNK => old_io::FileType::Symlink,
-//             _ => old_io::FileType::Unknown,
-//         },
-//         perm: FilePermission::from_bits_truncate(stat.st_mode as u32),
-//         created: mktime(stat.st_ctime as u64, stat.st_ctime_nsec as u64),
-//         modified: mktime(stat.st_mtime as u64, stat.st_mtime_nsec as u64),
-//         accessed: mktime(stat.st_atime as u64, stat.st_atime_nsec as u64),
-//         unstable: UnstableFileStat {
-//             device: stat.st_dev as u64,
-//             inode: stat.st_ino as u64,
-//             rdev: stat.st_rdev as u64,
-//             nlink: stat.st_nlink as u64,
-//             uid: stat.st_uid as u64,
-//             gid: stat.st_gid as u64,
-//             blksize: stat.st_blksize as u64,
-//             blocks: stat.st_blocks as u64,
-//             flags: flags(stat),
-//             gen: gen(stat),
-//         },
-//     }
-// }
+    pub fn modified(&self) -> (i64, u32) {
+        (self.stat.st_mtime as i64, self.stat.st_mtime_nsec as u32)
+    }
+    pub fn changed(&self) -> (i64, u32) {
+        (self.stat.st_ctime as i64, self.stat.st_ctime_nsec as u32)
+    }
+    #[cfg(any(target_os = "macos",
+              target_os = "ios",
+              target_os = "freebsd"))]
+    pub fn created(&self) -> Option<(i64, u32)> {
+        Some((self.stat.st_birthtime as i64,
+              self.stat.st_birthtime_nsec as u32))
+    }
+    #[cfg(not(any(target_os = "macos",
+                  target_os = "ios",
+                  target_os = "freebsd")))]
+    pub fn created(&self) -> Option<(i64, u32)> { None }
+
+    pub fn dev(&self) -> u64 { self.stat.st_dev as u64 }
+    pub fn ino(&self) -> u64 { self.stat.st_ino as u64 }
+    pub fn nlink(&self) -> u64 { self.stat.st_nlink as u64 }
+    pub fn uid(&self) -> u32 { self.stat.st_uid as u32 }
+    pub fn gid(&self) -> u32 { self.stat.st_gid as u32 }
+    pub fn blksize(&self) -> u64 { self.stat.st_blksize as u64 }
+    pub fn blocks(&self) -> u64 { self.stat.st_blocks as u64 }
 }
 
 impl FilePermission {
@@ -129,6 +120,13 @@ impl FilePermission {
             self.mode |= 0o222;
         }
     }
+    pub fn mode(&self) -> u32 { self.mode as u32 }
+    pub fn set_mode(&mut self, mode: u32) {
+        self.mode = (mode & 0o7777) as mode_t;
+    }
+    pub fn from_mode(mode: u32) -> FilePermission {
+        FilePermission { mode: (mode & 0o7777) as mode_t }
+    }
 }
 
 impl Iterator for ReadDir {
@@ -196,7 +194,7 @@ impl OpenOptions {
             flags: 0,
             read: false,
             write: false,
-            mode: libc::S_IRUSR | libc::S_IWUSR,
+            mode: 0o666,
         }
     }
 
@@ -220,6 +218,23 @@ impl OpenOptions {
         self.flag(libc::O_CREAT, create);
     }
 
+    pub fn mode(&mut self, mode: u32) {
+        self.mode = (mode & 0o7777) as mode_t;
+    }
+
+    pub fn exclusive(&mut self, exclusive: bool) {
+        if exclusive {
+            self.flag(libc::O_CREAT, true);
+        }
+        self.flag(libc::O_EXCL, exclusive);
+    }
+
+    // Keeps a terminal which is opened from becoming the controlling
+    // terminal of the process.
+    pub fn noctty(&mut self, noctty: bool) {
+        self.flag(c::O_NOCTTY, noctty);
+    }
+
     fn flag(&mut self, bit: c_int, on: bool) {
         if on {
             self.flags |= bit;
@@ -251,6 +266,10 @@ impl File {
         Ok(FileAttr { stat: stat })
     }
 
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.0.set_nonblocking(nonblocking)
+    }
+
     pub fn fsync(&mut self) -> io::Result<()> {
         try!(call!(unsafe { libc::fsync(self.0.raw()) }));
         Ok(())
@@ -278,17 +297,119 @@ impl File {
         }));
         Ok(())
     }
+
+    pub fn set_perm(&self, perm: FilePermission) -> io::Result<()> {
+        try!(call!(unsafe { libc::fchmod(self.0.raw(), perm.mode) }));
+        Ok(())
+    }
+
+    pub fn chown(&self, uid: u32, gid: u32) -> io::Result<()> {
+        try!(call!(unsafe {
+            c::fchown(self.0.raw(), uid as libc::uid_t, gid as libc::gid_t)
+        }));
+        Ok(())
+    }
+
+    pub fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        let ret = try!(call!(unsafe {
+            c::pread(self.0.raw(), buf.as_mut_ptr() as *mut c_void,
+                     buf.len() as size_t, offset as off_t)
+        }));
+        Ok(ret as usize)
+    }
+
+    pub fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        let ret = try!(call!(unsafe {
+            c::pwrite(self.0.raw(), buf.as_ptr() as *const c_void,
+                      buf.len() as size_t, offset as off_t)
+        }));
+        Ok(ret as usize)
+    }
+
+    pub fn lock(&self, exclusive: bool) -> io::Result<()> {
+        let op = if exclusive {c::LOCK_EX} else {c::LOCK_SH};
+        try!(cvt_r(|| unsafe { c::flock(self.0.raw(), op) }));
+        Ok(())
+    }
+
+    pub fn try_lock(&self, exclusive: bool) -> io::Result<()> {
+        let op = if exclusive {c::LOCK_EX} else {c::LOCK_SH};
+        match unsafe { c::flock(self.0.raw(), op | c::LOCK_NB) } {
+            -1 if os::errno() == libc::EWOULDBLOCK as i32 => Err(locked()),
+            -1 => Err(Error::last_os_error()),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn unlock(&self) -> io::Result<()> {
+        try!(call!(unsafe { c::flock(self.0.raw(), c::LOCK_UN) }));
+        Ok(())
+    }
+
+    pub fn lock_range(&self, exclusive: bool, start: u64, len: u64,
+                      wait: bool) -> io::Result<()> {
+        let ty = if exclusive {c::F_WRLCK} else {c::F_RDLCK};
+        self.setlk(ty, start, len, wait)
+    }
+
+    pub fn unlock_range(&self, start: u64, len: u64) -> io::Result<()> {
+        self.setlk(c::F_UNLCK, start, len, false)
+    }
+
+    fn setlk(&self, ty: libc::c_short, start: u64, len: u64,
+             wait: bool) -> io::Result<()> {
+        // `flock` takes signed offsets, and a range ending past the largest
+        // one can't be described either
+        let max: i64 = Int::max_value();
+        let max = max as u64;
+        if start > max || len > max - start {
+            return Err(Error::new(io::ErrorKind::InvalidInput,
+                                  "lock range is too large", None))
+        }
+        let mut lock: c::flock = unsafe { mem::zeroed() };
+        lock.l_type = ty;
+        lock.l_whence = libc::SEEK_SET as libc::c_short;
+        lock.l_start = start as i64;
+        lock.l_len = len as i64;
+        let fd = self.0.raw();
+        let cmd = setlk_cmd(fd, wait);
+        match cvt_r(|| unsafe { libc::fcntl(fd, cmd, &mut lock as *mut _) }) {
+            Ok(..) => Ok(()),
+            // POSIX allows either EAGAIN or EACCES for a conflicting lock
+            Err(..) if !wait && (os::errno() == libc::EAGAIN as i32 ||
+                                 os::errno() == libc::EACCES as i32) => {
+                Err(locked())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn is_terminal(&self) -> bool { sys::tty::isatty(&self.0) }
+
+    pub fn fd(&self) -> &FileDesc { &self.0 }
+
+    pub fn into_fd(self) -> FileDesc { self.0 }
+}
+
+impl FromInner<FileDesc> for File {
+    fn from_inner(fd: FileDesc) -> File { File(fd) }
 }
 
 impl Read for File {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         self.0.read(buf)
     }
+    fn read_vectored(&mut self, bufs: &mut [&mut [u8]]) -> io::Result<usize> {
+        self.0.read_vectored(bufs)
+    }
 }
 impl Write for File {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
         self.0.write(buf)
     }
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> io::Result<usize> {
+        self.0.write_vectored(bufs)
+    }
 }
 impl Seek for File {
     fn seek(&mut self, pos: SeekPos) -> io::Result<u64> {
@@ -302,13 +423,63 @@ impl Seek for File {
     }
 }
 
+// Open file description locks belong to this `File` rather than to the whole
+// process, so unlike classic POSIX locks they conflict with other `File`s in
+// this process and aren't dropped when some other descriptor for the file is
+// closed. Kernels older than 3.15 don't know them, in which case the classic
+// ones are used instead. Which of the two applies is only checked once.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn setlk_cmd(fd: c_int, wait: bool) -> c_int {
+    static OFD: AtomicUsize = ATOMIC_USIZE_INIT;
+    const UNKNOWN: usize = 0;
+    const SUPPORTED: usize = 1;
+    const UNSUPPORTED: usize = 2;
+
+    let mut state = OFD.load(Ordering::Relaxed);
+    if state == UNKNOWN {
+        // asking whether a lock could be placed has no effect, and fails
+        // with EINVAL only if the command itself is unknown
+        let mut probe: c::flock = unsafe { mem::zeroed() };
+        probe.l_type = c::F_RDLCK;
+        probe.l_whence = libc::SEEK_SET as libc::c_short;
+        let ret = unsafe {
+            libc::fcntl(fd, c::F_OFD_GETLK, &mut probe as *mut _)
+        };
+        state = if ret == -1 && os::errno() == libc::EINVAL as i32 {
+            UNSUPPORTED
+        } else {
+            SUPPORTED
+        };
+        OFD.store(state, Ordering::Relaxed);
+    }
+    match (state == SUPPORTED, wait) {
+        (true, true) => c::F_OFD_SETLKW,
+        (true, false) => c::F_OFD_SETLK,
+        (false, true) => c::F_SETLKW,
+        (false, false) => c::F_SETLK,
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn setlk_cmd(_fd: c_int, wait: bool) -> c_int {
+    if wait {c::F_SETLKW} else {c::F_SETLK}
+}
+
+fn locked() -> Error {
+    Error::new(io::ErrorKind::Locked, "file is locked", None)
+}
+
 fn cstr(path: &Path) -> CString {
     CString::from_slice(path.as_vec())
 }
 
 pub fn mkdir(p: &Path) -> io::Result<()> {
+    mkdir_mode(p, 0o777)
+}
+
+pub fn mkdir_mode(p: &Path, mode: u32) -> io::Result<()> {
     let p = cstr(p);
-    try!(call!(unsafe { libc::mkdir(p.as_ptr(), 0o777) }));
+    try!(call!(unsafe { libc::mkdir(p.as_ptr(), mode as mode_t) }));
     Ok(())
 }
 
@@ -344,18 +515,90 @@ pub fn set_perm(p: &Path, perm: FilePermission) -> io::Result<()> {
     Ok(())
 }
 
+// Creates an unnamed file in the directory `dir`, or returns `None` if the
+// kernel or the filesystem there doesn't support them.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn tmpfile(dir: &Path) -> io::Result<Option<File>> {
+    let dir = cstr(dir);
+    let flags = c::O_TMPFILE | libc::O_RDWR;
+    match unsafe { libc::open(dir.as_ptr(), flags, 0o600 as mode_t) } {
+        // Kernels before 3.11 don't know `O_TMPFILE`, and ignoring the flags
+        // they don't know leaves `O_DIRECTORY | O_RDWR`, hence EISDIR.
+        -1 if os::errno() == libc::EISDIR as i32 ||
+              os::errno() == libc::EINVAL as i32 ||
+              os::errno() == libc::EOPNOTSUPP as i32 => Ok(None),
+        -1 => Err(Error::last_os_error()),
+        fd => Ok(Some(File(FileDesc::new(fd)))),
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub fn tmpfile(_dir: &Path) -> io::Result<Option<File>> { Ok(None) }
+
+pub fn sync_dir(p: &Path) -> io::Result<()> {
+    let mut opts = OpenOptions::new();
+    opts.read(true);
+    let mut dir = try!(File::open(p, &opts));
+    dir.fsync()
+}
+
 pub fn rmdir(p: &Path) -> io::Result<()> {
     let p = cstr(p);
     try!(call!(unsafe { libc::rmdir(p.as_ptr()) }));
     Ok(())
 }
 
-// pub fn chown(p: &Path, uid: int, gid: int) -> IoResult<()> {
-//     let p = cstr(p);
-//     mkerr_libc(retry(|| unsafe {
-//         libc::chown(p.as_ptr(), uid as libc::uid_t, gid as libc::gid_t)
-//     }))
-// }
+pub fn chown(p: &Path, uid: u32, gid: u32) -> io::Result<()> {
+    let p = cstr(p);
+    try!(call!(unsafe {
+        libc::chown(p.as_ptr(), uid as libc::uid_t, gid as libc::gid_t)
+    }));
+    Ok(())
+}
+
+pub fn lchown(p: &Path, uid: u32, gid: u32) -> io::Result<()> {
+    let p = cstr(p);
+    try!(call!(unsafe {
+        c::lchown(p.as_ptr(), uid as libc::uid_t, gid as libc::gid_t)
+    }));
+    Ok(())
+}
+
+pub fn umask(mask: u32) -> u32 {
+    unsafe { c::umask(mask as mode_t) as u32 }
+}
+
+// Linux 4.7 and later show the mask in `/proc/self/status`, which is the
+// only way to read it without changing it.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn read_umask() -> Option<u32> {
+    let mut opts = OpenOptions::new();
+    opts.read(true);
+    let mut status = Vec::new();
+    match File::open(&Path::new("/proc/self/status"), &opts) {
+        Ok(mut file) => {
+            if file.read_to_end(&mut status).is_err() { return None }
+        }
+        Err(..) => return None,
+    }
+    let line = match status.split(|&b| b == b'\n').find(|l| {
+        l.starts_with(b"Umask:")
+    }) {
+        Some(line) => &line[b"Umask:".len()..],
+        None => return None,
+    };
+    let mut mask = 0;
+    for &b in line.iter().filter(|&&b| b != b' ' && b != b'\t') {
+        match b {
+            b'0'...b'7' => mask = mask * 8 + (b - b'0') as u32,
+            _ => return None,
+        }
+    }
+    Some(mask)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub fn read_umask() -> Option<u32> { None }
 
 pub fn readlink(p: &Path) -> io::Result<Path> {
     let c_path = cstr(p);
@@ -402,9 +645,53 @@ pub fn lstat(p: &Path) -> io::Result<FileAttr> {
     Ok(FileAttr { stat: stat })
 }
 
-pub fn utimes(p: &Path, atime: u64, mtime: u64) -> io::Result<()> {
+pub fn file_id(p: &Path) -> io::Result<(u64, u64)> {
+    stat(p).map(|attr| (attr.dev(), attr.ino()))
+}
+
+pub fn canonicalize(p: &Path) -> io::Result<Path> {
+    let p = cstr(p);
+    unsafe {
+        let r = c::realpath(p.as_ptr(), ptr::null_mut());
+        if r.is_null() {
+            return Err(Error::last_os_error())
+        }
+        let ret = Path::new(ffi::c_str_to_bytes(&(r as *const c_char)));
+        libc::free(r as *mut c_void);
+        Ok(ret)
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn set_times(p: &Path, atime: (i64, u32),
+                 mtime: (i64, u32)) -> io::Result<()> {
+    fn timespec((secs, nanos): (i64, u32)) -> libc::timespec {
+        libc::timespec {
+            tv_sec: secs as libc::time_t,
+            tv_nsec: nanos as libc::c_long,
+        }
+    }
+    let p = cstr(p);
+    let buf = [timespec(atime), timespec(mtime)];
+    try!(call!(unsafe {
+        c::utimensat(c::AT_FDCWD, p.as_ptr(), buf.as_ptr(), 0)
+    }));
+    Ok(())
+}
+
+// utimensat isn't available everywhere, so elsewhere the times are only set
+// to the microsecond.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub fn set_times(p: &Path, atime: (i64, u32),
+                 mtime: (i64, u32)) -> io::Result<()> {
+    fn timeval((secs, nanos): (i64, u32)) -> libc::timeval {
+        libc::timeval {
+            tv_sec: secs as libc::time_t,
+            tv_usec: (nanos / 1000) as libc::suseconds_t,
+        }
+    }
     let p = cstr(p);
-    let buf = [super::ms_to_timeval(atime), super::ms_to_timeval(mtime)];
+    let buf = [timeval(atime), timeval(mtime)];
     try!(call!(unsafe { c::utimes(p.as_ptr(), buf.as_ptr()) }));
     Ok(())
 }
@@ -9,21 +9,25 @@
 // except according to those terms.
 
 use libc;
-use os;
 
-use sys::fs::FileDesc;
+use sys::fd::FileDesc;
+use sys::os;
 
 pub type signal = libc::c_int;
 
 pub fn new() -> (signal, signal) {
-    let os::Pipe { reader, writer } = unsafe { os::pipe().unwrap() };
-    (reader, writer)
+    let (reader, writer) = unsafe { os::pipe().unwrap() };
+    reader.set_cloexec().unwrap();
+    writer.set_cloexec().unwrap();
+    (reader.into_raw(), writer.into_raw())
 }
 
 pub fn signal(fd: libc::c_int) {
-    FileDesc::new(fd, false).write(&[0]).ok().unwrap();
+    let fd = FileDesc::new(fd);
+    fd.write(&[0]).unwrap();
+    fd.into_raw();
 }
 
 pub fn close(fd: libc::c_int) {
-    let _fd = FileDesc::new(fd, true);
+    let _fd = FileDesc::new(fd);
 }
@@ -0,0 +1,75 @@
+// Copyright 2015 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use prelude::v1::*;
+
+use io::{self, Error};
+use libc::{self, c_void, size_t, off_t};
+use memory_map::Protection;
+use ptr;
+use sys::fs::File;
+use sys::os;
+
+pub struct MemoryMap {
+    ptr: *mut u8,
+    len: usize,
+}
+
+unsafe impl Send for MemoryMap {}
+unsafe impl Sync for MemoryMap {}
+
+impl MemoryMap {
+    pub fn new(file: &File, prot: Protection, offset: u64, len: usize)
+               -> io::Result<MemoryMap> {
+        let (prot, flags) = match prot {
+            Protection::Read => (libc::PROT_READ, libc::MAP_SHARED),
+            Protection::ReadWrite => (libc::PROT_READ | libc::PROT_WRITE,
+                                      libc::MAP_SHARED),
+            Protection::ReadCopy => (libc::PROT_READ | libc::PROT_WRITE,
+                                     libc::MAP_PRIVATE),
+        };
+        let ptr = unsafe {
+            libc::mmap(ptr::null_mut(), len as size_t, prot, flags,
+                       file.fd().raw(), offset as off_t)
+        };
+        if ptr == libc::MAP_FAILED {
+            Err(Error::last_os_error())
+        } else {
+            Ok(MemoryMap { ptr: ptr as *mut u8, len: len })
+        }
+    }
+
+    pub fn granularity() -> usize { os::page_size() }
+
+    pub fn ptr(&self) -> *mut u8 { self.ptr }
+
+    pub fn len(&self) -> usize { self.len }
+
+    pub fn flush(&self, offset: usize, len: usize) -> io::Result<()> {
+        // msync requires a page aligned address
+        let alignment = offset % MemoryMap::granularity();
+        let offset = offset - alignment;
+        let len = len + alignment;
+        try!(call!(unsafe {
+            libc::msync(self.ptr.offset(offset as isize) as *mut c_void,
+                        len as size_t, libc::MS_SYNC)
+        }));
+        Ok(())
+    }
+}
+
+impl Drop for MemoryMap {
+    fn drop(&mut self) {
+        // `munmap` only fails due to logic errors
+        unsafe {
+            libc::munmap(self.ptr as *mut c_void, self.len as size_t);
+        }
+    }
+}
@@ -33,29 +33,33 @@ macro_rules! call {
     }
 }
 
-// macro_rules! helper_init { (static $name:ident: Helper<$m:ty>) => (
-//     static $name: Helper<$m> = Helper {
-//         lock: ::sync::MUTEX_INIT,
-//         cond: ::sync::CONDVAR_INIT,
-//         chan: ::cell::UnsafeCell { value: 0 as *mut Sender<$m> },
-//         signal: ::cell::UnsafeCell { value: 0 },
-//         initialized: ::cell::UnsafeCell { value: false },
-//         shutdown: ::cell::UnsafeCell { value: false },
-//     };
-// ) }
-//
+macro_rules! helper_init { (static $name:ident: Helper<$m:ty>) => (
+    static $name: Helper<$m> = Helper {
+        lock: ::sync::MUTEX_INIT,
+        cond: ::sync::CONDVAR_INIT,
+        chan: ::cell::UnsafeCell { value: 0 as *mut ::sync::mpsc::Sender<$m> },
+        signal: ::cell::UnsafeCell { value: 0 },
+        initialized: ::cell::UnsafeCell { value: false },
+        shutdown: ::cell::UnsafeCell { value: false },
+    };
+) }
+
 pub mod c;
 pub mod ext;
 pub mod fd;
 pub mod fs;
-// pub mod helper_signal;
+pub mod memory_map;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub mod poll;
+pub mod helper_signal;
 pub mod os;
 pub mod net;
-// pub mod pipe;
-// pub mod process;
+pub mod stdio;
+pub mod pipe;
+pub mod process;
 // pub mod tcp;
-// pub mod timer;
-// pub mod tty;
+pub mod timer;
+pub mod tty;
 // pub mod udp;
 //
 // pub mod addrinfo {
@@ -103,7 +107,7 @@ pub fn decode_error_kind(errno: i32) -> ErrorKind {
         // but different values on others, so we can't use a match
         // clause
         x if x == libc::EAGAIN || x == libc::EWOULDBLOCK =>
-            ErrorKind::ResourceUnavailable,
+            ErrorKind::WouldBlock,
 
         _ => ErrorKind::Other,
     }
@@ -121,22 +125,16 @@ pub fn decode_error_kind(errno: i32) -> ErrorKind {
 //     }
 // }
 
-pub fn ms_to_timeval(ms: u64) -> libc::timeval {
-    libc::timeval {
-        tv_sec: (ms / 1000) as libc::time_t,
-        tv_usec: ((ms % 1000) * 1000) as libc::suseconds_t,
-    }
+pub fn set_nonblocking(fd: libc::c_int, nb: bool) -> io::Result<()> {
+    let set = nb as libc::c_int;
+    try!(cvt_r(|| unsafe { c::ioctl(fd, c::FIONBIO, &set) }));
+    Ok(())
 }
 
-// pub fn wouldblock() -> bool {
-//     let err = os::errno();
-//     err == libc::EWOULDBLOCK as int || err == libc::EAGAIN as int
-// }
-//
-// pub fn set_nonblocking(fd: sock_t, nb: bool) -> IoResult<()> {
-//     let set = nb as libc::c_int;
-//     mkerr_libc(retry(|| unsafe { c::ioctl(fd, c::FIONBIO, &set) }))
-// }
+pub fn is_nonblocking(fd: libc::c_int) -> io::Result<bool> {
+    let flags = try!(cvt_r(|| unsafe { libc::fcntl(fd, libc::F_GETFL) }));
+    Ok(flags & libc::O_NONBLOCK == libc::O_NONBLOCK)
+}
 
 pub fn cvt<T: SignedInt>(t: T) -> io::Result<T> {
     let one: T = Int::one();
@@ -1,13 +1,19 @@
 use prelude::v1::*;
 
+use cmp;
 use ffi;
 use io;
-use libc::{self, c_int, size_t};
+use libc::{self, c_int, size_t, socklen_t};
+use mem;
+use num::Int;
 use str;
 use sys::c;
+use sys::os;
 use net::{SocketAddr, IpAddr};
 use sys::fd::FileDesc;
-use sys_common::AsInner;
+use sys_common::{AsInner, FromInner};
+use sys_common::net::{getsockopt, setsockopt};
+use time::Duration;
 
 pub use sys::{cvt, cvt_r};
 
@@ -34,12 +40,22 @@ impl Socket {
             IpAddr::V4(..) => libc::AF_INET,
             IpAddr::V6(..) => libc::AF_INET6,
         };
+        Socket::new_raw(fam, ty)
+    }
+
+    pub fn new_raw(fam: c_int, ty: c_int) -> io::Result<Socket> {
         unsafe {
             let fd = try!(cvt(libc::socket(fam, ty, 0)));
             Ok(Socket(FileDesc::new(fd)))
         }
     }
 
+    pub fn new_pair(fam: c_int, ty: c_int) -> io::Result<(Socket, Socket)> {
+        let mut fds = [0, 0];
+        try!(cvt(unsafe { c::socketpair(fam, ty, 0, fds.as_mut_ptr()) }));
+        Ok((Socket(FileDesc::new(fds[0])), Socket(FileDesc::new(fds[1]))))
+    }
+
     pub fn accept(&self, storage: *mut libc::sockaddr,
                   len: *mut libc::socklen_t) -> io::Result<Socket> {
         let fd = try!(cvt_r(|| unsafe {
@@ -57,8 +73,149 @@ impl Socket {
     pub fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
         self.0.read(buf)
     }
+
+    pub fn read_vectored(&self, bufs: &mut [&mut [u8]]) -> io::Result<usize> {
+        self.0.read_vectored(bufs)
+    }
+
+    pub fn write_vectored(&self, bufs: &[&[u8]]) -> io::Result<usize> {
+        self.0.write_vectored(bufs)
+    }
+
+    pub fn fd(&self) -> &FileDesc { &self.0 }
+
+    pub fn into_fd(self) -> FileDesc { self.0 }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.0.set_nonblocking(nonblocking)
+    }
+
+    pub fn is_nonblocking(&self) -> io::Result<bool> {
+        self.0.is_nonblocking()
+    }
+
+    pub fn connect_timeout(&self, addr: *const libc::sockaddr, len: socklen_t,
+                           timeout: Duration) -> io::Result<()> {
+        // Make sure the call to connect() doesn't block, and be sure to turn
+        // blocking I/O back on regardless of how the connect went.
+        try!(self.set_nonblocking(true));
+        let ret = self.connect_nonblocking(addr, len, timeout);
+        try!(self.set_nonblocking(false));
+        ret
+    }
+
+    fn connect_nonblocking(&self, addr: *const libc::sockaddr, len: socklen_t,
+                           timeout: Duration) -> io::Result<()> {
+        if unsafe { libc::connect(self.0.raw(), addr, len) } == 0 {
+            return Ok(())
+        }
+        let err = os::errno();
+        if err != libc::EINPROGRESS as i32 {
+            return Err(io::Error::from_os_error(err))
+        }
+
+        // Round up so sub-millisecond timeouts don't turn into a non-blocking
+        // poll.
+        let timeout = cmp::max(timeout.num_milliseconds(), 1) as u64;
+        let mut pollfd = c::pollfd {
+            fd: self.0.raw(),
+            events: c::POLLOUT,
+            revents: 0,
+        };
+        let start = now_ms();
+        loop {
+            // Recalculate the timeout each iteration in case poll() was
+            // interrupted by a signal.
+            let elapsed = now_ms().saturating_sub(start);
+            if elapsed >= timeout {
+                return Err(timed_out())
+            }
+            let max: c_int = Int::max_value();
+            let remaining = cmp::min(timeout - elapsed, max as u64) as c_int;
+            match unsafe { c::poll(&mut pollfd, 1, remaining) } {
+                -1 if os::errno() == libc::EINTR as i32 => {}
+                -1 => return Err(io::Error::last_os_error()),
+                0 => return Err(timed_out()),
+                _ => break,
+            }
+        }
+
+        // The socket is writable once the connect finished, but whether it
+        // succeeded has to be read out of SO_ERROR.
+        let err: c_int = try!(getsockopt(self, libc::SOL_SOCKET,
+                                         libc::SO_ERROR));
+        if err == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::from_os_error(err))
+        }
+    }
+
+    pub fn set_timeout(&self, dur: Option<Duration>,
+                       kind: c_int) -> io::Result<()> {
+        let timeout = match dur {
+            Some(dur) => {
+                let secs = dur.num_seconds();
+                let usecs = (dur - Duration::seconds(secs)).num_microseconds()
+                                                           .unwrap();
+                let mut timeout = libc::timeval {
+                    tv_sec: secs as libc::time_t,
+                    tv_usec: usecs as libc::suseconds_t,
+                };
+                // A zeroed timeval means "no timeout", so round tiny
+                // durations up instead.
+                if timeout.tv_sec == 0 && timeout.tv_usec == 0 {
+                    timeout.tv_usec = 1;
+                }
+                timeout
+            }
+            None => libc::timeval { tv_sec: 0, tv_usec: 0 },
+        };
+        setsockopt(self, libc::SOL_SOCKET, kind, timeout)
+    }
+
+    pub fn timeout(&self, kind: c_int) -> io::Result<Option<Duration>> {
+        let timeout: libc::timeval = try!(getsockopt(self, libc::SOL_SOCKET,
+                                                     kind));
+        if timeout.tv_sec == 0 && timeout.tv_usec == 0 {
+            Ok(None)
+        } else {
+            Ok(Some(Duration::seconds(timeout.tv_sec as i64) +
+                    Duration::microseconds(timeout.tv_usec as i64)))
+        }
+    }
+}
+
+fn timed_out() -> io::Error {
+    io::Error::new(io::ErrorKind::TimedOut, "connection timed out", None)
+}
+
+// Reads a clock which isn't moved by changes to the system time, so that
+// setting the clock back can't stretch a timeout.
+#[cfg(not(any(target_os = "macos", target_os = "ios")))]
+fn now_ms() -> u64 {
+    unsafe {
+        let mut now: libc::timespec = mem::zeroed();
+        assert_eq!(c::clock_gettime(c::CLOCK_MONOTONIC, &mut now), 0);
+        (now.tv_sec as u64) * 1000 + (now.tv_nsec as u64) / 1000000
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+fn now_ms() -> u64 {
+    unsafe {
+        let mut info: c::mach_timebase_info = mem::zeroed();
+        assert_eq!(c::mach_timebase_info(&mut info), 0);
+        let ns = c::mach_absolute_time() * info.numer as u64 /
+                 info.denom as u64;
+        ns / 1000000
+    }
 }
 
 impl AsInner<c_int> for Socket {
     fn as_inner(&self) -> &c_int { self.0.as_inner() }
 }
+
+impl FromInner<FileDesc> for Socket {
+    fn from_inner(fd: FileDesc) -> Socket { Socket(fd) }
+}
@@ -11,7 +11,7 @@
 //! Implementation of `std::os` functionality for unix systems
 
 use prelude::v1::*;
-use os::unix::*;
+use os::unix::{OsStrExt, OsStringExt};
 
 use error::Error as StdError;
 use ffi::{self, CString, OsString, OsStr, AsOsStr};
@@ -24,6 +24,7 @@ use ptr;
 use slice;
 use str;
 use sys::c;
+use sys::fd::FileDesc;
 use vec;
 
 const BUF_BYTES: usize = 2048;
@@ -95,6 +96,10 @@ pub fn error_string(errno: i32) -> String {
     }
 }
 
+pub fn getpid() -> u32 {
+    unsafe { libc::getpid() as u32 }
+}
+
 pub fn getcwd() -> io::Result<Path> {
     let mut buf = [0 as c_char; BUF_BYTES];
     unsafe {
@@ -395,14 +400,45 @@ pub fn unsetenv(n: &OsStr) {
     }
 }
 
-// pub unsafe fn pipe() -> IoResult<(FileDesc, FileDesc)> {
-//     let mut fds = [0; 2];
-//     if libc::pipe(fds.as_mut_ptr()) == 0 {
-//         Ok((FileDesc::new(fds[0], true), FileDesc::new(fds[1], true)))
-//     } else {
-//         Err(super::last_error())
-//     }
-// }
+pub unsafe fn pipe() -> io::Result<(FileDesc, FileDesc)> {
+    let mut fds = [0; 2];
+    if libc::pipe(fds.as_mut_ptr()) == 0 {
+        Ok((FileDesc::new(fds[0]), FileDesc::new(fds[1])))
+    } else {
+        Err(Error::last_os_error())
+    }
+}
+
+pub fn home_dir() -> Option<Path> {
+    return getenv("HOME".as_os_str()).and_then(|home| {
+        let home = home.into_vec();
+        if home.is_empty() {None} else {Some(home)}
+    }).or_else(|| unsafe {
+        fallback()
+    }).map(Path::new);
+
+    #[cfg(any(target_os = "ios", target_os = "dragonfly"))]
+    unsafe fn fallback() -> Option<Vec<u8>> { None }
+    #[cfg(not(any(target_os = "ios", target_os = "dragonfly")))]
+    unsafe fn fallback() -> Option<Vec<u8>> {
+        let amt = match libc::sysconf(c::_SC_GETPW_R_SIZE_MAX) {
+            n if n < 0 => 512 as usize,
+            n => n as usize,
+        };
+        let me = libc::getuid();
+        let mut buf: Vec<c_char> = Vec::with_capacity(amt);
+        let mut passwd: c::passwd = mem::zeroed();
+        let mut result = ptr::null_mut();
+        match c::getpwuid_r(me, &mut passwd, buf.as_mut_ptr(),
+                            buf.capacity() as libc::size_t, &mut result) {
+            0 if !result.is_null() => {}
+            _ => return None
+        }
+        let ptr = passwd.pw_dir as *const _;
+        if ptr.is_null() { return None }
+        Some(ffi::c_str_to_bytes(&ptr).to_vec())
+    }
+}
 
 pub fn page_size() -> usize {
     unsafe {
@@ -8,202 +8,206 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+//! Unix domain sockets
+
 use prelude::v1::*;
 
-use ffi::CString;
-use libc;
+use io::{self, Error, ErrorKind};
+use libc::{self, c_int, c_char, c_void, size_t, socklen_t};
 use mem;
-use sync::{Arc, Mutex};
-use sync::atomic::{AtomicBool, Ordering};
-use old_io::{self, IoResult, IoError};
+use net::Shutdown;
+use sys::net::{cvt, cvt_r, Socket};
+use sys_common::{AsInner, FromInner};
+
+////////////////////////////////////////////////////////////////////////////////
+// sockaddr_un bindings
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct SocketAddr {
+    addr: libc::sockaddr_un,
+    len: socklen_t,
+}
 
-use sys::{self, timer, retry, c, set_nonblocking, wouldblock};
-use sys::fs::{fd_t, FileDesc};
-use sys_common::net::*;
-use sys_common::net::SocketStatus::*;
-use sys_common::{eof, mkerr_libc};
+pub enum AddressKind<'a> {
+    Unnamed,
+    Pathname(&'a [u8]),
+    Abstract(&'a [u8]),
+}
 
-fn unix_socket(ty: libc::c_int) -> IoResult<fd_t> {
-    match unsafe { libc::socket(libc::AF_UNIX, ty, 0) } {
-        -1 => Err(super::last_error()),
-        fd => Ok(fd)
+// The offset of `sun_path` differs across platforms (BSDs have a leading
+// `sun_len` byte), so compute it from an actual instance of the structure.
+fn sun_path_offset() -> usize {
+    unsafe {
+        let addr: libc::sockaddr_un = mem::zeroed();
+        let base = &addr as *const _ as usize;
+        let path = &addr.sun_path as *const _ as usize;
+        path - base
     }
 }
 
-fn addr_to_sockaddr_un(addr: &CString,
-                       storage: &mut libc::sockaddr_storage)
-                       -> IoResult<libc::socklen_t> {
-    // the sun_path length is limited to SUN_LEN (with null)
-    assert!(mem::size_of::<libc::sockaddr_storage>() >=
-            mem::size_of::<libc::sockaddr_un>());
-    let s = unsafe { &mut *(storage as *mut _ as *mut libc::sockaddr_un) };
-
-    let len = addr.len();
-    if len > s.sun_path.len() - 1 {
-        return Err(IoError {
-            kind: old_io::InvalidInput,
-            desc: "invalid argument: path must be smaller than SUN_LEN",
-            detail: None,
-        })
+impl SocketAddr {
+    fn new<F>(f: F) -> io::Result<SocketAddr>
+        where F: FnOnce(*mut libc::sockaddr, *mut socklen_t) -> c_int
+    {
+        unsafe {
+            let mut addr: libc::sockaddr_un = mem::zeroed();
+            let mut len = mem::size_of::<libc::sockaddr_un>() as socklen_t;
+            try!(cvt(f(&mut addr as *mut _ as *mut _, &mut len)));
+            SocketAddr::from_parts(addr, len)
+        }
     }
-    s.sun_family = libc::AF_UNIX as libc::sa_family_t;
-    for (slot, value) in s.sun_path.iter_mut().zip(addr.iter()) {
-        *slot = *value;
+
+    fn from_parts(addr: libc::sockaddr_un,
+                  mut len: socklen_t) -> io::Result<SocketAddr> {
+        if len == 0 {
+            // When a datagram is received from an unnamed unix socket linux
+            // reports a zero-length address.
+            len = sun_path_offset() as socklen_t;
+        } else if addr.sun_family != libc::AF_UNIX as libc::sa_family_t {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                  "socket is not a unix domain socket", None))
+        }
+        Ok(SocketAddr { addr: addr, len: len })
     }
 
-    // count the null terminator
-    let len = mem::size_of::<libc::sa_family_t>() + len + 1;
-    return Ok(len as libc::socklen_t);
-}
+    pub fn from_pathname(path: &[u8]) -> io::Result<SocketAddr> {
+        if path.iter().any(|b| *b == 0) {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                  "paths may not contain interior null bytes",
+                                  None))
+        }
+        SocketAddr::from_bytes(path, 0)
+    }
 
-struct Inner {
-    fd: fd_t,
+    pub fn from_abstract(name: &[u8]) -> io::Result<SocketAddr> {
+        SocketAddr::from_bytes(name, 1)
+    }
 
-    // Unused on Linux, where this lock is not necessary.
-    #[allow(dead_code)]
-    lock: Mutex<()>,
-}
+    fn from_bytes(bytes: &[u8], start: usize) -> io::Result<SocketAddr> {
+        let mut addr: libc::sockaddr_un = unsafe { mem::zeroed() };
+        addr.sun_family = libc::AF_UNIX as libc::sa_family_t;
 
-impl Inner {
-    fn new(fd: fd_t) -> Inner {
-        Inner { fd: fd, lock: Mutex::new(()) }
+        // Pathnames need room for their nul terminator and abstract names
+        // need room for their leading nul byte.
+        if bytes.len() + 1 > addr.sun_path.len() {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                  "path must be shorter than SUN_LEN", None))
+        }
+        for (slot, value) in addr.sun_path[start..].iter_mut()
+                                                    .zip(bytes.iter()) {
+            *slot = *value as c_char;
+        }
+        let len = sun_path_offset() + bytes.len() + 1;
+        Ok(SocketAddr { addr: addr, len: len as socklen_t })
     }
-}
-
-impl Drop for Inner {
-    fn drop(&mut self) { unsafe { let _ = libc::close(self.fd); } }
-}
 
-fn connect(addr: &CString, ty: libc::c_int,
-           timeout: Option<u64>) -> IoResult<Inner> {
-    let mut storage = unsafe { mem::zeroed() };
-    let len = try!(addr_to_sockaddr_un(addr, &mut storage));
-    let inner = Inner::new(try!(unix_socket(ty)));
-    let addrp = &storage as *const _ as *const libc::sockaddr;
-
-    match timeout {
-        None => {
-            match retry(|| unsafe { libc::connect(inner.fd, addrp, len) }) {
-                -1 => Err(super::last_error()),
-                _  => Ok(inner)
+    pub fn kind(&self) -> AddressKind {
+        let len = self.len as usize - sun_path_offset();
+        let path: &[u8] = unsafe { mem::transmute(&self.addr.sun_path[..]) };
+
+        if len == 0 {
+            AddressKind::Unnamed
+        } else if path[0] == 0 {
+            // Abstract addresses are a linux extension, everywhere else a
+            // leading nul byte just means the address is empty.
+            if cfg!(any(target_os = "linux", target_os = "android")) {
+                AddressKind::Abstract(&path[1..len])
+            } else {
+                AddressKind::Unnamed
             }
-        }
-        Some(timeout_ms) => {
-            try!(connect_timeout(inner.fd, addrp, len, timeout_ms));
-            Ok(inner)
+        } else {
+            // The reported length may or may not include the nul terminator
+            let path = &path[..len];
+            let end = path.iter().position(|b| *b == 0).unwrap_or(len);
+            AddressKind::Pathname(&path[..end])
         }
     }
-}
 
-fn bind(addr: &CString, ty: libc::c_int) -> IoResult<Inner> {
-    let mut storage = unsafe { mem::zeroed() };
-    let len = try!(addr_to_sockaddr_un(addr, &mut storage));
-    let inner = Inner::new(try!(unix_socket(ty)));
-    let addrp = &storage as *const _ as *const libc::sockaddr;
-    match unsafe {
-        libc::bind(inner.fd, addrp, len)
-    } {
-        -1 => Err(super::last_error()),
-        _  => Ok(inner)
+    fn as_ptr(&self) -> *const libc::sockaddr {
+        &self.addr as *const _ as *const libc::sockaddr
     }
 }
 
+fn shutdown(sock: &Socket, how: Shutdown) -> io::Result<()> {
+    use libc::consts::os::bsd44::SHUT_RDWR;
+
+    let how = match how {
+        Shutdown::Write => libc::SHUT_WR,
+        Shutdown::Read => libc::SHUT_RD,
+        Shutdown::Both => SHUT_RDWR,
+    };
+    try!(cvt(unsafe { libc::shutdown(*sock.as_inner(), how) }));
+    Ok(())
+}
+
+fn send(sock: &Socket, buf: &[u8]) -> io::Result<usize> {
+    let ret = try!(cvt(unsafe {
+        libc::send(*sock.as_inner(),
+                   buf.as_ptr() as *const c_void,
+                   buf.len() as size_t,
+                   0)
+    }));
+    Ok(ret as usize)
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 // Unix Streams
 ////////////////////////////////////////////////////////////////////////////////
 
 pub struct UnixStream {
-    inner: Arc<Inner>,
-    read_deadline: u64,
-    write_deadline: u64,
+    inner: Socket,
 }
 
 impl UnixStream {
-    pub fn connect(addr: &CString,
-                   timeout: Option<u64>) -> IoResult<UnixStream> {
-        connect(addr, libc::SOCK_STREAM, timeout).map(|inner| {
-            UnixStream::new(Arc::new(inner))
-        })
+    pub fn connect(addr: &SocketAddr) -> io::Result<UnixStream> {
+        let inner = try!(Socket::new_raw(libc::AF_UNIX, libc::SOCK_STREAM));
+        try!(cvt_r(|| unsafe {
+            libc::connect(*inner.as_inner(), addr.as_ptr(), addr.len)
+        }));
+        Ok(UnixStream { inner: inner })
     }
 
-    fn new(inner: Arc<Inner>) -> UnixStream {
-        UnixStream {
-            inner: inner,
-            read_deadline: 0,
-            write_deadline: 0,
-        }
+    pub fn pair() -> io::Result<(UnixStream, UnixStream)> {
+        let (a, b) = try!(Socket::new_pair(libc::AF_UNIX, libc::SOCK_STREAM));
+        Ok((UnixStream { inner: a }, UnixStream { inner: b }))
     }
 
-    pub fn fd(&self) -> fd_t { self.inner.fd }
-
-    #[cfg(target_os = "linux")]
-    fn lock_nonblocking(&self) {}
-
-    #[cfg(not(target_os = "linux"))]
-    fn lock_nonblocking<'a>(&'a self) -> Guard<'a> {
-        let ret = Guard {
-            fd: self.fd(),
-            guard: unsafe { self.inner.lock.lock().unwrap() },
-        };
-        assert!(set_nonblocking(self.fd(), true).is_ok());
-        ret
-    }
-
-    pub fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> {
-        let fd = self.fd();
-        let dolock = |&:| self.lock_nonblocking();
-        let doread = |&mut: nb| unsafe {
-            let flags = if nb {c::MSG_DONTWAIT} else {0};
-            libc::recv(fd,
-                       buf.as_mut_ptr() as *mut libc::c_void,
-                       buf.len() as libc::size_t,
-                       flags) as libc::c_int
-        };
-        read(fd, self.read_deadline, dolock, doread)
-    }
-
-    pub fn write(&mut self, buf: &[u8]) -> IoResult<()> {
-        let fd = self.fd();
-        let dolock = |&: | self.lock_nonblocking();
-        let dowrite = |&: nb: bool, buf: *const u8, len: uint| unsafe {
-            let flags = if nb {c::MSG_DONTWAIT} else {0};
-            libc::send(fd,
-                       buf as *const _,
-                       len as libc::size_t,
-                       flags) as i64
-        };
-        match write(fd, self.write_deadline, buf, true, dolock, dowrite) {
-            Ok(_) => Ok(()),
-            Err(e) => Err(e)
-        }
+    pub fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
     }
 
-    pub fn close_write(&mut self) -> IoResult<()> {
-        mkerr_libc(unsafe { libc::shutdown(self.fd(), libc::SHUT_WR) })
+    pub fn write(&self, buf: &[u8]) -> io::Result<usize> {
+        send(&self.inner, buf)
     }
 
-    pub fn close_read(&mut self) -> IoResult<()> {
-        mkerr_libc(unsafe { libc::shutdown(self.fd(), libc::SHUT_RD) })
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        SocketAddr::new(|addr, len| unsafe {
+            libc::getsockname(*self.inner.as_inner(), addr, len)
+        })
     }
 
-    pub fn set_timeout(&mut self, timeout: Option<u64>) {
-        let deadline = timeout.map(|a| timer::now() + a).unwrap_or(0);
-        self.read_deadline = deadline;
-        self.write_deadline = deadline;
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        SocketAddr::new(|addr, len| unsafe {
+            libc::getpeername(*self.inner.as_inner(), addr, len)
+        })
     }
 
-    pub fn set_read_timeout(&mut self, timeout: Option<u64>) {
-        self.read_deadline = timeout.map(|a| timer::now() + a).unwrap_or(0);
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        shutdown(&self.inner, how)
     }
 
-    pub fn set_write_timeout(&mut self, timeout: Option<u64>) {
-        self.write_deadline = timeout.map(|a| timer::now() + a).unwrap_or(0);
+    pub fn socket(&self) -> &Socket { &self.inner }
+
+    pub fn into_socket(self) -> Socket { self.inner }
+
+    pub fn duplicate(&self) -> io::Result<UnixStream> {
+        self.inner.duplicate().map(|s| UnixStream { inner: s })
     }
 }
 
-impl Clone for UnixStream {
-    fn clone(&self) -> UnixStream {
-        UnixStream::new(self.inner.clone())
+impl FromInner<Socket> for UnixStream {
+    fn from_inner(socket: Socket) -> UnixStream {
+        UnixStream { inner: socket }
     }
 }
 
@@ -212,115 +216,141 @@ impl Clone for UnixStream {
 ////////////////////////////////////////////////////////////////////////////////
 
 pub struct UnixListener {
-    inner: Inner,
-    path: CString,
+    inner: Socket,
 }
 
-// we currently own the CString, so these impls should be safe
-unsafe impl Send for UnixListener {}
-unsafe impl Sync for UnixListener {}
-
 impl UnixListener {
-    pub fn bind(addr: &CString) -> IoResult<UnixListener> {
-        bind(addr, libc::SOCK_STREAM).map(|fd| {
-            UnixListener { inner: fd, path: addr.clone() }
+    pub fn bind(addr: &SocketAddr) -> io::Result<UnixListener> {
+        let inner = try!(Socket::new_raw(libc::AF_UNIX, libc::SOCK_STREAM));
+        try!(cvt(unsafe {
+            libc::bind(*inner.as_inner(), addr.as_ptr(), addr.len)
+        }));
+        try!(cvt(unsafe { libc::listen(*inner.as_inner(), 128) }));
+        Ok(UnixListener { inner: inner })
+    }
+
+    pub fn accept(&self) -> io::Result<(UnixStream, SocketAddr)> {
+        let mut storage: libc::sockaddr_un = unsafe { mem::zeroed() };
+        let mut len = mem::size_of_val(&storage) as socklen_t;
+        let sock = try!(self.inner.accept(&mut storage as *mut _ as *mut _,
+                                          &mut len));
+        let addr = try!(SocketAddr::from_parts(storage, len));
+        Ok((UnixStream { inner: sock }, addr))
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        SocketAddr::new(|addr, len| unsafe {
+            libc::getsockname(*self.inner.as_inner(), addr, len)
         })
     }
 
-    pub fn fd(&self) -> fd_t { self.inner.fd }
-
-    pub fn listen(self) -> IoResult<UnixAcceptor> {
-        match unsafe { libc::listen(self.fd(), 128) } {
-            -1 => Err(super::last_error()),
-
-            _ => {
-                let (reader, writer) = try!(unsafe { sys::os::pipe() });
-                try!(set_nonblocking(reader.fd(), true));
-                try!(set_nonblocking(writer.fd(), true));
-                try!(set_nonblocking(self.fd(), true));
-                Ok(UnixAcceptor {
-                    inner: Arc::new(AcceptorInner {
-                        listener: self,
-                        reader: reader,
-                        writer: writer,
-                        closed: AtomicBool::new(false),
-                    }),
-                    deadline: 0,
-                })
-            }
-        }
+    pub fn socket(&self) -> &Socket { &self.inner }
+
+    pub fn into_socket(self) -> Socket { self.inner }
+
+    pub fn duplicate(&self) -> io::Result<UnixListener> {
+        self.inner.duplicate().map(|s| UnixListener { inner: s })
     }
 }
 
-pub struct UnixAcceptor {
-    inner: Arc<AcceptorInner>,
-    deadline: u64,
+impl FromInner<Socket> for UnixListener {
+    fn from_inner(socket: Socket) -> UnixListener {
+        UnixListener { inner: socket }
+    }
 }
 
-struct AcceptorInner {
-    listener: UnixListener,
-    reader: FileDesc,
-    writer: FileDesc,
-    closed: AtomicBool,
+////////////////////////////////////////////////////////////////////////////////
+// Unix Datagrams
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct UnixDatagram {
+    inner: Socket,
 }
 
-impl UnixAcceptor {
-    pub fn fd(&self) -> fd_t { self.inner.listener.fd() }
-
-    pub fn accept(&mut self) -> IoResult<UnixStream> {
-        let deadline = if self.deadline == 0 {None} else {Some(self.deadline)};
-
-        while !self.inner.closed.load(Ordering::SeqCst) {
-            unsafe {
-                let mut storage: libc::sockaddr_storage = mem::zeroed();
-                let storagep = &mut storage as *mut libc::sockaddr_storage;
-                let size = mem::size_of::<libc::sockaddr_storage>();
-                let mut size = size as libc::socklen_t;
-                match retry(|| {
-                    libc::accept(self.fd(),
-                                 storagep as *mut libc::sockaddr,
-                                 &mut size as *mut libc::socklen_t) as libc::c_int
-                }) {
-                    -1 if wouldblock() => {}
-                    -1 => return Err(super::last_error()),
-                    fd => return Ok(UnixStream::new(Arc::new(Inner::new(fd)))),
-                }
-            }
-            try!(await(&[self.fd(), self.inner.reader.fd()],
-                       deadline, Readable));
-        }
+impl UnixDatagram {
+    pub fn bind(addr: &SocketAddr) -> io::Result<UnixDatagram> {
+        let ret = try!(UnixDatagram::unbound());
+        try!(cvt(unsafe {
+            libc::bind(*ret.inner.as_inner(), addr.as_ptr(), addr.len)
+        }));
+        Ok(ret)
+    }
 
-        Err(eof())
+    pub fn unbound() -> io::Result<UnixDatagram> {
+        let inner = try!(Socket::new_raw(libc::AF_UNIX, libc::SOCK_DGRAM));
+        Ok(UnixDatagram { inner: inner })
     }
 
-    pub fn set_timeout(&mut self, timeout: Option<u64>) {
-        self.deadline = timeout.map(|a| timer::now() + a).unwrap_or(0);
+    pub fn pair() -> io::Result<(UnixDatagram, UnixDatagram)> {
+        let (a, b) = try!(Socket::new_pair(libc::AF_UNIX, libc::SOCK_DGRAM));
+        Ok((UnixDatagram { inner: a }, UnixDatagram { inner: b }))
     }
 
-    pub fn close_accept(&mut self) -> IoResult<()> {
-        self.inner.closed.store(true, Ordering::SeqCst);
-        let fd = FileDesc::new(self.inner.writer.fd(), false);
-        match fd.write(&[0]) {
-            Ok(..) => Ok(()),
-            Err(..) if wouldblock() => Ok(()),
-            Err(e) => Err(e),
-        }
+    pub fn connect(&self, addr: &SocketAddr) -> io::Result<()> {
+        try!(cvt(unsafe {
+            libc::connect(*self.inner.as_inner(), addr.as_ptr(), addr.len)
+        }));
+        Ok(())
+    }
+
+    pub fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        let mut count = 0;
+        let addr = try!(SocketAddr::new(|addr, len| unsafe {
+            let n = libc::recvfrom(*self.inner.as_inner(),
+                                   buf.as_mut_ptr() as *mut c_void,
+                                   buf.len() as size_t,
+                                   0, addr, len);
+            if n < 0 { return -1 }
+            count = n as usize;
+            0
+        }));
+        Ok((count, addr))
     }
-}
 
-impl Clone for UnixAcceptor {
-    fn clone(&self) -> UnixAcceptor {
-        UnixAcceptor { inner: self.inner.clone(), deadline: 0 }
+    pub fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+
+    pub fn send_to(&self, buf: &[u8], dst: &SocketAddr) -> io::Result<usize> {
+        let ret = try!(cvt(unsafe {
+            libc::sendto(*self.inner.as_inner(),
+                         buf.as_ptr() as *const c_void, buf.len() as size_t,
+                         0, dst.as_ptr(), dst.len)
+        }));
+        Ok(ret as usize)
+    }
+
+    pub fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        send(&self.inner, buf)
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        SocketAddr::new(|addr, len| unsafe {
+            libc::getsockname(*self.inner.as_inner(), addr, len)
+        })
+    }
+
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        SocketAddr::new(|addr, len| unsafe {
+            libc::getpeername(*self.inner.as_inner(), addr, len)
+        })
+    }
+
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        shutdown(&self.inner, how)
+    }
+
+    pub fn socket(&self) -> &Socket { &self.inner }
+
+    pub fn into_socket(self) -> Socket { self.inner }
+
+    pub fn duplicate(&self) -> io::Result<UnixDatagram> {
+        self.inner.duplicate().map(|s| UnixDatagram { inner: s })
     }
 }
 
-impl Drop for UnixListener {
-    fn drop(&mut self) {
-        // Unlink the path to the socket to ensure that it doesn't linger. We're
-        // careful to unlink the path before we close the file descriptor to
-        // prevent races where we unlink someone else's path.
-        unsafe {
-            let _ = libc::unlink(self.path.as_ptr());
-        }
+impl FromInner<Socket> for UnixDatagram {
+    fn from_inner(socket: Socket) -> UnixDatagram {
+        UnixDatagram { inner: socket }
     }
 }
@@ -0,0 +1,133 @@
+// Copyright 2015 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use prelude::v1::*;
+
+use cmp;
+use io::{self, Interest, Trigger};
+use libc::{self, c_int};
+use num::Int;
+use sys::c;
+use os::unix::Fd;
+use sys::fd::FileDesc;
+use sys::os;
+use time::Duration;
+
+pub struct Poll(FileDesc);
+
+pub struct Events(Vec<c::epoll_event>);
+
+#[derive(Copy, Clone)]
+pub struct Event(c::epoll_event);
+
+impl Poll {
+    pub fn new() -> io::Result<Poll> {
+        let fd = try!(call!(unsafe { c::epoll_create1(c::EPOLL_CLOEXEC) }));
+        Ok(Poll(FileDesc::new(fd)))
+    }
+
+    pub fn register(&self, fd: Fd, token: usize, interest: Interest,
+                    trigger: Trigger) -> io::Result<()> {
+        self.ctl(c::EPOLL_CTL_ADD, fd, flags(interest, trigger), token)
+    }
+
+    pub fn reregister(&self, fd: Fd, token: usize, interest: Interest,
+                      trigger: Trigger) -> io::Result<()> {
+        self.ctl(c::EPOLL_CTL_MOD, fd, flags(interest, trigger), token)
+    }
+
+    pub fn deregister(&self, fd: Fd) -> io::Result<()> {
+        // Kernels before 2.6.9 require a non-null event even though it is
+        // ignored, so always pass one.
+        self.ctl(c::EPOLL_CTL_DEL, fd, 0, 0)
+    }
+
+    fn ctl(&self, op: c_int, fd: Fd, events: u32,
+           token: usize) -> io::Result<()> {
+        let mut event = c::epoll_event { events: events, data: token as u64 };
+        try!(call!(unsafe {
+            c::epoll_ctl(self.0.raw(), op, fd, &mut event)
+        }));
+        Ok(())
+    }
+
+    pub fn wait(&self, events: &mut Events,
+                timeout: Option<Duration>) -> io::Result<usize> {
+        let timeout = match timeout {
+            Some(dur) => try!(timeout_ms(dur)),
+            None => -1,
+        };
+        let events = &mut events.0;
+        let cap = events.capacity();
+        let max: c_int = Int::max_value();
+        let n = unsafe {
+            c::epoll_wait(self.0.raw(), events.as_mut_ptr(),
+                          cmp::min(cap, max as usize) as c_int, timeout)
+        };
+        let n = match n {
+            // A signal arriving just means that nothing became ready yet
+            -1 if os::errno() == libc::EINTR as i32 => 0,
+            -1 => return Err(io::Error::last_os_error()),
+            n => n as usize,
+        };
+        unsafe { events.set_len(n) }
+        Ok(n)
+    }
+}
+
+fn flags(interest: Interest, trigger: Trigger) -> u32 {
+    let interest = match interest {
+        Interest::Readable => c::EPOLLIN | c::EPOLLRDHUP,
+        Interest::Writable => c::EPOLLOUT,
+        Interest::Both => c::EPOLLIN | c::EPOLLRDHUP | c::EPOLLOUT,
+    };
+    match trigger {
+        Trigger::Level => interest,
+        Trigger::Edge => interest | c::EPOLLET,
+    }
+}
+
+// epoll_wait only has millisecond resolution, so round up to make sure that
+// a short timeout doesn't turn into a busy loop.
+fn timeout_ms(dur: Duration) -> io::Result<c_int> {
+    if dur < Duration::zero() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                  "timeout must not be negative", None))
+    }
+    let mut ms = dur.num_milliseconds();
+    if Duration::milliseconds(ms) < dur {
+        ms += 1;
+    }
+    let max: c_int = Int::max_value();
+    Ok(cmp::min(ms, max as i64) as c_int)
+}
+
+impl Events {
+    pub fn with_capacity(cap: usize) -> Events {
+        // epoll_wait fails with EINVAL when asked for zero events
+        Events(Vec::with_capacity(cmp::max(cap, 1)))
+    }
+
+    pub fn capacity(&self) -> usize { self.0.capacity() }
+    pub fn len(&self) -> usize { self.0.len() }
+    pub fn get(&self, idx: usize) -> Option<Event> {
+        self.0.get(idx).map(|e| Event(*e))
+    }
+}
+
+impl Event {
+    pub fn token(&self) -> usize { self.0.data as usize }
+    pub fn is_readable(&self) -> bool { self.0.events & c::EPOLLIN != 0 }
+    pub fn is_writable(&self) -> bool { self.0.events & c::EPOLLOUT != 0 }
+    pub fn is_hangup(&self) -> bool {
+        self.0.events & (c::EPOLLHUP | c::EPOLLRDHUP) != 0
+    }
+    pub fn is_error(&self) -> bool { self.0.events & c::EPOLLERR != 0 }
+}
@@ -9,36 +9,191 @@
 // except according to those terms.
 
 use prelude::v1::*;
-use self::Req::*;
 
 use collections::HashMap;
-use collections::hash_map::Hasher;
-use ffi::CString;
-use hash::Hash;
-use old_io::process::{ProcessExit, ExitStatus, ExitSignal};
-use old_io::{self, IoResult, IoError, EndOfFile};
-use libc::{self, pid_t, c_void, c_int};
+use ffi::{OsString, OsStr, CString};
+use fmt;
+use io::prelude::*;
+use io::{self, Error};
+use libc::{self, pid_t, c_void, c_int, gid_t, uid_t};
 use mem;
-use os;
-use path::BytesContainer;
+use os::unix::OsStrExt;
+use path::{Path, GenericPath};
 use ptr;
-use sync::mpsc::{channel, Sender, Receiver};
-use sys::fs::FileDesc;
-use sys::{self, retry, c, wouldblock, set_nonblocking, ms_to_timeval};
-use sys_common::helper_thread::Helper;
-use sys_common::{AsInner, mkerr_libc, timeout};
+use sys::fd::FileDesc;
+use sys::{self, c, cvt, cvt_r};
+use sys_common::FromInner;
+
+////////////////////////////////////////////////////////////////////////////////
+// Command
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone)]
+pub struct Command {
+    pub program: CString,
+    pub args: Vec<CString>,
+    pub env: Option<HashMap<OsString, OsString>>,
+    pub cwd: Option<CString>,
+    pub uid: Option<uid_t>,
+    pub gid: Option<gid_t>,
+    pub detach: bool,
+}
 
-pub use sys_common::ProcessConfig;
+impl Command {
+    pub fn new(program: &OsStr) -> Command {
+        Command {
+            program: CString::from_slice(program.as_byte_slice()),
+            args: Vec::new(),
+            env: None,
+            cwd: None,
+            uid: None,
+            gid: None,
+            detach: false,
+        }
+    }
 
-helper_init! { static HELPER: Helper<Req> }
+    pub fn arg(&mut self, arg: &OsStr) {
+        self.args.push(CString::from_slice(arg.as_byte_slice()))
+    }
+    pub fn args<'a, I: Iterator<Item = &'a OsStr>>(&mut self, args: I) {
+        self.args.extend(args.map(|s| CString::from_slice(s.as_byte_slice())))
+    }
+    fn init_env_map(&mut self) {
+        if self.env.is_none() {
+            self.env = Some(sys::os::env().collect());
+        }
+    }
+    pub fn env(&mut self, key: &OsStr, val: &OsStr) {
+        self.init_env_map();
+        self.env.as_mut().unwrap().insert(key.to_os_string(),
+                                          val.to_os_string());
+    }
+    pub fn env_remove(&mut self, key: &OsStr) {
+        self.init_env_map();
+        self.env.as_mut().unwrap().remove(&key.to_os_string());
+    }
+    pub fn env_clear(&mut self) {
+        self.env = Some(HashMap::new())
+    }
+    pub fn cwd(&mut self, dir: &Path) {
+        self.cwd = Some(CString::from_slice(dir.as_vec()))
+    }
+}
 
-/// The unique id of the process (this should never be negative).
-pub struct Process {
-    pub pid: pid_t
+////////////////////////////////////////////////////////////////////////////////
+// Anonymous pipes
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct AnonPipe(FileDesc);
+
+// Both ends are close-on-exec so they don't leak into unrelated children; the
+// `dup2` which installs an end as a child's stdio clears the flag again.
+// Where `pipe2` exists the flag is set as the pipe is created, so that a fork
+// on another thread can't inherit the ends in between.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub unsafe fn anon_pipe() -> io::Result<(AnonPipe, AnonPipe)> {
+    let mut fds = [0; 2];
+    match c::pipe2(fds.as_mut_ptr(), c::O_CLOEXEC) {
+        // Linux only has `pipe2` since 2.6.27
+        -1 if sys::os::errno() == libc::ENOSYS as i32 => anon_pipe_cloexec(),
+        -1 => Err(Error::last_os_error()),
+        _ => Ok((AnonPipe(FileDesc::new(fds[0])),
+                 AnonPipe(FileDesc::new(fds[1])))),
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub unsafe fn anon_pipe() -> io::Result<(AnonPipe, AnonPipe)> {
+    anon_pipe_cloexec()
+}
+
+unsafe fn anon_pipe_cloexec() -> io::Result<(AnonPipe, AnonPipe)> {
+    let (reader, writer) = try!(sys::os::pipe());
+    try!(reader.set_cloexec());
+    try!(writer.set_cloexec());
+    Ok((AnonPipe(reader), AnonPipe(writer)))
 }
 
-enum Req {
-    NewChild(libc::pid_t, Sender<ProcessExit>, u64),
+impl AnonPipe {
+    pub fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+
+    pub fn write(&self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    pub fn duplicate(&self) -> io::Result<AnonPipe> {
+        self.0.duplicate().map(AnonPipe)
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.0.set_nonblocking(nonblocking)
+    }
+
+    pub fn raw(&self) -> c_int { self.0.raw() }
+    pub fn fd(&self) -> &FileDesc { &self.0 }
+    pub fn into_fd(self) -> FileDesc { self.0 }
+}
+
+impl FromInner<FileDesc> for AnonPipe {
+    fn from_inner(fd: FileDesc) -> AnonPipe { AnonPipe(fd) }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Processes
+////////////////////////////////////////////////////////////////////////////////
+
+/// Unix exit statuses
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ExitStatus {
+    /// Normal termination with an exit code.
+    Code(i32),
+
+    /// Termination by signal, with the signal number.
+    Signal(i32),
+}
+
+impl ExitStatus {
+    pub fn success(&self) -> bool {
+        *self == ExitStatus::Code(0)
+    }
+    pub fn code(&self) -> Option<i32> {
+        match *self {
+            ExitStatus::Code(c) => Some(c),
+            _ => None
+        }
+    }
+    pub fn signal(&self) -> Option<i32> {
+        match *self {
+            ExitStatus::Signal(s) => Some(s),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for ExitStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ExitStatus::Code(code) =>  write!(f, "exit code: {}", code),
+            ExitStatus::Signal(code) =>  write!(f, "signal: {}", code),
+        }
+    }
+}
+
+/// How a stdio stream of the child should be set up.
+pub enum Stdio {
+    /// Leave the parent's descriptor in place.
+    Inherit,
+    /// Open `/dev/null` onto the descriptor.
+    Null,
+    /// Duplicate this descriptor onto the child's stdio descriptor.
+    Raw(c_int),
+}
+
+/// The unique id of the process (this should never be negative).
+pub struct Process {
+    pid: pid_t
 }
 
 const CLOEXEC_MSG_FOOTER: &'static [u8] = b"NOEX";
@@ -48,21 +203,14 @@ impl Process {
         self.pid
     }
 
-    pub unsafe fn kill(&self, signal: int) -> IoResult<()> {
-        Process::killpid(self.pid, signal)
-    }
-
-    pub unsafe fn killpid(pid: pid_t, signal: int) -> IoResult<()> {
-        let r = libc::funcs::posix88::signal::kill(pid, signal as c_int);
-        mkerr_libc(r)
+    pub unsafe fn kill(&self) -> io::Result<()> {
+        try!(cvt(libc::funcs::posix88::signal::kill(self.pid, libc::SIGKILL)));
+        Ok(())
     }
 
-    pub fn spawn<K, V, C, P>(cfg: &C, in_fd: Option<P>,
-                              out_fd: Option<P>, err_fd: Option<P>)
-                              -> IoResult<Process>
-        where C: ProcessConfig<K, V>, P: AsInner<FileDesc>,
-              K: BytesContainer + Eq + Hash<Hasher>, V: BytesContainer
-    {
+    pub fn spawn(cfg: &Command,
+                 in_fd: Stdio, out_fd: Stdio, err_fd: Stdio)
+                 -> io::Result<Process> {
         use libc::funcs::posix88::unistd::{fork, dup2, close, chdir, execvp};
         use libc::funcs::bsd44::getdtablesize;
 
@@ -89,26 +237,21 @@ impl Process {
             assert_eq!(ret, 0);
         }
 
-        let dirp = cfg.cwd().map(|c| c.as_ptr()).unwrap_or(ptr::null());
-
-        // temporary until unboxed closures land
-        let cfg = unsafe {
-            mem::transmute::<&ProcessConfig<K,V>,&'static ProcessConfig<K,V>>(cfg)
-        };
+        let dirp = cfg.cwd.as_ref().map(|c| c.as_ptr()).unwrap_or(ptr::null());
 
-        with_envp(cfg.env(), move|: envp: *const c_void| {
-            with_argv(cfg.program(), cfg.args(), move|: argv: *const *const libc::c_char| unsafe {
+        with_envp(cfg.env.as_ref(), |envp: *const c_void| {
+            with_argv(&cfg.program, &cfg.args, |argv: *const *const libc::c_char| unsafe {
                 let (input, mut output) = try!(sys::os::pipe());
 
                 // We may use this in the child, so perform allocations before the
                 // fork
                 let devnull = b"/dev/null\0";
 
-                set_cloexec(output.fd());
+                set_cloexec(output.raw());
 
                 let pid = fork();
                 if pid < 0 {
-                    return Err(super::last_error())
+                    return Err(Error::last_os_error())
                 } else if pid > 0 {
                     #[inline]
                     fn combine(arr: &[u8]) -> i32 {
@@ -124,20 +267,20 @@ impl Process {
                     drop(output);
                     let mut bytes = [0; 8];
                     return match input.read(&mut bytes) {
+                        Ok(0) => Ok(p),
                         Ok(8) => {
                             assert!(combine(CLOEXEC_MSG_FOOTER) == combine(&bytes[4.. 8]),
                                 "Validation on the CLOEXEC pipe failed: {:?}", bytes);
                             let errno = combine(&bytes[0.. 4]);
-                            assert!(p.wait(0).is_ok(), "wait(0) should either return Ok or panic");
-                            Err(super::decode_error(errno))
+                            assert!(p.wait().is_ok(), "wait() should either return Ok or panic");
+                            Err(Error::from_os_error(errno))
                         }
-                        Err(ref e) if e.kind == EndOfFile => Ok(p),
                         Err(e) => {
-                            assert!(p.wait(0).is_ok(), "wait(0) should either return Ok or panic");
+                            assert!(p.wait().is_ok(), "wait() should either return Ok or panic");
                             panic!("the CLOEXEC pipe failed: {:?}", e)
                         },
                         Ok(..) => { // pipe I/O up to PIPE_BUF bytes should be atomic
-                            assert!(p.wait(0).is_ok(), "wait(0) should either return Ok or panic");
+                            assert!(p.wait().is_ok(), "wait() should either return Ok or panic");
                             panic!("short read on the CLOEXEC pipe")
                         }
                     };
@@ -173,7 +316,7 @@ impl Process {
                 // allocation). Instead we just close it manually. This will never
                 // have the drop glue anyway because this code never returns (the
                 // child will either exec() or invoke libc::exit)
-                let _ = libc::close(input.fd());
+                let _ = libc::close(input.raw());
 
                 fn fail(output: &mut FileDesc) -> ! {
                     let errno = sys::os::errno() as u32;
@@ -192,14 +335,16 @@ impl Process {
 
                 rustrt::rust_unset_sigprocmask();
 
-                // If a stdio file descriptor is set to be ignored (via a -1 file
-                // descriptor), then we don't actually close it, but rather open
-                // up /dev/null into that file descriptor. Otherwise, the first file
-                // descriptor opened up in the child would be numbered as one of the
-                // stdio file descriptors, which is likely to wreak havoc.
-                let setup = |&: src: Option<P>, dst: c_int| {
+                // If a stdio file descriptor is set to be ignored, then we don't
+                // actually close it, but rather open up /dev/null into that file
+                // descriptor. Otherwise, the first file descriptor opened up in
+                // the child would be numbered as one of the stdio file
+                // descriptors, which is likely to wreak havoc.
+                let setup = |src: Stdio, dst: c_int| {
                     let src = match src {
-                        None => {
+                        Stdio::Inherit => return true,
+                        Stdio::Raw(fd) => fd,
+                        Stdio::Null => {
                             let flags = if dst == libc::STDIN_FILENO {
                                 libc::O_RDONLY
                             } else {
@@ -207,16 +352,8 @@ impl Process {
                             };
                             libc::open(devnull.as_ptr() as *const _, flags, 0)
                         }
-                        Some(obj) => {
-                            let fd = obj.as_inner().fd();
-                            // Leak the memory and the file descriptor. We're in the
-                            // child now an all our resources are going to be
-                            // cleaned up very soon
-                            mem::forget(obj);
-                            fd
-                        }
                     };
-                    src != -1 && retry(|| dup2(src, dst)) != -1
+                    src != -1 && cvt_r(|| dup2(src, dst)).is_ok()
                 };
 
                 if !setup(in_fd, libc::STDIN_FILENO) { fail(&mut output) }
@@ -224,42 +361,36 @@ impl Process {
                 if !setup(err_fd, libc::STDERR_FILENO) { fail(&mut output) }
 
                 // close all other fds
-                for fd in range(3, getdtablesize()).rev() {
-                    if fd != output.fd() {
+                for fd in (3..getdtablesize()).rev() {
+                    if fd != output.raw() {
                         let _ = close(fd as c_int);
                     }
                 }
 
-                match cfg.gid() {
-                    Some(u) => {
-                        if libc::setgid(u as libc::gid_t) != 0 {
-                            fail(&mut output);
-                        }
+                if let Some(u) = cfg.gid {
+                    if libc::setgid(u as libc::gid_t) != 0 {
+                        fail(&mut output);
                     }
-                    None => {}
                 }
-                match cfg.uid() {
-                    Some(u) => {
-                        // When dropping privileges from root, the `setgroups` call
-                        // will remove any extraneous groups. If we don't call this,
-                        // then even though our uid has dropped, we may still have
-                        // groups that enable us to do super-user things. This will
-                        // fail if we aren't root, so don't bother checking the
-                        // return value, this is just done as an optimistic
-                        // privilege dropping function.
-                        extern {
-                            fn setgroups(ngroups: libc::c_int,
-                                         ptr: *const libc::c_void) -> libc::c_int;
-                        }
-                        let _ = setgroups(0, ptr::null());
+                if let Some(u) = cfg.uid {
+                    // When dropping privileges from root, the `setgroups` call
+                    // will remove any extraneous groups. If we don't call this,
+                    // then even though our uid has dropped, we may still have
+                    // groups that enable us to do super-user things. This will
+                    // fail if we aren't root, so don't bother checking the
+                    // return value, this is just done as an optimistic
+                    // privilege dropping function.
+                    extern {
+                        fn setgroups(ngroups: libc::c_int,
+                                     ptr: *const libc::c_void) -> libc::c_int;
+                    }
+                    let _ = setgroups(0, ptr::null());
 
-                        if libc::setuid(u as libc::uid_t) != 0 {
-                            fail(&mut output);
-                        }
+                    if libc::setuid(u as libc::uid_t) != 0 {
+                        fail(&mut output);
                     }
-                    None => {}
                 }
-                if cfg.detach() {
+                if cfg.detach {
                     // Don't check the error of setsid because it fails if we're the
                     // process leader already. We just forked so it shouldn't return
                     // error, but ignore it anyway.
@@ -277,258 +408,19 @@ impl Process {
         })
     }
 
-    pub fn wait(&self, deadline: u64) -> IoResult<ProcessExit> {
-        use cmp;
-        use sync::mpsc::TryRecvError;
-
-        static mut WRITE_FD: libc::c_int = 0;
-
+    pub fn wait(&self) -> io::Result<ExitStatus> {
         let mut status = 0 as c_int;
-        if deadline == 0 {
-            return match retry(|| unsafe { c::waitpid(self.pid, &mut status, 0) }) {
-                -1 => panic!("unknown waitpid error: {:?}", super::last_error()),
-                _ => Ok(translate_status(status)),
-            }
-        }
-
-        // On unix, wait() and its friends have no timeout parameters, so there is
-        // no way to time out a thread in wait(). From some googling and some
-        // thinking, it appears that there are a few ways to handle timeouts in
-        // wait(), but the only real reasonable one for a multi-threaded program is
-        // to listen for SIGCHLD.
-        //
-        // With this in mind, the waiting mechanism with a timeout barely uses
-        // waitpid() at all. There are a few times that waitpid() is invoked with
-        // WNOHANG, but otherwise all the necessary blocking is done by waiting for
-        // a SIGCHLD to arrive (and that blocking has a timeout). Note, however,
-        // that waitpid() is still used to actually reap the child.
-        //
-        // Signal handling is super tricky in general, and this is no exception. Due
-        // to the async nature of SIGCHLD, we use the self-pipe trick to transmit
-        // data out of the signal handler to the rest of the application. The first
-        // idea would be to have each thread waiting with a timeout to read this
-        // output file descriptor, but a write() is akin to a signal(), not a
-        // broadcast(), so it would only wake up one thread, and possibly the wrong
-        // thread. Hence a helper thread is used.
-        //
-        // The helper thread here is responsible for farming requests for a
-        // waitpid() with a timeout, and then processing all of the wait requests.
-        // By guaranteeing that only this helper thread is reading half of the
-        // self-pipe, we're sure that we'll never lose a SIGCHLD. This helper thread
-        // is also responsible for select() to wait for incoming messages or
-        // incoming SIGCHLD messages, along with passing an appropriate timeout to
-        // select() to wake things up as necessary.
-        //
-        // The ordering of the following statements is also very purposeful. First,
-        // we must be guaranteed that the helper thread is booted and available to
-        // receive SIGCHLD signals, and then we must also ensure that we do a
-        // nonblocking waitpid() at least once before we go ask the sigchld helper.
-        // This prevents the race where the child exits, we boot the helper, and
-        // then we ask for the child's exit status (never seeing a sigchld).
-        //
-        // The actual communication between the helper thread and this thread is
-        // quite simple, just a channel moving data around.
-
-        unsafe { HELPER.boot(register_sigchld, waitpid_helper) }
-
-        match self.try_wait() {
-            Some(ret) => return Ok(ret),
-            None => {}
-        }
-
-        let (tx, rx) = channel();
-        unsafe { HELPER.send(NewChild(self.pid, tx, deadline)); }
-        return match rx.recv() {
-            Ok(e) => Ok(e),
-            Err(..) => Err(timeout("wait timed out")),
-        };
-
-        // Register a new SIGCHLD handler, returning the reading half of the
-        // self-pipe plus the old handler registered (return value of sigaction).
-        //
-        // Be sure to set up the self-pipe first because as soon as we register a
-        // handler we're going to start receiving signals.
-        fn register_sigchld() -> (libc::c_int, c::sigaction) {
-            unsafe {
-                let mut pipes = [0; 2];
-                assert_eq!(libc::pipe(pipes.as_mut_ptr()), 0);
-                set_nonblocking(pipes[0], true).ok().unwrap();
-                set_nonblocking(pipes[1], true).ok().unwrap();
-                WRITE_FD = pipes[1];
-
-                let mut old: c::sigaction = mem::zeroed();
-                let mut new: c::sigaction = mem::zeroed();
-                new.sa_handler = sigchld_handler;
-                new.sa_flags = c::SA_NOCLDSTOP;
-                assert_eq!(c::sigaction(c::SIGCHLD, &new, &mut old), 0);
-                (pipes[0], old)
-            }
-        }
-
-        // Helper thread for processing SIGCHLD messages
-        fn waitpid_helper(input: libc::c_int,
-                          messages: Receiver<Req>,
-                          (read_fd, old): (libc::c_int, c::sigaction)) {
-            set_nonblocking(input, true).ok().unwrap();
-            let mut set: c::fd_set = unsafe { mem::zeroed() };
-            let mut tv: libc::timeval;
-            let mut active = Vec::<(libc::pid_t, Sender<ProcessExit>, u64)>::new();
-            let max = cmp::max(input, read_fd) + 1;
-
-            'outer: loop {
-                // Figure out the timeout of our syscall-to-happen. If we're waiting
-                // for some processes, then they'll have a timeout, otherwise we
-                // wait indefinitely for a message to arrive.
-                //
-                // FIXME: sure would be nice to not have to scan the entire array
-                let min = active.iter().map(|a| a.2).enumerate().min_by(|p| {
-                    p.1
-                });
-                let (p, idx) = match min {
-                    Some((idx, deadline)) => {
-                        let now = sys::timer::now();
-                        let ms = if now < deadline {deadline - now} else {0};
-                        tv = ms_to_timeval(ms);
-                        (&mut tv as *mut _, idx)
-                    }
-                    None => (ptr::null_mut(), -1),
-                };
-
-                // Wait for something to happen
-                c::fd_set(&mut set, input);
-                c::fd_set(&mut set, read_fd);
-                match unsafe { c::select(max, &mut set, ptr::null_mut(),
-                                         ptr::null_mut(), p) } {
-                    // interrupted, retry
-                    -1 if os::errno() == libc::EINTR as u32 => continue,
-
-                    // We read something, break out and process
-                    1 | 2 => {}
-
-                    // Timeout, the pending request is removed
-                    0 => {
-                        drop(active.remove(idx));
-                        continue
-                    }
-
-                    n => panic!("error in select {:?} ({:?})", os::errno(), n),
-                }
-
-                // Process any pending messages
-                if drain(input) {
-                    loop {
-                        match messages.try_recv() {
-                            Ok(NewChild(pid, tx, deadline)) => {
-                                active.push((pid, tx, deadline));
-                            }
-                            Err(TryRecvError::Disconnected) => {
-                                assert!(active.len() == 0);
-                                break 'outer;
-                            }
-                            Err(TryRecvError::Empty) => break,
-                        }
-                    }
-                }
-
-                // If a child exited (somehow received SIGCHLD), then poll all
-                // children to see if any of them exited.
-                //
-                // We also attempt to be responsible netizens when dealing with
-                // SIGCHLD by invoking any previous SIGCHLD handler instead of just
-                // ignoring any previous SIGCHLD handler. Note that we don't provide
-                // a 1:1 mapping of our handler invocations to the previous handler
-                // invocations because we drain the `read_fd` entirely. This is
-                // probably OK because the kernel is already allowed to coalesce
-                // simultaneous signals, we're just doing some extra coalescing.
-                //
-                // Another point of note is that this likely runs the signal handler
-                // on a different thread than the one that received the signal. I
-                // *think* this is ok at this time.
-                //
-                // The main reason for doing this is to allow stdtest to run native
-                // tests as well. Both libgreen and libnative are running around
-                // with process timeouts, but libgreen should get there first
-                // (currently libuv doesn't handle old signal handlers).
-                if drain(read_fd) {
-                    let i: uint = unsafe { mem::transmute(old.sa_handler) };
-                    if i != 0 {
-                        assert!(old.sa_flags & c::SA_SIGINFO == 0);
-                        (old.sa_handler)(c::SIGCHLD);
-                    }
-
-                    // FIXME: sure would be nice to not have to scan the entire
-                    //        array...
-                    active.retain(|&(pid, ref tx, _)| {
-                        let pr = Process { pid: pid };
-                        match pr.try_wait() {
-                            Some(msg) => { tx.send(msg).unwrap(); false }
-                            None => true,
-                        }
-                    });
-                }
-            }
-
-            // Once this helper thread is done, we re-register the old sigchld
-            // handler and close our intermediate file descriptors.
-            unsafe {
-                assert_eq!(c::sigaction(c::SIGCHLD, &old, ptr::null_mut()), 0);
-                let _ = libc::close(read_fd);
-                let _ = libc::close(WRITE_FD);
-                WRITE_FD = -1;
-            }
-        }
-
-        // Drain all pending data from the file descriptor, returning if any data
-        // could be drained. This requires that the file descriptor is in
-        // nonblocking mode.
-        fn drain(fd: libc::c_int) -> bool {
-            let mut ret = false;
-            loop {
-                let mut buf = [0u8; 1];
-                match unsafe {
-                    libc::read(fd, buf.as_mut_ptr() as *mut libc::c_void,
-                               buf.len() as libc::size_t)
-                } {
-                    n if n > 0 => { ret = true; }
-                    0 => return true,
-                    -1 if wouldblock() => return ret,
-                    n => panic!("bad read {:?} ({:?})", os::last_os_error(), n),
-                }
-            }
-        }
-
-        // Signal handler for SIGCHLD signals, must be async-signal-safe!
-        //
-        // This function will write to the writing half of the "self pipe" to wake
-        // up the helper thread if it's waiting. Note that this write must be
-        // nonblocking because if it blocks and the reader is the thread we
-        // interrupted, then we'll deadlock.
-        //
-        // When writing, if the write returns EWOULDBLOCK then we choose to ignore
-        // it. At that point we're guaranteed that there's something in the pipe
-        // which will wake up the other end at some point, so we just allow this
-        // signal to be coalesced with the pending signals on the pipe.
-        extern fn sigchld_handler(_signum: libc::c_int) {
-            let msg = 1i;
-            match unsafe {
-                libc::write(WRITE_FD, &msg as *const _ as *const libc::c_void, 1)
-            } {
-                1 => {}
-                -1 if wouldblock() => {} // see above comments
-                n => panic!("bad error on write fd: {:?} {:?}", n, os::errno()),
-            }
-        }
+        try!(cvt_r(|| unsafe { c::waitpid(self.pid, &mut status, 0) }));
+        Ok(translate_status(status))
     }
 
-    pub fn try_wait(&self) -> Option<ProcessExit> {
+    pub fn try_wait(&self) -> io::Result<Option<ExitStatus>> {
         let mut status = 0 as c_int;
-        match retry(|| unsafe {
+        match try!(cvt_r(|| unsafe {
             c::waitpid(self.pid, &mut status, c::WNOHANG)
-        }) {
-            n if n == self.pid => Some(translate_status(status)),
-            0 => None,
-            n => panic!("unknown waitpid error `{:?}`: {:?}", n,
-                       super::last_error()),
+        })) {
+            0 => Ok(None),
+            _ => Ok(Some(translate_status(status))),
         }
     }
 }
@@ -554,12 +446,8 @@ fn with_argv<T,F>(prog: &CString, args: &[CString],
     cb(ptrs.as_ptr())
 }
 
-fn with_envp<K,V,T,F>(env: Option<&HashMap<K, V>>,
-                      cb: F)
-                      -> T
-    where F : FnOnce(*const c_void) -> T,
-          K : BytesContainer + Eq + Hash<Hasher>,
-          V : BytesContainer
+fn with_envp<T, F>(env: Option<&HashMap<OsString, OsString>>, cb: F) -> T
+    where F: FnOnce(*const c_void) -> T
 {
     // On posixy systems we can pass a char** for envp, which is a
     // null-terminated array of "k=v\0" strings. Since we must create
@@ -572,9 +460,9 @@ fn with_envp<K,V,T,F>(env: Option<&HashMap<K, V>>,
 
             for pair in env.iter() {
                 let mut kv = Vec::new();
-                kv.push_all(pair.0.container_as_bytes());
+                kv.push_all(pair.0.as_byte_slice());
                 kv.push('=' as u8);
-                kv.push_all(pair.1.container_as_bytes());
+                kv.push_all(pair.1.as_byte_slice());
                 kv.push(0); // terminating null
                 tmps.push(kv);
             }
@@ -592,7 +480,7 @@ fn with_envp<K,V,T,F>(env: Option<&HashMap<K, V>>,
     }
 }
 
-fn translate_status(status: c_int) -> ProcessExit {
+fn translate_status(status: c_int) -> ExitStatus {
     #![allow(non_snake_case)]
     #[cfg(any(target_os = "linux", target_os = "android"))]
     mod imp {
@@ -612,8 +500,8 @@ fn translate_status(status: c_int) -> ProcessExit {
     }
 
     if imp::WIFEXITED(status) {
-        ExitStatus(imp::WEXITSTATUS(status) as int)
+        ExitStatus::Code(imp::WEXITSTATUS(status))
     } else {
-        ExitSignal(imp::WTERMSIG(status) as int)
+        ExitStatus::Signal(imp::WTERMSIG(status))
     }
 }
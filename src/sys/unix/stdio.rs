@@ -0,0 +1,59 @@
+// Copyright 2015 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use prelude::v1::*;
+use io::prelude::*;
+
+use io;
+use libc;
+use sys::fd::FileDesc;
+use sys::tty;
+
+// Note that `FileDesc` never closes the standard file descriptors when
+// dropped, so these types can freely be created and destroyed.
+pub struct Stdin(FileDesc);
+pub struct Stdout(FileDesc);
+pub struct Stderr(FileDesc);
+
+impl Stdin {
+    pub fn new() -> io::Result<Stdin> {
+        Ok(Stdin(FileDesc::new(libc::STDIN_FILENO)))
+    }
+
+    pub fn is_terminal(&self) -> bool { tty::isatty(&self.0) }
+
+    pub fn read(&self, data: &mut [u8]) -> io::Result<usize> {
+        self.0.read(data)
+    }
+}
+
+impl Stdout {
+    pub fn new() -> io::Result<Stdout> {
+        Ok(Stdout(FileDesc::new(libc::STDOUT_FILENO)))
+    }
+
+    pub fn is_terminal(&self) -> bool { tty::isatty(&self.0) }
+
+    pub fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.0.write(data)
+    }
+}
+
+impl Stderr {
+    pub fn new() -> io::Result<Stderr> {
+        Ok(Stderr(FileDesc::new(libc::STDERR_FILENO)))
+    }
+
+    pub fn is_terminal(&self) -> bool { tty::isatty(&self.0) }
+
+    pub fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.0.write(data)
+    }
+}
@@ -8,277 +8,422 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-//! Timers for non-Linux/non-Windows OSes
+//! Timers implemented with a worker thread
 //!
-//! This module implements timers with a worker thread, select(), and a lot of
-//! witchcraft that turns out to be horribly inaccurate timers. The unfortunate
-//! part is that I'm at a loss of what else to do one these OSes. This is also
-//! why Linux has a specialized timerfd implementation and windows has its own
-//! implementation (they're more accurate than this one).
+//! There is one worker thread for all timers, and it's communicated to via a
+//! channel and a pipe. New requests are sent over the channel and the pipe is
+//! written to in order to wake the worker thread up to receive them.
 //!
-//! The basic idea is that there is a worker thread that's communicated to via a
-//! channel and a pipe, the pipe is used by the worker thread in a select()
-//! syscall with a timeout. The timeout is the "next timer timeout" while the
-//! channel is used to send data over to the worker thread.
+//! On Linux every timer owns a timerfd which is armed by the timer itself, and
+//! the worker thread waits on the pipe and all active timerfds with epoll. The
+//! kernel does all of the time keeping, so these timers are about as accurate
+//! as the scheduler allows.
 //!
-//! Whenever the call to select() times out, then a channel receives a message.
-//! Whenever the call returns that the file descriptor has information, then the
-//! channel from timers is drained, enqueuing all incoming requests.
+//! Elsewhere the worker thread keeps a sorted array of timers in terms of
+//! their target firing date (an absolute time), and uses the nearest target as
+//! the timeout to a select() on the pipe. Whenever select() times out the
+//! first timer fires and, if it repeats, is re-enqueued. Relative/absolute
+//! time is juggled with `gettimeofday`, so this is a little lossy and not
+//! quite exact.
 //!
-//! The actual implementation of the helper thread is a sorted array of
-//! timers in terms of target firing date. The target is the absolute time at
-//! which the timer should fire. Timers are then re-enqueued after a firing if
-//! the repeat boolean is set.
-//!
-//! Naturally, all this logic of adding times and keeping track of
-//! relative/absolute time is a little lossy and not quite exact. I've done the
-//! best I could to reduce the amount of calls to 'now()', but there's likely
-//! still inaccuracies trickling in here and there.
-//!
-//! One of the tricky parts of this implementation is that whenever a timer is
-//! acted upon, it must cancel whatever the previous action was (if one is
-//! active) in order to act like the other implementations of this timer. In
-//! order to do this, the timer's inner pointer is transferred to the worker
-//! thread. Whenever the timer is modified, it first takes ownership back from
-//! the worker thread in order to modify the same data structure. This has the
-//! side effect of "cancelling" the previous requests while allowing a
-//! re-enqueuing later on.
-//!
-//! Note that all time units in this file are in *milliseconds*.
+//! Whenever a timer is acted upon it must cancel whatever the previous action
+//! was. This is done by synchronously asking the worker thread to remove the
+//! timer, after which the channel of the previous request has been dropped.
 
 use prelude::v1::*;
 use self::Req::*;
 
-use old_io::IoResult;
+use io;
 use libc;
-use mem;
-use os;
-use ptr;
 use sync::atomic::{self, Ordering};
-use sync::mpsc::{channel, Sender, Receiver, TryRecvError};
-use sys::c;
-use sys::fs::FileDesc;
+use sync::mpsc::{channel, Sender};
+use sys::os;
 use sys_common::helper_thread::Helper;
+use time::Duration;
 
 helper_init! { static HELPER: Helper<Req> }
 
-pub trait Callback {
-    fn call(&mut self);
-}
-
 pub struct Timer {
-    id: uint,
-    inner: Option<Box<Inner>>,
+    id: usize,
+    active: bool,
+    source: imp::Source,
 }
 
 pub struct Inner {
-    cb: Option<Box<Callback + Send>>,
-    interval: u64,
+    tx: Sender<()>,
+    id: usize,
     repeat: bool,
-    target: u64,
-    id: uint,
+    source: imp::Source,
 }
 
 pub enum Req {
     // Add a new timer to the helper thread.
     NewTimer(Box<Inner>),
 
-    // Remove a timer based on its id and then send it back on the channel
-    // provided
-    RemoveTimer(uint, Sender<Box<Inner>>),
+    // Remove a timer based on its id, acknowledging on the channel provided
+    // once the timer is gone.
+    RemoveTimer(usize, Sender<()>),
 }
 
-// returns the current time (in milliseconds)
-pub fn now() -> u64 {
-    unsafe {
-        let mut now: libc::timeval = mem::zeroed();
-        assert_eq!(c::gettimeofday(&mut now, ptr::null_mut()), 0);
-        return (now.tv_sec as u64) * 1000 + (now.tv_usec as u64) / 1000;
+impl Timer {
+    pub fn new() -> io::Result<Timer> {
+        HELPER.boot(|| {}, imp::helper);
+
+        static ID: atomic::AtomicUsize = atomic::ATOMIC_USIZE_INIT;
+        Ok(Timer {
+            id: ID.fetch_add(1, Ordering::Relaxed),
+            active: false,
+            source: try!(imp::Source::new()),
+        })
     }
-}
 
-fn helper(input: libc::c_int, messages: Receiver<Req>, _: ()) {
-    let mut set: c::fd_set = unsafe { mem::zeroed() };
+    pub fn sleep(&mut self, dur: Duration) {
+        self.cancel();
+        if dur <= Duration::zero() { return }
 
-    let mut fd = FileDesc::new(input, true);
-    let mut timeout: libc::timeval = unsafe { mem::zeroed() };
+        let secs = dur.num_seconds();
+        let nsecs = (dur - Duration::seconds(secs)).num_nanoseconds().unwrap();
+        let mut to_sleep = libc::timespec {
+            tv_sec: secs as libc::time_t,
+            tv_nsec: nsecs as libc::c_long,
+        };
+        while unsafe { libc::nanosleep(&to_sleep, &mut to_sleep) } != 0 {
+            if os::errno() != libc::EINTR as i32 {
+                panic!("failed to sleep, but not because of EINTR?");
+            }
+        }
+    }
 
-    // active timers are those which are able to be selected upon (and it's a
-    // sorted list, and dead timers are those which have expired, but ownership
-    // hasn't yet been transferred back to the timer itself.
-    let mut active: Vec<Box<Inner>> = vec![];
-    let mut dead = vec![];
+    pub fn oneshot(&mut self, dur: Duration, tx: Sender<()>) {
+        self.schedule(dur, tx, false)
+    }
 
-    // inserts a timer into an array of timers (sorted by firing time)
-    fn insert(t: Box<Inner>, active: &mut Vec<Box<Inner>>) {
-        match active.iter().position(|tm| tm.target > t.target) {
-            Some(pos) => { active.insert(pos, t); }
-            None => { active.push(t); }
-        }
+    pub fn period(&mut self, dur: Duration, tx: Sender<()>) {
+        self.schedule(dur, tx, true)
     }
 
-    // signals the first requests in the queue, possible re-enqueueing it.
-    fn signal(active: &mut Vec<Box<Inner>>,
-              dead: &mut Vec<(uint, Box<Inner>)>) {
-        if active.is_empty() { return }
-
-        let mut timer = active.remove(0);
-        let mut cb = timer.cb.take().unwrap();
-        cb.call();
-        if timer.repeat {
-            timer.cb = Some(cb);
-            timer.target += timer.interval;
-            insert(timer, active);
-        } else {
-            dead.push((timer.id, timer));
-        }
+    fn schedule(&mut self, dur: Duration, tx: Sender<()>, repeat: bool) {
+        self.cancel();
+
+        HELPER.send(NewTimer(Box::new(Inner {
+            tx: tx,
+            id: self.id,
+            repeat: repeat,
+            source: self.source.arm(dur, repeat),
+        })));
+        self.active = true;
+    }
+
+    pub fn cancel(&mut self) {
+        if !self.active { return }
+        let (tx, rx) = channel();
+        HELPER.send(RemoveTimer(self.id, tx));
+        rx.recv().unwrap();
+        self.active = false;
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        self.cancel();
     }
+}
 
-    'outer: loop {
-        let timeout = if active.len() == 0 {
-            // Empty array? no timeout (wait forever for the next request)
-            ptr::null_mut()
-        } else {
-            let now = now();
-            // If this request has already expired, then signal it and go
-            // through another iteration
-            if active[0].target <= now {
-                signal(&mut active, &mut dead);
-                continue;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod imp {
+    use prelude::v1::*;
+
+    use io;
+    use libc;
+    use mem;
+    use ptr;
+    use super::{Inner, Req};
+    use super::Req::*;
+    use sync::Arc;
+    use sync::mpsc::{Receiver, TryRecvError};
+    use sys::c;
+    use sys::fd::FileDesc;
+    use sys::os;
+    use time::Duration;
+
+    // The epoll token of the wakeup pipe, timers are keyed by their id.
+    const INPUT: u64 = !0;
+
+    #[derive(Clone)]
+    pub struct Source(Arc<FileDesc>);
+
+    impl Source {
+        pub fn new() -> io::Result<Source> {
+            let fd = try!(call!(unsafe {
+                c::timerfd_create(c::CLOCK_MONOTONIC,
+                                  c::TFD_CLOEXEC | c::TFD_NONBLOCK)
+            }));
+            Ok(Source(Arc::new(FileDesc::new(fd))))
+        }
+
+        // Arming the timer also resets its count of expirations, so nothing
+        // left over from a previous request can leak into this one.
+        pub fn arm(&self, dur: Duration, repeat: bool) -> Source {
+            let dur = if dur <= Duration::zero() {
+                Duration::nanoseconds(1)
+            } else {
+                dur
+            };
+            let secs = dur.num_seconds();
+            let nsecs = (dur - Duration::seconds(secs)).num_nanoseconds()
+                                                       .unwrap();
+            let value = libc::timespec {
+                tv_sec: secs as libc::time_t,
+                tv_nsec: nsecs as libc::c_long,
+            };
+            let interval = if repeat {
+                value
+            } else {
+                libc::timespec { tv_sec: 0, tv_nsec: 0 }
+            };
+            let spec = c::itimerspec { it_interval: interval, it_value: value };
+            assert_eq!(unsafe {
+                c::timerfd_settime(self.0.raw(), 0, &spec, ptr::null_mut())
+            }, 0);
+            self.clone()
+        }
+
+        // Returns the number of times the timer expired since it was last
+        // read, if any.
+        fn expirations(&self) -> Option<u64> {
+            let mut buf = [0u8; 8];
+            match self.0.read(&mut buf) {
+                Ok(8) => Some(unsafe { mem::transmute(buf) }),
+                _ => None,
             }
+        }
+    }
 
-            // The actual timeout listed in the requests array is an
-            // absolute date, so here we translate the absolute time to a
-            // relative time.
-            let tm = active[0].target - now;
-            timeout.tv_sec = (tm / 1000) as libc::time_t;
-            timeout.tv_usec = ((tm % 1000) * 1000) as libc::suseconds_t;
-            &mut timeout as *mut libc::timeval
-        };
+    fn ctl(epfd: &FileDesc, op: libc::c_int, fd: libc::c_int, data: u64) {
+        let mut event = c::epoll_event { events: c::EPOLLIN, data: data };
+        assert_eq!(unsafe { c::epoll_ctl(epfd.raw(), op, fd, &mut event) }, 0);
+    }
 
-        c::fd_set(&mut set, input);
-        match unsafe {
-            c::select(input + 1, &mut set, ptr::null_mut(),
-                      ptr::null_mut(), timeout)
-        } {
-            // timed out
-            0 => signal(&mut active, &mut dead),
-
-            // file descriptor write woke us up, we've got some new requests
-            1 => {
-                loop {
-                    match messages.try_recv() {
-                        Err(TryRecvError::Disconnected) => {
-                            assert!(active.len() == 0);
-                            break 'outer;
-                        }
+    pub fn helper(input: libc::c_int, messages: Receiver<Req>, _: ()) {
+        let input = FileDesc::new(input);
+        let epfd = unsafe { c::epoll_create1(c::EPOLL_CLOEXEC) };
+        assert!(epfd >= 0);
+        let epfd = FileDesc::new(epfd);
+        ctl(&epfd, c::EPOLL_CTL_ADD, input.raw(), INPUT);
+
+        let mut active: Vec<Box<Inner>> = vec![];
+        let mut events: [c::epoll_event; 16] = unsafe { mem::zeroed() };
+
+        'outer: loop {
+            let n = unsafe {
+                c::epoll_wait(epfd.raw(), events.as_mut_ptr(),
+                              events.len() as libc::c_int, -1)
+            };
+            if n == -1 {
+                if os::errno() == libc::EINTR as i32 { continue }
+                panic!("helper thread failed in epoll_wait() with error: {}",
+                       io::Error::last_os_error());
+            }
 
-                        Ok(NewTimer(timer)) => insert(timer, &mut active),
+            for event in events[..n as usize].iter() {
+                let data = event.data;
+                if data == INPUT {
+                    loop {
+                        match messages.try_recv() {
+                            Err(TryRecvError::Disconnected) => break 'outer,
+
+                            Ok(NewTimer(timer)) => {
+                                ctl(&epfd, c::EPOLL_CTL_ADD,
+                                    timer.source.0.raw(), timer.id as u64);
+                                active.push(timer);
+                            }
 
-                        Ok(RemoveTimer(id, ack)) => {
-                            match dead.iter().position(|&(i, _)| id == i) {
-                                Some(i) => {
-                                    let (_, i) = dead.remove(i);
-                                    ack.send(i).unwrap();
-                                    continue
+                            Ok(RemoveTimer(id, ack)) => {
+                                match active.iter().position(|t| t.id == id) {
+                                    Some(i) => {
+                                        let t = active.remove(i);
+                                        ctl(&epfd, c::EPOLL_CTL_DEL,
+                                            t.source.0.raw(), 0);
+                                    }
+                                    None => {}
                                 }
-                                None => {}
+                                ack.send(()).unwrap();
                             }
-                            let i = active.iter().position(|i| i.id == id);
-                            let i = i.expect("no timer found");
-                            let t = active.remove(i);
-                            ack.send(t).unwrap();
+
+                            Err(TryRecvError::Empty) => break
                         }
-                        Err(..) => break
                     }
+
+                    // drain the file descriptor
+                    let mut buf = [0];
+                    assert_eq!(input.read(&mut buf).ok().unwrap(), 1);
+                    continue
                 }
 
-                // drain the file descriptor
-                let mut buf = [0];
-                assert_eq!(fd.read(&mut buf).ok().unwrap(), 1);
+                // The timer may have been removed or replaced since this
+                // event was generated, in which case there is nothing to read.
+                let id = data as usize;
+                let i = match active.iter().position(|t| t.id == id) {
+                    Some(i) => i,
+                    None => continue,
+                };
+                let n = match active[i].source.expirations() {
+                    Some(n) => n,
+                    None => continue,
+                };
+                let mut keep = active[i].repeat;
+                for _ in 0..n {
+                    if active[i].tx.send(()).is_err() {
+                        keep = false;
+                        break
+                    }
+                }
+                if !keep {
+                    let t = active.remove(i);
+                    ctl(&epfd, c::EPOLL_CTL_DEL, t.source.0.raw(), 0);
+                }
             }
-
-            -1 if os::errno() == libc::EINTR as u32 => {}
-            n => panic!("helper thread failed in select() with error: {} ({})",
-                       n, os::last_os_error())
         }
     }
 }
 
-impl Timer {
-    pub fn new() -> IoResult<Timer> {
-        // See notes above regarding using int return value
-        // instead of ()
-        HELPER.boot(|| {}, helper);
-
-        static ID: atomic::AtomicUsize = atomic::ATOMIC_USIZE_INIT;
-        let id = ID.fetch_add(1, Ordering::Relaxed);
-        Ok(Timer {
-            id: id,
-            inner: Some(box Inner {
-                cb: None,
-                interval: 0,
-                target: 0,
-                repeat: false,
-                id: id,
-            })
-        })
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+mod imp {
+    use prelude::v1::*;
+
+    use cmp;
+    use io;
+    use libc;
+    use mem;
+    use ptr;
+    use super::{Inner, Req};
+    use super::Req::*;
+    use sync::mpsc::{Receiver, TryRecvError};
+    use sys::c;
+    use sys::fd::FileDesc;
+    use sys::os;
+    use time::Duration;
+
+    // Times are in milliseconds, and the target is an absolute time.
+    pub struct Source {
+        interval: u64,
+        target: u64,
     }
 
-    pub fn sleep(&mut self, ms: u64) {
-        let mut inner = self.inner();
-        inner.cb = None; // cancel any previous request
-        self.inner = Some(inner);
+    impl Source {
+        pub fn new() -> io::Result<Source> {
+            Ok(Source { interval: 0, target: 0 })
+        }
 
-        let mut to_sleep = libc::timespec {
-            tv_sec: (ms / 1000) as libc::time_t,
-            tv_nsec: ((ms % 1000) * 1000000) as libc::c_long,
-        };
-        while unsafe { libc::nanosleep(&to_sleep, &mut to_sleep) } != 0 {
-            if os::errno() as int != libc::EINTR as int {
-                panic!("failed to sleep, but not because of EINTR?");
-            }
+        pub fn arm(&self, dur: Duration, _repeat: bool) -> Source {
+            // Round up so that a timer never fires early, and never has a
+            // zero interval.
+            let ms = dur.num_milliseconds();
+            let ms = if Duration::milliseconds(ms) < dur {ms + 1} else {ms};
+            let ms = cmp::max(ms, 1) as u64;
+            Source { interval: ms, target: now() + ms }
         }
     }
 
-    pub fn oneshot(&mut self, msecs: u64, cb: Box<Callback + Send>) {
-        let now = now();
-        let mut inner = self.inner();
+    // returns the current time (in milliseconds)
+    pub fn now() -> u64 {
+        unsafe {
+            let mut now: libc::timeval = mem::zeroed();
+            assert_eq!(c::gettimeofday(&mut now, ptr::null_mut()), 0);
+            return (now.tv_sec as u64) * 1000 + (now.tv_usec as u64) / 1000;
+        }
+    }
 
-        inner.repeat = false;
-        inner.cb = Some(cb);
-        inner.interval = msecs;
-        inner.target = now + msecs;
+    pub fn helper(input: libc::c_int, messages: Receiver<Req>, _: ()) {
+        let mut set: c::fd_set = unsafe { mem::zeroed() };
 
-        HELPER.send(NewTimer(inner));
-    }
+        let fd = FileDesc::new(input);
+        let mut timeout: libc::timeval = unsafe { mem::zeroed() };
 
-    pub fn period(&mut self, msecs: u64, cb: Box<Callback + Send>) {
-        let now = now();
-        let mut inner = self.inner();
+        // active timers are those which are able to be selected upon (and
+        // it's a sorted list).
+        let mut active: Vec<Box<Inner>> = vec![];
 
-        inner.repeat = true;
-        inner.cb = Some(cb);
-        inner.interval = msecs;
-        inner.target = now + msecs;
+        // inserts a timer into an array of timers (sorted by firing time)
+        fn insert(t: Box<Inner>, active: &mut Vec<Box<Inner>>) {
+            let target = t.source.target;
+            match active.iter().position(|tm| tm.source.target > target) {
+                Some(pos) => { active.insert(pos, t); }
+                None => { active.push(t); }
+            }
+        }
 
-        HELPER.send(NewTimer(inner));
-    }
+        // signals the first requests in the queue, possible re-enqueueing it.
+        fn signal(active: &mut Vec<Box<Inner>>) {
+            if active.is_empty() { return }
 
-    fn inner(&mut self) -> Box<Inner> {
-        match self.inner.take() {
-            Some(i) => i,
-            None => {
-                let (tx, rx) = channel();
-                HELPER.send(RemoveTimer(self.id, tx));
-                rx.recv().unwrap()
+            let mut timer = active.remove(0);
+            if timer.tx.send(()).is_ok() && timer.repeat {
+                timer.source.target += timer.source.interval;
+                insert(timer, active);
             }
         }
-    }
-}
 
-impl Drop for Timer {
-    fn drop(&mut self) {
-        self.inner = Some(self.inner());
+        'outer: loop {
+            let timeout = if active.len() == 0 {
+                // Empty array? no timeout (wait forever for the next request)
+                ptr::null_mut()
+            } else {
+                let now = now();
+                // If this request has already expired, then signal it and go
+                // through another iteration
+                if active[0].source.target <= now {
+                    signal(&mut active);
+                    continue;
+                }
+
+                // The actual timeout listed in the requests array is an
+                // absolute date, so here we translate the absolute time to a
+                // relative time.
+                let tm = active[0].source.target - now;
+                timeout.tv_sec = (tm / 1000) as libc::time_t;
+                timeout.tv_usec = ((tm % 1000) * 1000) as libc::suseconds_t;
+                &mut timeout as *mut libc::timeval
+            };
+
+            c::fd_set(&mut set, input);
+            match unsafe {
+                c::select(input + 1, &mut set, ptr::null_mut(),
+                          ptr::null_mut(), timeout)
+            } {
+                // timed out
+                0 => signal(&mut active),
+
+                // file descriptor write woke us up, we've got some new
+                // requests
+                1 => {
+                    loop {
+                        match messages.try_recv() {
+                            Err(TryRecvError::Disconnected) => break 'outer,
+
+                            Ok(NewTimer(timer)) => insert(timer, &mut active),
+
+                            Ok(RemoveTimer(id, ack)) => {
+                                match active.iter().position(|t| t.id == id) {
+                                    Some(i) => { active.remove(i); }
+                                    None => {}
+                                }
+                                ack.send(()).unwrap();
+                            }
+
+                            Err(TryRecvError::Empty) => break
+                        }
+                    }
+
+                    // drain the file descriptor
+                    let mut buf = [0];
+                    assert_eq!(fd.read(&mut buf).ok().unwrap(), 1);
+                }
+
+                -1 if os::errno() == libc::EINTR as i32 => {}
+                n => panic!("helper thread failed in select() with error: \
+                             {} ({})", n, io::Error::last_os_error())
+            }
+        }
     }
 }
@@ -10,39 +10,117 @@
 
 use prelude::v1::*;
 
-use sys::fs::FileDesc;
-use libc::{self, c_int};
-use old_io::{self, IoResult, IoError};
-use sys_common;
+use io::{self, Error};
+use libc;
+use mem;
+use path::Path;
+use sys::{c, cvt};
+use sys::fd::FileDesc;
+use sys::fs::{File, OpenOptions};
+use sys_common::FromInner;
 
-pub struct TTY {
-    pub fd: FileDesc,
+pub struct Terminal(FileDesc);
+
+#[derive(Copy, Clone)]
+pub struct Attr(c::termios);
+
+pub fn isatty(fd: &FileDesc) -> bool {
+    unsafe { libc::isatty(fd.raw()) != 0 }
 }
 
-impl TTY {
-    pub fn new(fd: c_int) -> IoResult<TTY> {
-        if unsafe { libc::isatty(fd) } != 0 {
-            Ok(TTY { fd: FileDesc::new(fd, true) })
+impl Terminal {
+    pub fn new(fd: FileDesc) -> io::Result<Terminal> {
+        if isatty(&fd) {
+            Ok(Terminal(fd))
         } else {
-            Err(IoError {
-                kind: old_io::MismatchedFileTypeForOperation,
-                desc: "file descriptor is not a TTY",
-                detail: None,
-            })
+            // `isatty` leaves ENOTTY (or EBADF) in errno
+            Err(Error::last_os_error())
         }
     }
 
-    pub fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> {
-        self.fd.read(buf)
+    pub fn open(path: &Path) -> io::Result<Terminal> {
+        let mut opts = OpenOptions::new();
+        opts.read(true);
+        opts.write(true);
+        opts.noctty(true);
+        let file = try!(File::open(path, &opts));
+        Terminal::new(file.into_fd())
+    }
+
+    pub fn get_attr(&self) -> io::Result<Attr> {
+        let mut attr: c::termios = unsafe { mem::zeroed() };
+        try!(cvt(unsafe { c::tcgetattr(self.0.raw(), &mut attr) }));
+        Ok(Attr(attr))
+    }
+
+    pub fn set_attr(&self, attr: &Attr) -> io::Result<()> {
+        // Pending input is discarded so that bytes typed under the old
+        // settings are not interpreted under the new ones.
+        try!(cvt(unsafe {
+            c::tcsetattr(self.0.raw(), c::TCSAFLUSH, &attr.0)
+        }));
+        Ok(())
     }
-    pub fn write(&mut self, buf: &[u8]) -> IoResult<()> {
-        self.fd.write(buf)
+
+    pub fn window_size(&self) -> io::Result<(u16, u16)> {
+        let mut size: c::winsize = unsafe { mem::zeroed() };
+        try!(cvt(unsafe {
+            c::ioctl(self.0.raw(), c::TIOCGWINSZ, &mut size)
+        }));
+        Ok((size.ws_col as u16, size.ws_row as u16))
     }
-    pub fn set_raw(&mut self, _raw: bool) -> IoResult<()> {
-        Err(sys_common::unimpl())
+
+    pub fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
     }
-    pub fn get_winsize(&mut self) -> IoResult<(int, int)> {
-        Err(sys_common::unimpl())
+
+    pub fn write(&self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
     }
-    pub fn isatty(&self) -> bool { false }
+
+    pub fn fd(&self) -> &FileDesc { &self.0 }
+
+    pub fn into_fd(self) -> FileDesc { self.0 }
+}
+
+impl FromInner<FileDesc> for Terminal {
+    fn from_inner(fd: FileDesc) -> Terminal { Terminal(fd) }
+}
+
+impl Attr {
+    /// Equivalent of `cfmakeraw`: no line editing, signal generation, echo
+    /// or output processing, and reads return as soon as a byte is available.
+    pub fn set_raw(&mut self) {
+        let t = &mut self.0;
+        t.c_iflag &= !(c::IGNBRK | c::BRKINT | c::PARMRK | c::ISTRIP |
+                       c::INLCR | c::IGNCR | c::ICRNL | c::IXON);
+        t.c_oflag &= !c::OPOST;
+        t.c_lflag &= !(c::ECHO | c::ECHONL | c::ICANON | c::ISIG | c::IEXTEN);
+        t.c_cflag &= !(c::CSIZE | c::PARENB);
+        t.c_cflag |= c::CS8;
+        t.c_cc[c::VMIN] = 1;
+        t.c_cc[c::VTIME] = 0;
+    }
+
+    /// Input is delivered a byte at a time and not echoed, but signals and
+    /// output processing are left untouched.
+    pub fn set_cbreak(&mut self) {
+        let t = &mut self.0;
+        t.c_lflag &= !(c::ECHO | c::ICANON);
+        t.c_cc[c::VMIN] = 1;
+        t.c_cc[c::VTIME] = 0;
+    }
+
+    pub fn set_echo(&mut self, echo: bool) {
+        if echo {
+            self.0.c_lflag |= c::ECHO;
+        } else {
+            // Newlines are still echoed, as `getpass` does, so the cursor
+            // moves on once the user presses enter.
+            self.0.c_lflag &= !c::ECHO;
+            self.0.c_lflag |= c::ECHONL;
+        }
+    }
+
+    pub fn echo(&self) -> bool { self.0.c_lflag & c::ECHO != 0 }
 }
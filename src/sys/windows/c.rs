@@ -44,8 +44,28 @@ pub const WSA_WAIT_TIMEOUT: libc::DWORD = libc::consts::os::extra::WAIT_TIMEOUT;
 pub const WSA_WAIT_EVENT_0: libc::DWORD = libc::consts::os::extra::WAIT_OBJECT_0;
 pub const WSA_WAIT_FAILED: libc::DWORD = libc::consts::os::extra::WAIT_FAILED;
 pub const WSAESHUTDOWN: libc::c_int = 10058;
+pub const WSAETIMEDOUT: libc::c_int = 10060;
+
+pub const SO_SNDTIMEO: libc::c_int = 0x1005;
+pub const SO_RCVTIMEO: libc::c_int = 0x1006;
+
+pub const FILE_ATTRIBUTE_REPARSE_POINT: libc::DWORD = 0x400;
+pub const FILE_FLAG_BACKUP_SEMANTICS: libc::DWORD = 0x02000000;
 
 pub const ERROR_NO_MORE_FILES: libc::DWORD = 18;
+pub const ERROR_HANDLE_EOF: libc::DWORD = 38;
+pub const ERROR_LOCK_VIOLATION: libc::DWORD = 33;
+pub const ERROR_FILE_EXISTS: libc::c_int = 80;
+
+pub const LOCKFILE_FAIL_IMMEDIATELY: libc::DWORD = 0x1;
+pub const LOCKFILE_EXCLUSIVE_LOCK: libc::DWORD = 0x2;
+
+pub const STD_INPUT_HANDLE: libc::DWORD = -10i32 as libc::DWORD;
+pub const STD_OUTPUT_HANDLE: libc::DWORD = -11i32 as libc::DWORD;
+pub const STD_ERROR_HANDLE: libc::DWORD = -12i32 as libc::DWORD;
+
+pub const PAGE_WRITECOPY: libc::DWORD = 0x08;
+pub const FILE_MAP_COPY: libc::DWORD = 0x01;
 
 #[repr(C)]
 #[cfg(target_arch = "x86")]
@@ -204,6 +224,15 @@ pub enum FILE_INFO_BY_HANDLE_CLASS {
     MaximumFileInfoByHandlesClass
 }
 
+#[repr(C)]
+pub struct FILE_BASIC_INFO {
+    pub CreationTime: libc::LARGE_INTEGER,
+    pub LastAccessTime: libc::LARGE_INTEGER,
+    pub LastWriteTime: libc::LARGE_INTEGER,
+    pub ChangeTime: libc::LARGE_INTEGER,
+    pub FileAttributes: libc::DWORD,
+}
+
 #[repr(C)]
 pub struct FILE_END_OF_FILE_INFO {
     pub EndOfFile: libc::LARGE_INTEGER,
@@ -264,6 +293,18 @@ extern "system" {
     pub fn CancelIo(hFile: libc::HANDLE) -> libc::BOOL;
     pub fn CancelIoEx(hFile: libc::HANDLE,
                       lpOverlapped: libc::LPOVERLAPPED) -> libc::BOOL;
+
+    pub fn LockFileEx(hFile: libc::HANDLE,
+                      dwFlags: libc::DWORD,
+                      dwReserved: libc::DWORD,
+                      nNumberOfBytesToLockLow: libc::DWORD,
+                      nNumberOfBytesToLockHigh: libc::DWORD,
+                      lpOverlapped: libc::LPOVERLAPPED) -> libc::BOOL;
+    pub fn UnlockFileEx(hFile: libc::HANDLE,
+                        dwReserved: libc::DWORD,
+                        nNumberOfBytesToUnlockLow: libc::DWORD,
+                        nNumberOfBytesToUnlockHigh: libc::DWORD,
+                        lpOverlapped: libc::LPOVERLAPPED) -> libc::BOOL;
 }
 
 pub mod compat {
@@ -408,6 +449,7 @@ extern "system" {
                             -> libc::BOOL;
 
     pub fn SetLastError(dwErrCode: libc::DWORD);
+    pub fn GetStdHandle(nStdHandle: libc::DWORD) -> libc::HANDLE;
     pub fn GetCommandLineW() -> *mut libc::LPCWSTR;
     pub fn LocalFree(ptr: *mut libc::c_void);
     pub fn CommandLineToArgvW(lpCmdLine: *mut libc::LPCWSTR,
@@ -420,4 +462,6 @@ extern "system" {
                     FileInformationClass: FILE_INFO_BY_HANDLE_CLASS,
                     lpFileInformation: libc::LPVOID,
                     dwBufferSize: libc::DWORD) -> libc::BOOL;
+    pub fn FlushViewOfFile(lpBaseAddress: libc::LPCVOID,
+                           dwNumberOfBytesToFlush: libc::SIZE_T) -> libc::BOOL;
 }
@@ -11,6 +11,7 @@
 use core::prelude::*;
 use io::prelude::*;
 
+use fs::FileType;
 use io::{self, Error, ErrorKind, SeekPos, Seek};
 use libc::{self, HANDLE};
 use mem;
@@ -183,6 +184,18 @@ impl OpenOptions {
             }
         };
     }
+
+    pub fn exclusive(&mut self, exclusive: bool) {
+        // CREATE_NEW    -- fails if the file exists, otherwise creates it
+        self.dwCreationDisposition = if exclusive {
+            libc::CREATE_NEW
+        } else {
+            match self.dwCreationDisposition {
+                libc::CREATE_NEW => libc::OPEN_ALWAYS,
+                n => n,
+            }
+        };
+    }
 }
 
 fn flag(slot: &mut libc::DWORD, val: libc::DWORD, on: bool) {
@@ -219,6 +232,95 @@ impl File {
 
     pub fn datasync(&mut self) -> io::Result<()> { self.fsync() }
 
+    pub fn set_nonblocking(&self, _nonblocking: bool) -> io::Result<()> {
+        // Windows only has overlapped I/O for files, there is no mode which
+        // makes a plain read or write return early.
+        Err(io::Error::new(io::ErrorKind::InvalidInput,
+                           "files cannot be put into non-blocking mode",
+                           None))
+    }
+
+    pub fn handle(&self) -> &Handle { &self.handle }
+
+    // Passing an offset through an OVERLAPPED structure is how windows does
+    // positional I/O on synchronous handles, but unlike `pread` and `pwrite`
+    // this also moves the file cursor.
+    pub fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        let mut read = 0;
+        let mut overlapped = overlapped_at(offset);
+        match unsafe {
+            libc::ReadFile(self.handle.raw(),
+                           buf.as_mut_ptr() as libc::LPVOID,
+                           buf.len() as libc::DWORD,
+                           &mut read,
+                           &mut overlapped)
+        } {
+            // Reading at or past the end of the file is reported as an error
+            0 if unsafe { libc::GetLastError() } == c::ERROR_HANDLE_EOF => {
+                Ok(0)
+            }
+            0 => Err(Error::last_os_error()),
+            _ => Ok(read as usize),
+        }
+    }
+
+    pub fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        let mut amt = 0;
+        let mut overlapped = overlapped_at(offset);
+        try!(call!(unsafe {
+            libc::WriteFile(self.handle.raw(),
+                            buf.as_ptr() as libc::LPVOID,
+                            buf.len() as libc::DWORD,
+                            &mut amt,
+                            &mut overlapped)
+        }));
+        Ok(amt as usize)
+    }
+
+    // Windows has no whole-file locks, so those lock the largest possible
+    // range instead.
+    pub fn lock(&self, exclusive: bool) -> io::Result<()> {
+        self.lock_range(exclusive, 0, 0, true)
+    }
+
+    pub fn try_lock(&self, exclusive: bool) -> io::Result<()> {
+        self.lock_range(exclusive, 0, 0, false)
+    }
+
+    pub fn unlock(&self) -> io::Result<()> {
+        self.unlock_range(0, 0)
+    }
+
+    // A length of 0 means up to the end of the file, however large it grows,
+    // as it does for `fcntl` locks.
+    pub fn lock_range(&self, exclusive: bool, start: u64, len: u64,
+                      wait: bool) -> io::Result<()> {
+        let mut flags = 0;
+        if exclusive { flags |= c::LOCKFILE_EXCLUSIVE_LOCK }
+        if !wait { flags |= c::LOCKFILE_FAIL_IMMEDIATELY }
+        let len = if len == 0 {!0} else {len};
+        let mut overlapped = overlapped_at(start);
+        try!(call!(unsafe {
+            c::LockFileEx(self.handle.raw(), flags, 0,
+                          len as libc::DWORD, (len >> 32) as libc::DWORD,
+                          &mut overlapped)
+        }));
+        Ok(())
+    }
+
+    pub fn unlock_range(&self, start: u64, len: u64) -> io::Result<()> {
+        let len = if len == 0 {!0} else {len};
+        let mut overlapped = overlapped_at(start);
+        try!(call!(unsafe {
+            c::UnlockFileEx(self.handle.raw(), 0,
+                            len as libc::DWORD, (len >> 32) as libc::DWORD,
+                            &mut overlapped)
+        }));
+        Ok(())
+    }
+
+    pub fn is_terminal(&self) -> bool { sys::is_console(self.handle.raw()) }
+
     pub fn truncate(&mut self, size: u64) -> io::Result<()> {
         let mut info = c::FILE_END_OF_FILE_INFO {
             EndOfFile: size as libc::LARGE_INTEGER,
@@ -233,6 +335,30 @@ impl File {
         Ok(())
     }
 
+    pub fn set_perm(&self, perm: FilePermission) -> io::Result<()> {
+        // Zeroed times are left alone, but zeroed attributes are too so
+        // clearing every attribute has to be spelled as "normal".
+        let attrs = match perm.attrs {
+            0 => libc::FILE_ATTRIBUTE_NORMAL,
+            attrs => attrs,
+        };
+        let mut info = c::FILE_BASIC_INFO {
+            CreationTime: 0,
+            LastAccessTime: 0,
+            LastWriteTime: 0,
+            ChangeTime: 0,
+            FileAttributes: attrs,
+        };
+        let size = mem::size_of_val(&info);
+        try!(call!(unsafe {
+            c::SetFileInformationByHandle(self.handle.raw(),
+                                          c::FileBasicInfo,
+                                          &mut info as *mut _ as *mut _,
+                                          size as libc::DWORD)
+        }));
+        Ok(())
+    }
+
     pub fn file_attr(&self) -> io::Result<FileAttr> {
         unsafe {
             let mut info: c::BY_HANDLE_FILE_INFORMATION = mem::zeroed();
@@ -252,6 +378,13 @@ impl File {
     }
 }
 
+fn overlapped_at(offset: u64) -> libc::OVERLAPPED {
+    let mut overlapped: libc::OVERLAPPED = unsafe { mem::zeroed() };
+    overlapped.Offset = offset as libc::DWORD;
+    overlapped.OffsetHigh = (offset >> 32) as libc::DWORD;
+    overlapped
+}
+
 impl Read for File {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         let mut read = 0;
@@ -314,14 +447,34 @@ impl FileAttr {
         FilePermission { attrs: self.data.dwFileAttributes }
     }
 
-    pub fn accessed(&self) -> u64 { self.to_ms(&self.data.ftLastAccessTime) }
-    pub fn modified(&self) -> u64 { self.to_ms(&self.data.ftLastWriteTime) }
+    pub fn file_type(&self) -> FileType {
+        // Symlinks and junctions are both reparse points, and `stat` doesn't
+        // follow them.
+        let attrs = self.data.dwFileAttributes;
+        if attrs & c::FILE_ATTRIBUTE_REPARSE_POINT != 0 {
+            FileType::Symlink
+        } else if attrs & c::FILE_ATTRIBUTE_DIRECTORY != 0 {
+            FileType::Dir
+        } else {
+            FileType::File
+        }
+    }
+
+    pub fn accessed(&self) -> (i64, u32) {
+        self.to_unix(&self.data.ftLastAccessTime)
+    }
+    pub fn modified(&self) -> (i64, u32) {
+        self.to_unix(&self.data.ftLastWriteTime)
+    }
+    pub fn created(&self) -> Option<(i64, u32)> {
+        Some(self.to_unix(&self.data.ftCreationTime))
+    }
 
-    fn to_ms(&self, ft: &libc::FILETIME) -> u64 {
-        // FILETIME is in 100ns intervals and there are 10000 intervals in a
-        // millisecond.
+    fn to_unix(&self, ft: &libc::FILETIME) -> (i64, u32) {
         let bits = (ft.dwLowDateTime as u64) | ((ft.dwHighDateTime as u64) << 32);
-        bits / 10000
+        let bits = bits as i64;
+        (bits / INTERVALS_PER_SEC - EPOCH_DIFFERENCE,
+         (bits % INTERVALS_PER_SEC * 100) as u32)
     }
 }
 
@@ -407,6 +560,13 @@ pub fn rename(old: &Path, new: &Path) -> io::Result<()> {
     Ok(())
 }
 
+// There are no unnamed files on Windows
+pub fn tmpfile(_dir: &Path) -> io::Result<Option<File>> { Ok(None) }
+
+// Directories can't be opened for writing to flush them on Windows, and NTFS
+// journals the metadata change of a rename anyway.
+pub fn sync_dir(_p: &Path) -> io::Result<()> { Ok(()) }
+
 pub fn rmdir(p: &Path) -> io::Result<()> {
     let p = try!(to_utf16(p));
     try!(call!(unsafe { c::RemoveDirectoryW(p.as_ptr()) }));
@@ -468,6 +628,39 @@ pub fn stat(p: &Path) -> io::Result<FileAttr> {
     }
 }
 
+// Opens `p` only to query it, which works for directories too.
+fn open_for_query(p: &Path) -> io::Result<File> {
+    let mut o = OpenOptions::new();
+    // directories can only be opened with backup semantics
+    o.dwFlagsAndAttributes |= c::FILE_FLAG_BACKUP_SEMANTICS;
+    File::open(p, &o)
+}
+
+pub fn file_id(p: &Path) -> io::Result<(u64, u64)> {
+    let f = try!(open_for_query(p));
+    unsafe {
+        let mut info: c::BY_HANDLE_FILE_INFORMATION = mem::zeroed();
+        try!(call!(c::GetFileInformationByHandle(f.handle.raw(), &mut info)));
+        Ok((info.dwVolumeSerialNumber as u64,
+            ((info.nFileIndexHigh as u64) << 32) | info.nFileIndexLow as u64))
+    }
+}
+
+pub fn canonicalize(p: &Path) -> io::Result<Path> {
+    use sys::c::compat::kernel32::GetFinalPathNameByHandleW;
+    let f = try!(open_for_query(p));
+    let ret = try!(super::fill_utf16_buf_and_decode(|buf, sz| unsafe {
+        GetFinalPathNameByHandleW(f.handle.raw(), buf as *const u16, sz - 1,
+                                  libc::VOLUME_NAME_DOS)
+    }));
+    match String::from_utf16(ret.as_slice()) {
+        Ok(ref s) if s.starts_with(r"\\?\") => Ok(Path::new(&s[4..])),
+        Ok(s) => Ok(Path::new(s)),
+        Err(..) => Err(Error::new(ErrorKind::InvalidInput,
+                                  "path was not valid UTF-16", None)),
+    }
+}
+
 pub fn set_perm(p: &Path, perm: FilePermission) -> io::Result<()> {
     let p = try!(to_utf16(p));
     unsafe {
@@ -476,9 +669,24 @@ pub fn set_perm(p: &Path, perm: FilePermission) -> io::Result<()> {
     }
 }
 
-pub fn utimes(p: &Path, atime: u64, mtime: u64) -> io::Result<()> {
-    let atime = super::ms_to_filetime(atime);
-    let mtime = super::ms_to_filetime(mtime);
+// FILETIME is in 100ns intervals since 1601, which was 11644473600 seconds
+// before the unix epoch.
+const INTERVALS_PER_SEC: i64 = 10_000_000;
+const EPOCH_DIFFERENCE: i64 = 11_644_473_600;
+
+fn to_filetime((secs, nanos): (i64, u32)) -> libc::FILETIME {
+    let bits = (secs + EPOCH_DIFFERENCE) * INTERVALS_PER_SEC +
+               (nanos / 100) as i64;
+    libc::FILETIME {
+        dwLowDateTime: bits as u32,
+        dwHighDateTime: ((bits as u64) >> 32) as u32,
+    }
+}
+
+pub fn set_times(p: &Path, atime: (i64, u32),
+                 mtime: (i64, u32)) -> io::Result<()> {
+    let atime = to_filetime(atime);
+    let mtime = to_filetime(mtime);
 
     let mut o = OpenOptions::new();
     o.write(true);
@@ -10,7 +10,9 @@
 
 use prelude::v1::*;
 
+use io;
 use libc::{self, HANDLE};
+use ptr;
 
 pub struct Handle(HANDLE);
 
@@ -22,6 +24,16 @@ impl Handle {
         Handle(handle)
     }
     pub fn raw(&self) -> HANDLE { self.0 }
+
+    pub fn duplicate(&self) -> io::Result<Handle> {
+        let mut ret = ptr::null_mut();
+        try!(call!(unsafe {
+            let cur_proc = libc::GetCurrentProcess();
+            libc::DuplicateHandle(cur_proc, self.0, cur_proc, &mut ret, 0,
+                                  libc::FALSE, libc::DUPLICATE_SAME_ACCESS)
+        }));
+        Ok(Handle::new(ret))
+    }
 }
 
 impl Drop for Handle {
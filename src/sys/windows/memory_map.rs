@@ -0,0 +1,99 @@
+// Copyright 2015 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use prelude::v1::*;
+
+use io::{self, Error};
+use libc::{self, DWORD, SIZE_T, LPCVOID};
+use mem;
+use memory_map::Protection;
+use ptr;
+use sys::c;
+use sys::fs::File;
+use sys::handle::Handle;
+
+pub struct MemoryMap {
+    ptr: *mut u8,
+    len: usize,
+    _mapping: Handle,
+    // kept so that `flush` can wait for the file itself to be written
+    file: Handle,
+}
+
+unsafe impl Send for MemoryMap {}
+unsafe impl Sync for MemoryMap {}
+
+impl MemoryMap {
+    pub fn new(file: &File, prot: Protection, offset: u64, len: usize)
+               -> io::Result<MemoryMap> {
+        let (protect, access) = match prot {
+            Protection::Read => (libc::PAGE_READONLY, libc::FILE_MAP_READ),
+            Protection::ReadWrite => (libc::PAGE_READWRITE,
+                                      libc::FILE_MAP_WRITE),
+            Protection::ReadCopy => (c::PAGE_WRITECOPY, c::FILE_MAP_COPY),
+        };
+        let handle = try!(file.handle().duplicate());
+        unsafe {
+            let mapping = libc::CreateFileMappingW(handle.raw(),
+                                                   ptr::null_mut(),
+                                                   protect, 0, 0,
+                                                   ptr::null());
+            if mapping.is_null() {
+                return Err(Error::last_os_error())
+            }
+            let mapping = Handle::new(mapping);
+            let ptr = libc::MapViewOfFile(mapping.raw(), access,
+                                          (offset >> 32) as DWORD,
+                                          (offset & 0xffff_ffff) as DWORD,
+                                          len as SIZE_T);
+            if ptr.is_null() {
+                Err(Error::last_os_error())
+            } else {
+                Ok(MemoryMap {
+                    ptr: ptr as *mut u8,
+                    len: len,
+                    _mapping: mapping,
+                    file: handle,
+                })
+            }
+        }
+    }
+
+    pub fn granularity() -> usize {
+        unsafe {
+            let mut info = mem::zeroed();
+            libc::GetSystemInfo(&mut info);
+            info.dwAllocationGranularity as usize
+        }
+    }
+
+    pub fn ptr(&self) -> *mut u8 { self.ptr }
+
+    pub fn len(&self) -> usize { self.len }
+
+    pub fn flush(&self, offset: usize, len: usize) -> io::Result<()> {
+        try!(call!(unsafe {
+            c::FlushViewOfFile(self.ptr.offset(offset as isize) as LPCVOID,
+                               len as SIZE_T)
+        }));
+        // FlushViewOfFile only starts writing the pages back, this waits for
+        // them to reach the disk
+        try!(call!(unsafe { libc::FlushFileBuffers(self.file.raw()) }));
+        Ok(())
+    }
+}
+
+impl Drop for MemoryMap {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = libc::UnmapViewOfFile(self.ptr as LPCVOID);
+        }
+    }
+}
@@ -46,10 +46,12 @@ macro_rules! call {
 pub mod c;
 // pub mod ext;
 pub mod fs;
+pub mod memory_map;
 pub mod net;
 pub mod handle;
-// pub mod helper_signal;
+pub mod helper_signal;
 pub mod os;
+pub mod stdio;
 // pub mod pipe;
 // pub mod process;
 // pub mod tcp;
@@ -83,8 +85,12 @@ pub fn decode_error_kind(errno: i32) -> ErrorKind {
         libc::ERROR_ACCESS_DENIED => ErrorKind::PermissionDenied,
         libc::ERROR_ALREADY_EXISTS => ErrorKind::PathAlreadyExists,
         libc::ERROR_BROKEN_PIPE => ErrorKind::BrokenPipe,
+        c::ERROR_FILE_EXISTS => ErrorKind::PathAlreadyExists,
         libc::ERROR_FILE_NOT_FOUND => ErrorKind::FileNotFound,
         libc::ERROR_INVALID_FUNCTION => ErrorKind::InvalidInput,
+        x if x as libc::DWORD == c::ERROR_LOCK_VIOLATION => {
+            ErrorKind::Locked
+        }
         libc::ERROR_INVALID_HANDLE => ErrorKind::MismatchedFileTypeForOperation,
         libc::ERROR_INVALID_NAME => ErrorKind::InvalidInput,
         libc::ERROR_NOTHING_TO_TERMINATE => ErrorKind::InvalidInput,
@@ -99,36 +105,29 @@ pub fn decode_error_kind(errno: i32) -> ErrorKind {
         libc::WSAECONNRESET => ErrorKind::ConnectionReset,
         libc::WSAEINVAL => ErrorKind::InvalidInput,
         libc::WSAENOTCONN => ErrorKind::NotConnected,
-        libc::WSAEWOULDBLOCK => ErrorKind::ResourceUnavailable,
+        c::WSAETIMEDOUT => ErrorKind::TimedOut,
+        libc::WSAEWOULDBLOCK => ErrorKind::WouldBlock,
 
         _ => ErrorKind::Other,
     }
 }
 
-pub fn ms_to_filetime(ms: u64) -> libc::FILETIME {
-    // A FILETIME is a count of 100 nanosecond intervals, so we multiply by
-    // 10000 b/c there are 10000 intervals in 1 ms
-    let ms = ms * 10000;
-    libc::FILETIME {
-        dwLowDateTime: ms as u32,
-        dwHighDateTime: (ms >> 32) as u32,
+pub fn set_nonblocking(sock: libc::SOCKET, nb: bool) -> io::Result<()> {
+    let mut set = nb as libc::c_ulong;
+    if unsafe { c::ioctlsocket(sock, c::FIONBIO, &mut set) != 0 } {
+        Err(Error::from_os_error(unsafe { c::WSAGetLastError() }))
+    } else {
+        Ok(())
     }
 }
 
-// pub fn wouldblock() -> bool {
-//     let err = os::errno();
-//     err == libc::WSAEWOULDBLOCK as uint
-// }
-//
-// pub fn set_nonblocking(fd: sock_t, nb: bool) -> IoResult<()> {
-//     let mut set = nb as libc::c_ulong;
-//     if unsafe { c::ioctlsocket(fd, c::FIONBIO, &mut set) != 0 } {
-//         Err(last_error())
-//     } else {
-//         Ok(())
-//     }
-// }
-//
+/// Only console handles have a console mode, so this is how a handle is
+/// identified as referring to a terminal.
+pub fn is_console(handle: libc::HANDLE) -> bool {
+    let mut mode = 0;
+    unsafe { c::GetConsoleMode(handle, &mut mode) != 0 }
+}
+
 // pub fn init_net() {
 //     unsafe {
 //         static START: Once = ONCE_INIT;
@@ -3,13 +3,17 @@ use prelude::v1::*;
 use io;
 use libc::consts::os::extra::INVALID_SOCKET;
 use libc::{self, c_int, c_void};
+use cmp;
 use mem;
 use net::{SocketAddr, IpAddr};
 use num::{SignedInt, Int};
+use ptr;
 use rt;
 use sync::{Once, ONCE_INIT};
-use sys::c;
+use sys::{self, c};
 use sys_common::AsInner;
+use sys_common::net::{getsockopt, setsockopt};
+use time::Duration;
 
 pub type wrlen_t = i32;
 
@@ -98,6 +102,104 @@ impl Socket {
             }
         }
     }
+
+    // FIXME: these should use WSARecv and WSASend to transfer all of the
+    //        buffers at once, for now only the first nonempty one is used.
+    pub fn read_vectored(&self, bufs: &mut [&mut [u8]]) -> io::Result<usize> {
+        match bufs.iter_mut().find(|b| !b.is_empty()) {
+            Some(buf) => self.read(*buf),
+            None => Ok(0),
+        }
+    }
+
+    pub fn write_vectored(&self, bufs: &[&[u8]]) -> io::Result<usize> {
+        let buf = match bufs.iter().find(|b| !b.is_empty()) {
+            Some(buf) => *buf,
+            None => return Ok(0),
+        };
+        let ret = try!(cvt(unsafe {
+            libc::send(self.0, buf.as_ptr() as *const c_void,
+                       buf.len() as i32, 0)
+        }));
+        Ok(ret as usize)
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        sys::set_nonblocking(self.0, nonblocking)
+    }
+
+    pub fn connect_timeout(&self, addr: *const libc::sockaddr,
+                           len: libc::socklen_t,
+                           timeout: Duration) -> io::Result<()> {
+        // Make sure the call to connect() doesn't block, and be sure to turn
+        // blocking I/O back on regardless of how the connect went.
+        try!(self.set_nonblocking(true));
+        let ret = self.connect_nonblocking(addr, len, timeout);
+        try!(self.set_nonblocking(false));
+        ret
+    }
+
+    fn connect_nonblocking(&self, addr: *const libc::sockaddr,
+                           len: libc::socklen_t,
+                           timeout: Duration) -> io::Result<()> {
+        match unsafe { libc::connect(self.0, addr, len) } {
+            -1 if unsafe { c::WSAGetLastError() } == libc::WSAEWOULDBLOCK => {}
+            -1 => return Err(last_error()),
+            _ => return Ok(()),
+        }
+
+        // A successful connect shows up in the write set and a failed one in
+        // the exception set.
+        let mut writefds: c::fd_set = unsafe { mem::zeroed() };
+        let mut errorfds: c::fd_set = unsafe { mem::zeroed() };
+        c::fd_set(&mut writefds, self.0);
+        c::fd_set(&mut errorfds, self.0);
+        let timeout = cmp::max(timeout.num_milliseconds(), 1) as u64;
+        let mut tv = libc::timeval {
+            tv_sec: (timeout / 1000) as libc::c_long,
+            tv_usec: ((timeout % 1000) * 1000) as libc::c_long,
+        };
+        match unsafe {
+            c::select(1, ptr::null_mut(), &mut writefds, &mut errorfds, &mut tv)
+        } {
+            -1 => return Err(last_error()),
+            0 => return Err(io::Error::new(io::ErrorKind::TimedOut,
+                                           "connection timed out", None)),
+            _ => {}
+        }
+
+        let err: c_int = try!(getsockopt(self, libc::SOL_SOCKET,
+                                         libc::SO_ERROR));
+        if err == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::from_os_error(err))
+        }
+    }
+
+    pub fn set_timeout(&self, dur: Option<Duration>,
+                       kind: c_int) -> io::Result<()> {
+        // A timeout of 0 means "no timeout", so round tiny durations up.
+        let timeout = match dur {
+            Some(dur) => {
+                let max: libc::DWORD = Int::max_value();
+                cmp::max(cmp::min(dur.num_milliseconds(), max as i64),
+                         1) as libc::DWORD
+            }
+            None => 0,
+        };
+        setsockopt(self, libc::SOL_SOCKET, kind, timeout)
+    }
+
+    pub fn timeout(&self, kind: c_int) -> io::Result<Option<Duration>> {
+        let timeout: libc::DWORD = try!(getsockopt(self, libc::SOL_SOCKET,
+                                                   kind));
+        if timeout == 0 {
+            Ok(None)
+        } else {
+            Ok(Some(Duration::milliseconds(timeout as i64)))
+        }
+    }
 }
 
 impl Drop for Socket {
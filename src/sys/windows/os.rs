@@ -236,6 +236,10 @@ pub fn current_exe() -> Option<Path> {
     }).ok()
 }
 
+pub fn getpid() -> u32 {
+    unsafe { c::GetCurrentProcessId() as u32 }
+}
+
 pub fn getcwd() -> io::Result<Path> {
     let buf = try!(super::fill_utf16_buf_and_decode(|buf, sz| unsafe {
         libc::GetCurrentDirectoryW(sz, buf)
@@ -326,6 +330,17 @@ pub fn args() -> Args {
     }
 }
 
+pub fn home_dir() -> Option<Path> {
+    fn var(key: &str) -> Option<Path> {
+        // TODO: OsString -> Path
+        getenv(OsStr::from_str(key)).and_then(|s| s.into_string().ok())
+                                    .and_then(|s| {
+            if s.is_empty() {None} else {Some(Path::new(s))}
+        })
+    }
+    var("HOME").or_else(|| var("USERPROFILE"))
+}
+
 pub fn page_size() -> usize {
     unsafe {
         let mut info = mem::zeroed();
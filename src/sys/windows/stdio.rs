@@ -0,0 +1,101 @@
+// Copyright 2015 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// FIXME: consoles should be read and written with `ReadConsoleW` and
+//        `WriteConsoleW` so unicode input and output isn't mangled by the
+//        active code page. For now all handles are treated as byte streams.
+
+use prelude::v1::*;
+
+use io::{self, Error};
+use libc;
+use ptr;
+use sys::{self, c};
+
+// The handles returned by `GetStdHandle` are owned by the process, so unlike
+// `sys::handle::Handle` these are never closed.
+pub struct Stdin(libc::HANDLE);
+pub struct Stdout(libc::HANDLE);
+pub struct Stderr(libc::HANDLE);
+
+unsafe impl Send for Stdin {}
+unsafe impl Send for Stdout {}
+unsafe impl Send for Stderr {}
+
+fn get(which: libc::DWORD) -> io::Result<libc::HANDLE> {
+    let handle = unsafe { c::GetStdHandle(which) };
+    if handle == libc::INVALID_HANDLE_VALUE {
+        Err(Error::last_os_error())
+    } else {
+        Ok(handle)
+    }
+}
+
+fn write(handle: libc::HANDLE, data: &[u8]) -> io::Result<usize> {
+    let mut amt = 0;
+    try!(call!(unsafe {
+        libc::WriteFile(handle,
+                        data.as_ptr() as libc::LPVOID,
+                        data.len() as libc::DWORD,
+                        &mut amt,
+                        ptr::null_mut())
+    }));
+    Ok(amt as usize)
+}
+
+impl Stdin {
+    pub fn new() -> io::Result<Stdin> {
+        get(c::STD_INPUT_HANDLE).map(Stdin)
+    }
+
+    pub fn is_terminal(&self) -> bool { sys::is_console(self.0) }
+
+    pub fn read(&self, data: &mut [u8]) -> io::Result<usize> {
+        let mut read = 0;
+        match unsafe {
+            libc::ReadFile(self.0,
+                           data.as_mut_ptr() as libc::LPVOID,
+                           data.len() as libc::DWORD,
+                           &mut read,
+                           ptr::null_mut())
+        } {
+            // A closed pipe on the other end is how EOF is signaled for
+            // redirected input.
+            0 if unsafe { libc::GetLastError() } ==
+                    libc::ERROR_BROKEN_PIPE as libc::DWORD => Ok(0),
+            0 => Err(Error::last_os_error()),
+            _ => Ok(read as usize),
+        }
+    }
+}
+
+impl Stdout {
+    pub fn new() -> io::Result<Stdout> {
+        get(c::STD_OUTPUT_HANDLE).map(Stdout)
+    }
+
+    pub fn is_terminal(&self) -> bool { sys::is_console(self.0) }
+
+    pub fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        write(self.0, data)
+    }
+}
+
+impl Stderr {
+    pub fn new() -> io::Result<Stderr> {
+        get(c::STD_ERROR_HANDLE).map(Stderr)
+    }
+
+    pub fn is_terminal(&self) -> bool { sys::is_console(self.0) }
+
+    pub fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        write(self.0, data)
+    }
+}
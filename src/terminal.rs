@@ -0,0 +1,211 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Terminal control: detecting terminals, switching them into raw or cbreak
+//! mode, turning echo off and querying their size.
+//!
+//! Whether a handle refers to a terminal at all can be checked on any
+//! platform with the `is_terminal` methods of `File` and the standard I/O
+//! handles.
+
+use prelude::v1::*;
+use io::prelude::*;
+
+use io;
+use libc;
+use mem;
+use path::Path;
+use sys::fd::FileDesc;
+use sys::tty as imp;
+use sys_common::{AsInner, FromInner, IntoInner};
+
+/// A handle to a terminal device whose settings can be changed.
+///
+/// Every mode change returns a `ModeGuard` which puts back the settings the
+/// terminal had beforehand when it goes out of scope, so a program can't
+/// accidentally leave the user's terminal in raw mode.
+///
+/// # Example
+///
+/// ```no_run
+/// use io2::io::prelude::*;
+/// use io2::terminal::Terminal;
+///
+/// let term = Terminal::stdin().unwrap();
+/// let mut password = String::new();
+/// {
+///     let _guard = term.set_echo(false).unwrap();
+///     (&term).read_to_string(&mut password).unwrap();
+/// }
+/// ```
+pub struct Terminal(imp::Terminal);
+
+/// Restores the settings of a terminal when dropped.
+///
+/// Created by the mode-changing methods of `Terminal`.
+#[must_use]
+pub struct ModeGuard<'a> {
+    term: &'a Terminal,
+    prev: imp::Attr,
+}
+
+impl Terminal {
+    /// Returns a handle to the terminal on the standard input of this
+    /// process.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if standard input is not a terminal.
+    pub fn stdin() -> io::Result<Terminal> {
+        Terminal::std(libc::STDIN_FILENO)
+    }
+
+    /// Returns a handle to the terminal on the standard output of this
+    /// process.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if standard output is not a terminal.
+    pub fn stdout() -> io::Result<Terminal> {
+        Terminal::std(libc::STDOUT_FILENO)
+    }
+
+    /// Returns a handle to the terminal on the standard error of this
+    /// process.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if standard error is not a terminal.
+    pub fn stderr() -> io::Result<Terminal> {
+        Terminal::std(libc::STDERR_FILENO)
+    }
+
+    fn std(fd: libc::c_int) -> io::Result<Terminal> {
+        // The standard descriptors are never closed by a `FileDesc`
+        imp::Terminal::new(FileDesc::new(fd)).map(Terminal)
+    }
+
+    /// Opens the terminal device at `path` for reading and writing, for
+    /// example `/dev/tty` for the controlling terminal of this process.
+    /// Opening a terminal never makes it the controlling terminal.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `path` can't be opened or is not a terminal.
+    pub fn open(path: &Path) -> io::Result<Terminal> {
+        imp::Terminal::open(path).map(Terminal)
+    }
+
+    /// Returns the size of this terminal as a `(columns, rows)` pair.
+    pub fn window_size(&self) -> io::Result<(u16, u16)> {
+        self.0.window_size()
+    }
+
+    /// Puts this terminal into raw mode.
+    ///
+    /// Input is available byte by byte as it is typed and isn't echoed,
+    /// special characters such as ctrl-C are delivered as input instead of
+    /// generating signals, and output is written without any processing
+    /// (e.g. `\n` is no longer translated to `\r\n`).
+    pub fn raw_mode(&self) -> io::Result<ModeGuard> {
+        self.change(|attr| attr.set_raw())
+    }
+
+    /// Puts this terminal into cbreak mode.
+    ///
+    /// Like raw mode, input is available byte by byte and isn't echoed, but
+    /// special characters still generate signals and output is processed as
+    /// usual.
+    pub fn cbreak_mode(&self) -> io::Result<ModeGuard> {
+        self.change(|attr| attr.set_cbreak())
+    }
+
+    /// Turns echoing of input on or off.
+    ///
+    /// With echo off the text typed is not displayed, which is what password
+    /// prompts want. The newline ending the line is still echoed.
+    pub fn set_echo(&self, echo: bool) -> io::Result<ModeGuard> {
+        self.change(|attr| attr.set_echo(echo))
+    }
+
+    /// Returns whether input is currently echoed.
+    pub fn echo(&self) -> io::Result<bool> {
+        self.0.get_attr().map(|attr| attr.echo())
+    }
+
+    fn change<F>(&self, f: F) -> io::Result<ModeGuard>
+        where F: FnOnce(&mut imp::Attr)
+    {
+        let prev = try!(self.0.get_attr());
+        let mut attr = prev;
+        f(&mut attr);
+        try!(self.0.set_attr(&attr));
+        Ok(ModeGuard { term: self, prev: prev })
+    }
+}
+
+impl Read for Terminal {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl Write for Terminal {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
+
+// Reading and writing through a shared reference allows a terminal to be used
+// while a `ModeGuard` borrows it.
+impl<'a> Read for &'a Terminal {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl<'a> Write for &'a Terminal {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
+
+impl AsInner<FileDesc> for Terminal {
+    fn as_inner(&self) -> &FileDesc { self.0.fd() }
+}
+
+impl FromInner<FileDesc> for Terminal {
+    fn from_inner(fd: FileDesc) -> Terminal {
+        Terminal(FromInner::from_inner(fd))
+    }
+}
+
+impl IntoInner<FileDesc> for Terminal {
+    fn into_inner(self) -> FileDesc { self.0.into_fd() }
+}
+
+impl<'a> ModeGuard<'a> {
+    /// Restores the previous settings now, reporting any error which would
+    /// otherwise be ignored when the guard is dropped.
+    pub fn restore(self) -> io::Result<()> {
+        let ret = self.term.0.set_attr(&self.prev);
+        mem::forget(self);
+        ret
+    }
+}
+
+#[unsafe_destructor]
+impl<'a> Drop for ModeGuard<'a> {
+    fn drop(&mut self) {
+        let _ = self.term.0.set_attr(&self.prev);
+    }
+}
@@ -0,0 +1,87 @@
+// Copyright 2015 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Temporal quantification and timers
+
+pub use std::time::Duration;
+
+#[cfg(unix)] pub use self::timer::Timer;
+
+#[cfg(unix)]
+mod timer {
+    use prelude::v1::*;
+
+    use io;
+    use sync::mpsc::{channel, Receiver};
+    use sys::timer as imp;
+    use super::Duration;
+
+    /// A timer which can block the current thread or send notifications on a
+    /// channel after a period of time.
+    ///
+    /// A timer has at most one request outstanding at a time: each call to
+    /// `sleep`, `oneshot` or `periodic` cancels whatever the timer was
+    /// previously doing, as does dropping the timer. The receiver handed out
+    /// by a cancelled request is disconnected without receiving any further
+    /// notifications.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use io2::time::{Duration, Timer};
+    ///
+    /// let mut timer = Timer::new().unwrap();
+    /// let ticks = timer.periodic(Duration::milliseconds(10));
+    /// for _ in 0..3 {
+    ///     ticks.recv().unwrap();
+    /// }
+    /// ```
+    pub struct Timer(imp::Timer);
+
+    impl Timer {
+        /// Creates a new timer.
+        pub fn new() -> io::Result<Timer> {
+            imp::Timer::new().map(Timer)
+        }
+
+        /// Blocks the current thread for `dur`.
+        ///
+        /// A zero or negative duration returns immediately.
+        pub fn sleep(&mut self, dur: Duration) {
+            self.0.sleep(dur)
+        }
+
+        /// Returns a receiver which is notified once after `dur`.
+        ///
+        /// A zero or negative duration is notified immediately.
+        pub fn oneshot(&mut self, dur: Duration) -> Receiver<()> {
+            let (tx, rx) = channel();
+            if dur <= Duration::zero() {
+                self.0.cancel();
+                tx.send(()).unwrap();
+            } else {
+                self.0.oneshot(dur, tx);
+            }
+            rx
+        }
+
+        /// Returns a receiver which is notified every `dur` until the timer
+        /// is cancelled.
+        ///
+        /// A zero or negative duration is rounded up to the shortest period
+        /// the platform supports. If the receiver falls behind it receives
+        /// one notification per missed period.
+        pub fn periodic(&mut self, dur: Duration) -> Receiver<()> {
+            let (tx, rx) = channel();
+            self.0.period(dur, tx);
+            rx
+        }
+    }
+}
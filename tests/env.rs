@@ -0,0 +1,187 @@
+#![feature(std_misc, path, os, rand)]
+
+extern crate io2;
+
+use io2::env::*;
+use io2::process::Command;
+use std::ffi::{OsString, AsOsStr};
+use std::rand::{self, Rng};
+
+fn make_rand_name() -> OsString {
+    let mut rng = rand::thread_rng();
+    let n = format!("TEST{}", rng.gen_ascii_chars().take(10)
+                                 .collect::<String>());
+    let n = OsString::from_string(n);
+    assert!(var(&n).is_none());
+    n
+}
+
+fn eq(a: Option<OsString>, b: Option<&str>) {
+    assert_eq!(a.as_ref().map(|s| &**s), b.map(AsOsStr::as_os_str));
+}
+
+#[test]
+fn test_set_var() {
+    let n = make_rand_name();
+    set_var(&n, "VALUE");
+    eq(var(&n), Some("VALUE"));
+}
+
+#[test]
+fn test_remove_var() {
+    let n = make_rand_name();
+    set_var(&n, "VALUE");
+    remove_var(&n);
+    eq(var(&n), None);
+}
+
+#[test]
+fn test_set_var_overwrite() {
+    let n = make_rand_name();
+    set_var(&n, "1");
+    set_var(&n, "2");
+    eq(var(&n), Some("2"));
+    set_var(&n, "");
+    eq(var(&n), Some(""));
+}
+
+#[test]
+fn test_vars() {
+    let n = make_rand_name();
+    set_var(&n, "VALUE");
+    let key = n.into_string().unwrap();
+    assert!(vars().any(|(k, v)| k == key && v == "VALUE"));
+}
+
+#[test]
+fn test_vars_os() {
+    let n = make_rand_name();
+    set_var(&n, "VALUE");
+    let value = OsString::from_str("VALUE");
+    assert!(vars_os().any(|(k, v)| k == n && v == value));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_vars_os_non_unicode() {
+    use std::os::unix::OsStringExt;
+
+    let n = make_rand_name();
+    let value: OsString = OsStringExt::from_vec(vec![0xff, 0xfe]);
+    set_var(&n, &value);
+    assert!(vars_os().any(|(k, v)| k == n && v == value));
+    remove_var(&n);
+}
+
+#[test]
+fn test_args() {
+    let args = args_os().collect::<Vec<_>>();
+    assert!(args.len() >= 1);
+    assert_eq!(args_os().count(), args().count());
+}
+
+#[test]
+fn test_home_dir() {
+    // Other tests may read HOME while this one runs, so it is only changed
+    // in a child which runs nothing but this test.
+    if var("IO2_TEST_HOME_DIR").is_none() {
+        let exe = current_exe().unwrap();
+        let status = Command::new(exe.as_str().unwrap())
+                             .arg("test_home_dir")
+                             .env("IO2_TEST_HOME_DIR", "1")
+                             .status().unwrap();
+        assert!(status.success());
+        return
+    }
+
+    set_var("HOME", "/home/MountainView");
+    assert!(home_dir() == Some(Path::new("/home/MountainView")));
+}
+
+#[test]
+#[cfg(unix)]
+fn split_paths_unix() {
+    fn check_parse(unparsed: &str, parsed: &[&str]) -> bool {
+        split_paths(unparsed).collect::<Vec<_>>() ==
+            parsed.iter().map(|s| Path::new(*s)).collect::<Vec<_>>()
+    }
+
+    assert!(check_parse("", &mut [""]));
+    assert!(check_parse("::", &mut ["", "", ""]));
+    assert!(check_parse("/", &mut ["/"]));
+    assert!(check_parse("/:", &mut ["/", ""]));
+    assert!(check_parse("/:/usr/local", &mut ["/", "/usr/local"]));
+}
+
+#[test]
+#[cfg(unix)]
+fn join_paths_unix() {
+    fn test_eq(input: &[&str], output: &str) -> bool {
+        join_paths(input.iter().map(|s| *s)).unwrap() ==
+            OsString::from_str(output)
+    }
+
+    assert!(test_eq(&[], ""));
+    assert!(test_eq(&["/bin", "/usr/bin", "/usr/local/bin"],
+                    "/bin:/usr/bin:/usr/local/bin"));
+    assert!(test_eq(&["", "/bin", "", "", "/usr/bin", ""],
+                    ":/bin:::/usr/bin:"));
+    assert!(join_paths(["/te:st"].iter().map(|s| *s)).is_err());
+}
+
+#[test]
+#[cfg(unix)]
+fn join_paths_error() {
+    let err = join_paths(["/bin", "/usr/local:/bin"].iter().map(|s| *s))
+                  .err().unwrap();
+    assert_eq!(err.to_string(), "path segment contains separator `:`");
+}
+
+#[test]
+#[cfg(unix)]
+fn split_join_round_trip() {
+    let input = "/bin:/usr/bin::/usr/local/bin";
+    let paths = split_paths(input).collect::<Vec<_>>();
+    assert_eq!(paths.len(), 4);
+    let joined = join_paths(paths.iter()).unwrap();
+    assert_eq!(joined, OsString::from_str(input));
+    assert_eq!(split_paths(&joined).collect::<Vec<_>>(), paths);
+}
+
+#[test]
+#[cfg(windows)]
+fn split_paths_windows() {
+    fn check_parse(unparsed: &str, parsed: &[&str]) -> bool {
+        split_paths(unparsed).collect::<Vec<_>>() ==
+            parsed.iter().map(|s| Path::new(*s)).collect::<Vec<_>>()
+    }
+
+    assert!(check_parse("", &mut [""]));
+    assert!(check_parse(r#""""#, &mut [""]));
+    assert!(check_parse(";;", &mut ["", "", ""]));
+    assert!(check_parse(r"c:\", &mut [r"c:\"]));
+    assert!(check_parse(r"c:\;", &mut [r"c:\", ""]));
+    assert!(check_parse(r"c:\;c:\Program Files\",
+                        &mut [r"c:\", r"c:\Program Files\"]));
+    assert!(check_parse(r#"c:\;c:\"foo"\"#, &mut [r"c:\", r"c:\foo\"]));
+    assert!(check_parse(r#"c:\;c:\"foo;bar"\;c:\baz"#,
+                        &mut [r"c:\", r"c:\foo;bar\", r"c:\baz"]));
+}
+
+#[test]
+#[cfg(windows)]
+fn join_paths_windows() {
+    fn test_eq(input: &[&str], output: &str) -> bool {
+        join_paths(input.iter().map(|s| *s)).unwrap() ==
+            OsString::from_str(output)
+    }
+
+    assert!(test_eq(&[], ""));
+    assert!(test_eq(&[r"c:\windows", r"c:\"],
+                    r"c:\windows;c:\"));
+    assert!(test_eq(&["", r"c:\windows", "", "", r"c:\", ""],
+                    r";c:\windows;;;c:\;"));
+    assert!(test_eq(&[r"c:\te;st", r"c:\"],
+                    r#""c:\te;st";c:\"#));
+    assert!(join_paths([r#"c:\te"st"#].iter().map(|s| *s)).is_err());
+}
@@ -1,13 +1,14 @@
-#![feature(rand, path, core, os)]
+#![feature(rand, path, core, libc, std_misc)]
 #![allow(warnings)]
 
 extern crate io2;
+#[cfg(unix)] extern crate libc;
 
 use io2::io::prelude::*;
-use io2::fs::{self, File, OpenOptions};
-use io2::io::{ErrorKind, SeekPos};
-use std::os;
-use std::rand::{self, StdRng, Rng};
+use io2::fs::{self, File, OpenOptions, LockKind, FileType, WalkDir};
+use io2::fs::TempDir;
+use io2::io::{ErrorKind, SeekPos, BufWriter, LineWriter};
+use std::rand::{StdRng, Rng};
 use std::str;
 
 macro_rules! check { ($e:expr) => (
@@ -25,40 +26,11 @@ macro_rules! error { ($e:expr, $s:expr) => (
     }
 ) }
 
-pub struct TempDir(Path);
-
-impl TempDir {
-    fn join(&self, path: &str) -> Path {
-        let TempDir(ref p) = *self;
-        p.join(path)
-    }
-
-    fn path<'a>(&'a self) -> &'a Path {
-        let TempDir(ref p) = *self;
-        p
-    }
-}
-
-impl Drop for TempDir {
-    fn drop(&mut self) {
-        // Gee, seeing how we're testing the fs module I sure hope that we
-        // at least implement this correctly!
-        let TempDir(ref p) = *self;
-        check!(fs::remove_dir_all(p));
-    }
-}
-
-pub fn tmpdir() -> TempDir {
-    let ret = os::tmpdir().join(format!("rust-{}", rand::random::<u32>()));
-    check!(fs::make_dir(&ret));
-    TempDir(ret)
-}
-
 #[test]
 fn file_test_io_smoke_test() {
     let message = "it's alright. have a good time";
-    let tmpdir = tmpdir();
-    let filename = &tmpdir.join("file_rt_io_file_test.txt");
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let filename = &tmpdir.path().join("file_rt_io_file_test.txt");
     {
         let mut write_stream = check!(File::create(filename));
         check!(write_stream.write(message.as_bytes()));
@@ -75,10 +47,40 @@ fn file_test_io_smoke_test() {
     check!(fs::remove_file(filename));
 }
 
+#[test]
+fn file_test_buffered_writer() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let filename = &tmpdir.path().join("file_rt_io_file_test_buffered.txt");
+    {
+        let mut writer = BufWriter::new(check!(File::create(filename)));
+        check!(write!(&mut writer, "{}-{}", "ten", "four"));
+        assert_eq!(check!(fs::file_attr(filename)).size(), 0);
+        check!(writer.flush());
+        assert_eq!(check!(fs::file_attr(filename)).size(), 8);
+        check!(writer.write(b"!"));
+    }
+    let mut v = Vec::new();
+    check!(check!(File::open(filename)).read_to_end(&mut v));
+    assert_eq!(v.as_slice(), b"ten-four!");
+}
+
+#[test]
+fn file_test_line_writer() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let filename = &tmpdir.path().join("file_rt_io_file_test_line.txt");
+    let mut writer = LineWriter::new(check!(File::create(filename)));
+    check!(writer.write(b"first"));
+    assert_eq!(check!(fs::file_attr(filename)).size(), 0);
+    check!(writer.write(b" line\nsecond"));
+    assert_eq!(check!(fs::file_attr(filename)).size(), 11);
+    let file = check!(writer.into_inner());
+    assert_eq!(check!(file.file_attr()).size(), 17);
+}
+
 #[test]
 fn invalid_path_raises() {
-    let tmpdir = tmpdir();
-    let filename = &tmpdir.join("file_that_does_not_exist.txt");
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let filename = &tmpdir.path().join("file_that_does_not_exist.txt");
     let result = File::open(filename);
 
     if cfg!(unix) {
@@ -90,8 +92,8 @@ fn invalid_path_raises() {
 
 #[test]
 fn file_test_iounlinking_invalid_path_should_raise_condition() {
-    let tmpdir = tmpdir();
-    let filename = &tmpdir.join("file_another_file_that_does_not_exist.txt");
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let filename = &tmpdir.path().join("file_another_file_that_does_not_exist.txt");
 
     let result = fs::remove_file(filename);
 
@@ -106,8 +108,8 @@ fn file_test_iounlinking_invalid_path_should_raise_condition() {
 fn file_test_io_non_positional_read() {
     let message: &str = "ten-four";
     let mut read_mem = [0; 8];
-    let tmpdir = tmpdir();
-    let filename = &tmpdir.join("file_rt_io_file_test_positional.txt");
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let filename = &tmpdir.path().join("file_rt_io_file_test_positional.txt");
     {
         let mut rw_stream = check!(File::create(filename));
         check!(rw_stream.write(message.as_bytes()));
@@ -135,8 +137,8 @@ fn file_test_io_seek_and_tell_smoke_test() {
     let set_cursor = 4 as u64;
     let mut tell_pos_pre_read;
     let mut tell_pos_post_read;
-    let tmpdir = tmpdir();
-    let filename = &tmpdir.join("file_rt_io_file_test_seeking.txt");
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let filename = &tmpdir.path().join("file_rt_io_file_test_seeking.txt");
     {
         let mut rw_stream = check!(File::create(filename));
         check!(rw_stream.write(message.as_bytes()));
@@ -162,8 +164,8 @@ fn file_test_io_seek_and_write() {
     let final_msg =     "foo-the-bar!!";
     let seek_idx = 3;
     let mut read_mem = [0; 13];
-    let tmpdir = tmpdir();
-    let filename = &tmpdir.join("file_rt_io_file_test_seek_and_write.txt");
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let filename = &tmpdir.path().join("file_rt_io_file_test_seek_and_write.txt");
     {
         let mut rw_stream = check!(File::create(filename));
         check!(rw_stream.write(initial_msg.as_bytes()));
@@ -187,8 +189,8 @@ fn file_test_io_seek_shakedown() {
     let chunk_two: &str = "asdf";
     let chunk_three: &str = "zxcv";
     let mut read_mem = [0; 4];
-    let tmpdir = tmpdir();
-    let filename = &tmpdir.join("file_rt_io_file_test_seek_shakedown.txt");
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let filename = &tmpdir.path().join("file_rt_io_file_test_seek_shakedown.txt");
     {
         let mut rw_stream = check!(File::create(filename));
         check!(rw_stream.write(initial_msg.as_bytes()));
@@ -211,10 +213,130 @@ fn file_test_io_seek_shakedown() {
     check!(fs::remove_file(filename));
 }
 
+#[test]
+fn file_test_io_positional_read() {
+    let message: &str = "ten-four";
+    let mut read_mem = [0; 8];
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let filename = &tmpdir.path().join("file_rt_io_file_test_read_at.txt");
+    {
+        let mut rw_stream = check!(File::create(filename));
+        check!(rw_stream.write(message.as_bytes()));
+    }
+    {
+        let read_stream = check!(File::open(filename));
+        {
+            let read_buf = &mut read_mem[4..8];
+            check!(read_stream.read_exact_at(read_buf, 4));
+        }
+        {
+            let read_buf = &mut read_mem[0..4];
+            check!(read_stream.read_exact_at(read_buf, 0));
+        }
+        assert_eq!(check!(read_stream.read_at(&mut [0; 4], 8)), 0);
+        assert_eq!(check!(read_stream.read_at(&mut [0; 4], 100)), 0);
+    }
+    check!(fs::remove_file(filename));
+    let read_str = str::from_utf8(&read_mem).unwrap();
+    assert_eq!(read_str, message);
+}
+
+#[test]
+#[cfg(unix)]
+fn file_test_io_positional_read_keeps_cursor() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let filename = &tmpdir.path().join("file_rt_io_file_test_read_at_cursor.txt");
+    check!(check!(File::create(filename)).write(b"qwer-asdf"));
+
+    let mut read_stream = check!(File::open(filename));
+    let mut read_mem = [0; 4];
+    check!(read_stream.read_exact_at(&mut read_mem, 5));
+    assert_eq!(&read_mem, b"asdf");
+    assert_eq!(check!(read_stream.seek(SeekPos::FromCur(0))), 0);
+    check!(read_stream.read(&mut read_mem));
+    assert_eq!(&read_mem, b"qwer");
+}
+
+#[test]
+fn file_test_io_positional_shakedown() {
+    //                   01234567890123
+    let initial_msg =   "qwer-asdf-zxcv";
+    let chunk_one: &str = "qwer";
+    let chunk_two: &str = "asdf";
+    let chunk_three: &str = "zxcv";
+    let mut read_mem = [0; 4];
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let filename = &tmpdir.path().join("file_rt_io_file_test_positional_shakedown.txt");
+    {
+        let rw_stream = check!(File::create(filename));
+        check!(rw_stream.write_all_at(initial_msg.as_bytes(), 0));
+    }
+    {
+        let read_stream = check!(File::open(filename));
+
+        check!(read_stream.read_exact_at(&mut read_mem, 10));
+        assert_eq!(str::from_utf8(&read_mem).unwrap(), chunk_three);
+
+        check!(read_stream.read_exact_at(&mut read_mem, 5));
+        assert_eq!(str::from_utf8(&read_mem).unwrap(), chunk_two);
+
+        check!(read_stream.read_exact_at(&mut read_mem, 0));
+        assert_eq!(str::from_utf8(&read_mem).unwrap(), chunk_one);
+    }
+    check!(fs::remove_file(filename));
+}
+
+#[test]
+fn file_test_io_positional_write() {
+    let initial_msg =   "food-is-yummy";
+    let overwrite_msg =    "-the-bar!!";
+    let final_msg =     "foo-the-bar!!";
+    let mut read_mem = [0; 13];
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let filename = &tmpdir.path().join("file_rt_io_file_test_write_at.txt");
+    {
+        let rw_stream = check!(File::create(filename));
+        check!(rw_stream.write_all_at(initial_msg.as_bytes(), 0));
+        check!(rw_stream.write_all_at(overwrite_msg.as_bytes(), 3));
+    }
+    {
+        let read_stream = check!(File::open(filename));
+        check!(read_stream.read_exact_at(&mut read_mem, 0));
+    }
+    check!(fs::remove_file(filename));
+    let read_str = str::from_utf8(&read_mem).unwrap();
+    assert!(read_str == final_msg);
+}
+
+#[test]
+fn file_test_io_positional_write_extends() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let filename = &tmpdir.path().join("file_rt_io_file_test_write_at_extends.txt");
+    let rw_stream = check!(File::create(filename));
+    check!(rw_stream.write_all_at(b"end", 5));
+    assert_eq!(check!(rw_stream.file_attr()).size(), 8);
+
+    let read_stream = check!(File::open(filename));
+    let mut read_mem = [1; 8];
+    check!(read_stream.read_exact_at(&mut read_mem, 0));
+    assert_eq!(&read_mem, b"\0\0\0\0\0end");
+}
+
+#[test]
+fn file_test_io_read_exact_at_eof() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let filename = &tmpdir.path().join("file_rt_io_file_test_read_exact_at_eof.txt");
+    check!(check!(File::create(filename)).write(b"short"));
+    let read_stream = check!(File::open(filename));
+    let err = read_stream.read_exact_at(&mut [0; 8], 0).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::EndOfFile);
+    check!(read_stream.read_exact_at(&mut [], 100));
+}
+
 #[test]
 fn file_test_stat_is_correct_on_is_file() {
-    let tmpdir = tmpdir();
-    let filename = &tmpdir.join("file_stat_correct_on_is_file.txt");
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let filename = &tmpdir.path().join("file_stat_correct_on_is_file.txt");
     {
         let mut opts = OpenOptions::new();
         let mut fs = check!(File::open_opts(filename,
@@ -235,8 +357,8 @@ fn file_test_stat_is_correct_on_is_file() {
 
 #[test]
 fn file_test_stat_is_correct_on_is_dir() {
-    let tmpdir = tmpdir();
-    let filename = &tmpdir.join("file_stat_correct_on_is_dir");
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let filename = &tmpdir.path().join("file_stat_correct_on_is_dir");
     check!(fs::make_dir(filename));
     let stat_res_fn = check!(fs::file_attr(filename));
     assert!(stat_res_fn.is_dir());
@@ -247,8 +369,8 @@ fn file_test_stat_is_correct_on_is_dir() {
 
 #[test]
 fn file_test_fileinfo_false_when_checking_is_file_on_a_directory() {
-    let tmpdir = tmpdir();
-    let dir = &tmpdir.join("fileinfo_false_on_dir");
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let dir = &tmpdir.path().join("fileinfo_false_on_dir");
     check!(fs::make_dir(dir));
     assert!(dir.is_file() == false);
     check!(fs::remove_dir(dir));
@@ -256,8 +378,8 @@ fn file_test_fileinfo_false_when_checking_is_file_on_a_directory() {
 
 #[test]
 fn file_test_fileinfo_check_exists_before_and_after_file_creation() {
-    let tmpdir = tmpdir();
-    let file = &tmpdir.join("fileinfo_check_exists_b_and_a.txt");
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let file = &tmpdir.path().join("fileinfo_check_exists_b_and_a.txt");
     check!(check!(File::create(file)).write(b"foo"));
     assert!(file.exists());
     check!(fs::remove_file(file));
@@ -266,8 +388,8 @@ fn file_test_fileinfo_check_exists_before_and_after_file_creation() {
 
 #[test]
 fn file_test_directoryinfo_check_exists_before_and_after_mkdir() {
-    let tmpdir = tmpdir();
-    let dir = &tmpdir.join("before_and_after_dir");
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let dir = &tmpdir.path().join("before_and_after_dir");
     assert!(!dir.exists());
     check!(fs::make_dir(dir));
     assert!(dir.exists());
@@ -278,8 +400,8 @@ fn file_test_directoryinfo_check_exists_before_and_after_mkdir() {
 
 #[test]
 fn file_test_directoryinfo_readdir() {
-    let tmpdir = tmpdir();
-    let dir = &tmpdir.join("di_readdir");
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let dir = &tmpdir.path().join("di_readdir");
     check!(fs::make_dir(dir));
     let prefix = "foo";
     for n in range(0, 3) {
@@ -310,8 +432,8 @@ fn file_test_directoryinfo_readdir() {
 
 #[test]
 fn file_test_walk_dir() {
-    let tmpdir = tmpdir();
-    let dir = &tmpdir.join("walk_dir");
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let dir = &tmpdir.path().join("walk_dir");
     check!(fs::make_dir(dir));
 
     let dir1 = &dir.join("01/02/03");
@@ -338,8 +460,8 @@ fn file_test_walk_dir() {
 
 #[test]
 fn mkdir_path_already_exists_error() {
-    let tmpdir = tmpdir();
-    let dir = &tmpdir.join("mkdir_error_twice");
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let dir = &tmpdir.path().join("mkdir_error_twice");
     check!(fs::make_dir(dir));
     let e = fs::make_dir(dir).err().unwrap();
     assert_eq!(e.kind(), ErrorKind::PathAlreadyExists);
@@ -347,16 +469,16 @@ fn mkdir_path_already_exists_error() {
 
 #[test]
 fn recursive_mkdir() {
-    let tmpdir = tmpdir();
-    let dir = tmpdir.join("d1/d2");
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let dir = tmpdir.path().join("d1/d2");
     check!(fs::make_dir_all(&dir));
     assert!(dir.is_dir())
 }
 
 #[test]
 fn recursive_mkdir_failure() {
-    let tmpdir = tmpdir();
-    let dir = tmpdir.join("d1");
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let dir = tmpdir.path().join("d1");
     let file = dir.join("f1");
 
     check!(fs::make_dir_all(&dir));
@@ -380,11 +502,11 @@ fn recursive_mkdir_slash() {
 #[cfg(not(windows))]
 #[test]
 fn recursive_rmdir() {
-    let tmpdir = tmpdir();
-    let d1 = tmpdir.join("d1");
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let d1 = tmpdir.path().join("d1");
     let dt = d1.join("t");
     let dtt = dt.join("t");
-    let d2 = tmpdir.join("d2");
+    let d2 = tmpdir.path().join("d2");
     let canary = d2.join("do_not_delete");
     check!(fs::make_dir_all(&dtt));
     check!(fs::make_dir_all(&d2));
@@ -401,7 +523,7 @@ fn unicode_path_is_dir() {
     assert!(Path::new(".").is_dir());
     assert!(!Path::new("test/stdtest/fs.rs").is_dir());
 
-    let tmpdir = tmpdir();
+    let tmpdir = check!(TempDir::new("io2-fs"));
 
     let mut dirpath = tmpdir.path().clone();
     dirpath.push(format!("test-가一ー你好"));
@@ -420,7 +542,7 @@ fn unicode_path_exists() {
     assert!(Path::new(".").exists());
     assert!(!Path::new("test/nonexistent-bogus-path").exists());
 
-    let tmpdir = tmpdir();
+    let tmpdir = check!(TempDir::new("io2-fs"));
     let unicode = tmpdir.path();
     let unicode = unicode.join(format!("test-각丁ー再见"));
     check!(fs::make_dir(&unicode));
@@ -444,9 +566,9 @@ fn copy_file_does_not_exist() {
 
 #[test]
 fn copy_file_ok() {
-    let tmpdir = tmpdir();
-    let input = tmpdir.join("in.txt");
-    let out = tmpdir.join("out.txt");
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let input = tmpdir.path().join("in.txt");
+    let out = tmpdir.path().join("out.txt");
 
     check!(check!(File::create(&input)).write(b"hello"));
     check!(fs::copy(&input, &out));
@@ -460,8 +582,8 @@ fn copy_file_ok() {
 
 #[test]
 fn copy_file_dst_dir() {
-    let tmpdir = tmpdir();
-    let out = tmpdir.join("out");
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let out = tmpdir.path().join("out");
 
     check!(File::create(&out));
     match fs::copy(&out, tmpdir.path()) {
@@ -471,9 +593,9 @@ fn copy_file_dst_dir() {
 
 #[test]
 fn copy_file_dst_exists() {
-    let tmpdir = tmpdir();
-    let input = tmpdir.join("in");
-    let output = tmpdir.join("out");
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let input = tmpdir.path().join("in");
+    let output = tmpdir.path().join("out");
 
     check!(check!(File::create(&input)).write("foo".as_bytes()));
     check!(check!(File::create(&output)).write("bar".as_bytes()));
@@ -486,8 +608,8 @@ fn copy_file_dst_exists() {
 
 #[test]
 fn copy_file_src_dir() {
-    let tmpdir = tmpdir();
-    let out = tmpdir.join("out");
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let out = tmpdir.path().join("out");
 
     match fs::copy(tmpdir.path(), &out) {
         Ok(..) => panic!(), Err(..) => {}
@@ -497,9 +619,9 @@ fn copy_file_src_dir() {
 
 #[test]
 fn copy_file_preserves_perm_bits() {
-    let tmpdir = tmpdir();
-    let input = tmpdir.join("in.txt");
-    let out = tmpdir.join("out.txt");
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let input = tmpdir.path().join("in.txt");
+    let out = tmpdir.path().join("out.txt");
 
     let attr = check!(check!(File::create(&input)).file_attr());
     let mut p = attr.perm();
@@ -512,16 +634,15 @@ fn copy_file_preserves_perm_bits() {
 #[cfg(not(windows))] // FIXME(#10264) operation not permitted?
 #[test]
 fn symlinks_work() {
-    let tmpdir = tmpdir();
-    let input = tmpdir.join("in.txt");
-    let out = tmpdir.join("out.txt");
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let input = tmpdir.path().join("in.txt");
+    let out = tmpdir.path().join("out.txt");
 
     check!(check!(File::create(&input)).write("foobar".as_bytes()));
     check!(fs::sym_link(&input, &out));
-    // if cfg!(not(windows)) {
-    //     assert_eq!(check!(lstat(&out)).kind, FileType::Symlink);
-    //     assert_eq!(check!(out.lstat()).kind, FileType::Symlink);
-    // }
+    assert_eq!(check!(fs::symlink_attr(&out)).file_type(), FileType::Symlink);
+    assert!(check!(fs::symlink_attr(&out)).is_symlink());
+    assert_eq!(check!(fs::file_attr(&out)).file_type(), FileType::File);
     assert_eq!(check!(fs::file_attr(&out)).size(),
                check!(fs::file_attr(&input)).size());
     let mut v = Vec::new();
@@ -532,16 +653,16 @@ fn symlinks_work() {
 #[cfg(not(windows))] // apparently windows doesn't like symlinks
 #[test]
 fn symlink_noexist() {
-    let tmpdir = tmpdir();
+    let tmpdir = check!(TempDir::new("io2-fs"));
     // symlinks can point to things that don't exist
-    check!(fs::sym_link(&tmpdir.join("foo"), &tmpdir.join("bar")));
-    assert_eq!(check!(fs::read_link(&tmpdir.join("bar"))),
-               tmpdir.join("foo"));
+    check!(fs::sym_link(&tmpdir.path().join("foo"), &tmpdir.path().join("bar")));
+    assert_eq!(check!(fs::read_link(&tmpdir.path().join("bar"))),
+               tmpdir.path().join("foo"));
 }
 
 #[test]
 fn readlink_not_symlink() {
-    let tmpdir = tmpdir();
+    let tmpdir = check!(TempDir::new("io2-fs"));
     match fs::read_link(tmpdir.path()) {
         Ok(..) => panic!("wanted a failure"),
         Err(..) => {}
@@ -550,9 +671,9 @@ fn readlink_not_symlink() {
 
 #[test]
 fn links_work() {
-    let tmpdir = tmpdir();
-    let input = tmpdir.join("in.txt");
-    let out = tmpdir.join("out.txt");
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let input = tmpdir.path().join("in.txt");
+    let out = tmpdir.path().join("out.txt");
 
     check!(check!(File::create(&input)).write("foobar".as_bytes()));
     check!(fs::hard_link(&input, &out));
@@ -570,7 +691,7 @@ fn links_work() {
         Err(..) => {}
     }
     // can't link to something that doesn't exist
-    match fs::hard_link(&tmpdir.join("foo"), &tmpdir.join("bar")) {
+    match fs::hard_link(&tmpdir.path().join("foo"), &tmpdir.path().join("bar")) {
         Ok(..) => panic!("wanted a failure"),
         Err(..) => {}
     }
@@ -578,8 +699,8 @@ fn links_work() {
 
 #[test]
 fn chmod_works() {
-    let tmpdir = tmpdir();
-    let file = tmpdir.join("in.txt");
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let file = tmpdir.path().join("in.txt");
 
     check!(File::create(&file));
     let attr = check!(fs::file_attr(&file));
@@ -590,7 +711,7 @@ fn chmod_works() {
     let attr = check!(fs::file_attr(&file));
     assert!(attr.perm().readonly());
 
-    match fs::set_perm(&tmpdir.join("foo"), p) {
+    match fs::set_perm(&tmpdir.path().join("foo"), p) {
         Ok(..) => panic!("wanted a panic"),
         Err(..) => {}
     }
@@ -598,8 +719,8 @@ fn chmod_works() {
 
 #[test]
 fn sync_doesnt_kill_anything() {
-    let tmpdir = tmpdir();
-    let path = tmpdir.join("in.txt");
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let path = tmpdir.path().join("in.txt");
 
     let mut file = check!(File::create(&path));
     check!(file.flush());
@@ -611,8 +732,8 @@ fn sync_doesnt_kill_anything() {
 
 #[test]
 fn truncate_works() {
-    let tmpdir = tmpdir();
-    let path = tmpdir.join("in.txt");
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let path = tmpdir.path().join("in.txt");
 
     let mut file = check!(File::create(&path));
     check!(file.write(b"foo"));
@@ -648,86 +769,86 @@ fn open_flavors() {
     use io2::fs::OpenOptions as OO;
     fn c<T: Clone>(t: &T) -> T { t.clone() }
 
-    let tmpdir = tmpdir();
+    let tmpdir = check!(TempDir::new("io2-fs"));
 
     let mut r = OO::new(); r.read(true);
     let mut w = OO::new(); w.write(true);
     let mut rw = OO::new(); rw.write(true).read(true);
 
-    match File::open_opts(&tmpdir.join("a"), &r) {
+    match File::open_opts(&tmpdir.path().join("a"), &r) {
         Ok(..) => panic!(), Err(..) => {}
     }
 
     // Perform each one twice to make sure that it succeeds the second time
     // (where the file exists)
-    check!(File::open_opts(&tmpdir.join("b"), c(&w).create(true)));
-    assert!(tmpdir.join("b").exists());
-    check!(File::open_opts(&tmpdir.join("b"), c(&w).create(true)));
-    check!(File::open_opts(&tmpdir.join("b"), &w));
-
-    check!(File::open_opts(&tmpdir.join("c"), c(&rw).create(true)));
-    assert!(tmpdir.join("c").exists());
-    check!(File::open_opts(&tmpdir.join("c"), c(&rw).create(true)));
-    check!(File::open_opts(&tmpdir.join("c"), &rw));
-
-    check!(File::open_opts(&tmpdir.join("d"), c(&w).append(true).create(true)));
-    assert!(tmpdir.join("d").exists());
-    check!(File::open_opts(&tmpdir.join("d"), c(&w).append(true).create(true)));
-    check!(File::open_opts(&tmpdir.join("d"), c(&w).append(true)));
-
-    check!(File::open_opts(&tmpdir.join("e"), c(&rw).append(true).create(true)));
-    assert!(tmpdir.join("e").exists());
-    check!(File::open_opts(&tmpdir.join("e"), c(&rw).append(true).create(true)));
-    check!(File::open_opts(&tmpdir.join("e"), c(&rw).append(true)));
-
-    check!(File::open_opts(&tmpdir.join("f"), c(&w).truncate(true).create(true)));
-    assert!(tmpdir.join("f").exists());
-    check!(File::open_opts(&tmpdir.join("f"), c(&w).truncate(true).create(true)));
-    check!(File::open_opts(&tmpdir.join("f"), c(&w).truncate(true)));
-
-    check!(File::open_opts(&tmpdir.join("g"), c(&rw).truncate(true).create(true)));
-    assert!(tmpdir.join("g").exists());
-    check!(File::open_opts(&tmpdir.join("g"), c(&rw).truncate(true).create(true)));
-    check!(File::open_opts(&tmpdir.join("g"), c(&rw).truncate(true)));
-
-    check!(check!(File::create(&tmpdir.join("h"))).write("foo".as_bytes()));
-    check!(File::open_opts(&tmpdir.join("h"), &r));
+    check!(File::open_opts(&tmpdir.path().join("b"), c(&w).create(true)));
+    assert!(tmpdir.path().join("b").exists());
+    check!(File::open_opts(&tmpdir.path().join("b"), c(&w).create(true)));
+    check!(File::open_opts(&tmpdir.path().join("b"), &w));
+
+    check!(File::open_opts(&tmpdir.path().join("c"), c(&rw).create(true)));
+    assert!(tmpdir.path().join("c").exists());
+    check!(File::open_opts(&tmpdir.path().join("c"), c(&rw).create(true)));
+    check!(File::open_opts(&tmpdir.path().join("c"), &rw));
+
+    check!(File::open_opts(&tmpdir.path().join("d"), c(&w).append(true).create(true)));
+    assert!(tmpdir.path().join("d").exists());
+    check!(File::open_opts(&tmpdir.path().join("d"), c(&w).append(true).create(true)));
+    check!(File::open_opts(&tmpdir.path().join("d"), c(&w).append(true)));
+
+    check!(File::open_opts(&tmpdir.path().join("e"), c(&rw).append(true).create(true)));
+    assert!(tmpdir.path().join("e").exists());
+    check!(File::open_opts(&tmpdir.path().join("e"), c(&rw).append(true).create(true)));
+    check!(File::open_opts(&tmpdir.path().join("e"), c(&rw).append(true)));
+
+    check!(File::open_opts(&tmpdir.path().join("f"), c(&w).truncate(true).create(true)));
+    assert!(tmpdir.path().join("f").exists());
+    check!(File::open_opts(&tmpdir.path().join("f"), c(&w).truncate(true).create(true)));
+    check!(File::open_opts(&tmpdir.path().join("f"), c(&w).truncate(true)));
+
+    check!(File::open_opts(&tmpdir.path().join("g"), c(&rw).truncate(true).create(true)));
+    assert!(tmpdir.path().join("g").exists());
+    check!(File::open_opts(&tmpdir.path().join("g"), c(&rw).truncate(true).create(true)));
+    check!(File::open_opts(&tmpdir.path().join("g"), c(&rw).truncate(true)));
+
+    check!(check!(File::create(&tmpdir.path().join("h"))).write("foo".as_bytes()));
+    check!(File::open_opts(&tmpdir.path().join("h"), &r));
     {
-        let mut f = check!(File::open_opts(&tmpdir.join("h"), &r));
+        let mut f = check!(File::open_opts(&tmpdir.path().join("h"), &r));
         assert!(f.write("wut".as_bytes()).is_err());
     }
-    assert_eq!(check!(fs::file_attr(&tmpdir.join("h"))).size(), 3);
+    assert_eq!(check!(fs::file_attr(&tmpdir.path().join("h"))).size(), 3);
     {
-        let mut f = check!(File::open_opts(&tmpdir.join("h"), c(&w).append(true)));
+        let mut f = check!(File::open_opts(&tmpdir.path().join("h"), c(&w).append(true)));
         check!(f.write("bar".as_bytes()));
     }
-    assert_eq!(check!(fs::file_attr(&tmpdir.join("h"))).size(), 6);
+    assert_eq!(check!(fs::file_attr(&tmpdir.path().join("h"))).size(), 6);
     {
-        let mut f = check!(File::open_opts(&tmpdir.join("h"),
+        let mut f = check!(File::open_opts(&tmpdir.path().join("h"),
                                            c(&w).truncate(true)));
         check!(f.write("bar".as_bytes()));
     }
-    assert_eq!(check!(fs::file_attr(&tmpdir.join("h"))).size(), 3);
+    assert_eq!(check!(fs::file_attr(&tmpdir.path().join("h"))).size(), 3);
 }
 
 #[test]
 fn utime() {
-    let tmpdir = tmpdir();
-    let path = tmpdir.join("a");
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let path = tmpdir.path().join("a");
     check!(File::create(&path));
     // These numbers have to be bigger than the time in the day to account
     // for timezones Windows in particular will fail in certain timezones
     // with small enough values
     check!(fs::change_file_times(&path, 100000, 200000));
-    assert_eq!(check!(path.file_attr()).accessed(), 100000);
-    assert_eq!(check!(path.file_attr()).modified(), 200000);
+    assert_eq!(check!(path.file_attr()).accessed().milliseconds(), 100000);
+    assert_eq!(check!(path.file_attr()).modified().milliseconds(), 200000);
 }
 
 #[test]
 fn utime_noexist() {
-    let tmpdir = tmpdir();
+    let tmpdir = check!(TempDir::new("io2-fs"));
 
-    match fs::change_file_times(&tmpdir.join("a"), 100, 200) {
+    match fs::change_file_times(&tmpdir.path().join("a"), 100, 200) {
         Ok(..) => panic!(),
         Err(..) => {}
     }
@@ -738,21 +859,973 @@ fn binary_file() {
     let mut bytes = [0; 1024];
     StdRng::new().ok().unwrap().fill_bytes(&mut bytes);
 
-    let tmpdir = tmpdir();
+    let tmpdir = check!(TempDir::new("io2-fs"));
 
-    check!(check!(File::create(&tmpdir.join("test"))).write(&bytes));
+    check!(check!(File::create(&tmpdir.path().join("test"))).write(&bytes));
     let mut v = Vec::new();
-    check!(check!(File::open(&tmpdir.join("test"))).read_to_end(&mut v));
+    check!(check!(File::open(&tmpdir.path().join("test"))).read_to_end(&mut v));
     assert!(v == bytes.as_slice());
 }
 
 #[test]
 fn unlink_readonly() {
-    let tmpdir = tmpdir();
-    let path = tmpdir.join("file");
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let path = tmpdir.path().join("file");
     check!(File::create(&path));
     let mut perm = check!(fs::file_attr(&path)).perm();
     perm.set_readonly(true);
     check!(fs::set_perm(&path, perm));
     check!(fs::remove_file(&path));
 }
+
+#[test]
+#[cfg(unix)]
+fn file_test_nonblocking_regular_file() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let path = tmpdir.path().join("nonblocking");
+    check!(check!(File::create(&path)).write(b"hello"));
+
+    // regular files are always ready, so non-blocking mode changes nothing
+    let mut file = check!(File::open(&path));
+    check!(file.set_nonblocking(true));
+    let mut buf = [0; 5];
+    assert_eq!(check!(file.read(&mut buf)), 5);
+    assert_eq!(&buf, b"hello");
+    check!(file.set_nonblocking(false));
+}
+
+#[test]
+fn file_test_vectored_read_write() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let path = tmpdir.path().join("vectored");
+    {
+        let mut file = check!(File::create(&path));
+        let bufs: &[&[u8]] = &[b"qwer", b"", b"-asdf-", b"zxcv"];
+        assert_eq!(check!(file.write_vectored(bufs)), 14);
+    }
+
+    let mut file = check!(File::open(&path));
+    let (mut a, mut b, mut c) = ([0; 4], [0; 6], [0; 8]);
+    assert_eq!(check!(file.read_vectored(&mut [&mut a, &mut b, &mut c])), 14);
+    assert_eq!(&a, b"qwer");
+    assert_eq!(&b, b"-asdf-");
+    assert_eq!(&c[..4], b"zxcv");
+    assert_eq!(check!(file.read_vectored(&mut [&mut a])), 0);
+}
+
+#[test]
+fn file_test_lock_exclusive_conflicts() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let path = tmpdir.path().join("lock_exclusive");
+    let a = check!(File::create(&path));
+    let b = check!(File::open(&path));
+
+    check!(a.lock_exclusive());
+    assert_eq!(b.try_lock_shared().err().unwrap().kind(), ErrorKind::Locked);
+    assert_eq!(b.try_lock_exclusive().err().unwrap().kind(), ErrorKind::Locked);
+
+    check!(a.unlock());
+    check!(b.try_lock_exclusive());
+    assert_eq!(a.try_lock_shared().err().unwrap().kind(), ErrorKind::Locked);
+    check!(b.unlock());
+}
+
+#[test]
+fn file_test_lock_shared_coexists() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let path = tmpdir.path().join("lock_shared");
+    let a = check!(File::create(&path));
+    let b = check!(File::open(&path));
+    let c = check!(File::open(&path));
+
+    check!(a.lock_shared());
+    check!(b.try_lock_shared());
+    assert_eq!(c.try_lock_exclusive().err().unwrap().kind(), ErrorKind::Locked);
+    check!(a.unlock());
+    check!(b.unlock());
+    check!(c.try_lock_exclusive());
+}
+
+#[test]
+fn file_test_lock_released_on_drop() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let path = tmpdir.path().join("lock_drop");
+    let a = check!(File::create(&path));
+    check!(a.lock_exclusive());
+    drop(a);
+
+    let b = check!(File::open(&path));
+    check!(b.try_lock_exclusive());
+}
+
+#[test]
+#[cfg(any(target_os = "linux", windows))]
+fn file_test_lock_range() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let path = tmpdir.path().join("lock_range");
+    let a = check!(File::create(&path));
+    let mut opts = OpenOptions::new();
+    let b = check!(File::open_opts(&path, opts.read(true).write(true)));
+
+    check!(a.lock_range(LockKind::Exclusive, 0, 10));
+    assert_eq!(b.try_lock_range(LockKind::Shared, 5, 10).err().unwrap().kind(),
+               ErrorKind::Locked);
+    // ranges which don't overlap don't conflict
+    check!(b.try_lock_range(LockKind::Exclusive, 10, 10));
+
+    check!(a.unlock_range(0, 10));
+    check!(b.try_lock_range(LockKind::Shared, 0, 10));
+    check!(b.unlock_range(0, 10));
+    check!(b.unlock_range(10, 10));
+}
+
+#[test]
+#[cfg(any(target_os = "linux", windows))]
+fn file_test_lock_range_released_on_drop() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let path = tmpdir.path().join("lock_range_drop");
+    let a = check!(File::create(&path));
+    check!(a.lock_range(LockKind::Exclusive, 0, 0));
+    drop(a);
+
+    let mut opts = OpenOptions::new();
+    let b = check!(File::open_opts(&path, opts.read(true).write(true)));
+    check!(b.try_lock_range(LockKind::Exclusive, 100, 1));
+}
+
+#[test]
+#[cfg(unix)]
+fn file_test_lock_range_too_large() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let a = check!(File::create(&tmpdir.path().join("lock_range_large")));
+    let max = std::i64::MAX as u64;
+    for &(start, len) in [(max + 1, 1), (max, 1), (1, max)].iter() {
+        assert_eq!(a.try_lock_range(LockKind::Shared, start, len)
+                    .err().unwrap().kind(), ErrorKind::InvalidInput);
+    }
+    check!(a.try_lock_range(LockKind::Shared, max - 1, 1));
+}
+
+#[test]
+fn file_test_attr_file_type() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let file = tmpdir.path().join("file");
+    let dir = tmpdir.path().join("dir");
+    check!(File::create(&file));
+    check!(fs::make_dir(&dir));
+
+    let attr = check!(fs::file_attr(&file));
+    assert_eq!(attr.file_type(), FileType::File);
+    assert!(!attr.is_symlink());
+    let attr = check!(fs::symlink_attr(&dir));
+    assert_eq!(attr.file_type(), FileType::Dir);
+    assert!(!attr.is_symlink());
+}
+
+#[test]
+#[cfg(unix)]
+fn file_test_attr_fifo() {
+    use std::ffi::CString;
+
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let path = tmpdir.path().join("fifo");
+    let cpath = CString::from_slice(path.as_vec());
+    assert_eq!(unsafe { libc::mkfifo(cpath.as_ptr(), 0o644) }, 0);
+    assert_eq!(check!(fs::file_attr(&path)).file_type(), FileType::Fifo);
+}
+
+#[test]
+fn file_test_attr_len() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let path = tmpdir.path().join("len");
+    check!(check!(File::create(&path)).write_all(b"hello"));
+    let attr = check!(fs::file_attr(&path));
+    assert_eq!(attr.len(), 5);
+    assert_eq!(attr.len(), attr.size());
+}
+
+#[test]
+fn file_test_attr_nanosecond_times() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let path = tmpdir.path().join("times");
+    check!(File::create(&path));
+    check!(fs::change_file_times(&path, 100123, 200456));
+
+    let attr = check!(fs::file_attr(&path));
+    assert_eq!(attr.accessed().seconds(), 100);
+    assert_eq!(attr.accessed().nanoseconds(), 123000000);
+    assert_eq!(attr.modified().seconds(), 200);
+    assert_eq!(attr.modified().nanoseconds(), 456000000);
+    assert!(attr.accessed() < attr.modified());
+}
+
+#[test]
+fn file_test_set_file_times() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let path = tmpdir.path().join("times");
+    check!(File::create(&path));
+    let atime = fs::FileTime::from_parts(100, 123456700);
+    let mtime = fs::FileTime::from_parts(-200, 400);
+    check!(fs::set_file_times(&path, atime, mtime));
+
+    let attr = check!(fs::file_attr(&path));
+    assert_eq!(attr.accessed().seconds(), 100);
+    assert_eq!(attr.modified().seconds(), -200);
+    if cfg!(any(target_os = "linux", target_os = "android")) {
+        assert_eq!(attr.accessed(), atime);
+        assert_eq!(attr.modified(), mtime);
+    }
+}
+
+#[test]
+#[cfg(unix)]
+fn file_test_attr_unix() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let input = tmpdir.path().join("in");
+    let out = tmpdir.path().join("out");
+    check!(check!(File::create(&input)).write_all(&[0; 10000]));
+    assert_eq!(check!(fs::file_attr(&input)).nlink(), 1);
+    check!(fs::hard_link(&input, &out));
+
+    let a = check!(fs::file_attr(&input));
+    let b = check!(fs::file_attr(&out));
+    assert_eq!(a.nlink(), 2);
+    assert_eq!(a.ino(), b.ino());
+    assert_eq!(a.dev(), b.dev());
+    assert_eq!(a.uid(), unsafe { libc::getuid() } as u32);
+    assert_eq!(a.gid(), unsafe { libc::getgid() } as u32);
+    assert!(a.blksize() > 0);
+    assert!(a.blocks() > 0);
+    assert!(a.changed() >= a.modified());
+}
+
+#[test]
+fn file_test_set_perm_on_file() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let path = tmpdir.path().join("set_perm");
+    let file = check!(File::create(&path));
+    let mut p = check!(file.file_attr()).perm();
+    p.set_readonly(true);
+    check!(file.set_perm(p));
+    assert!(check!(fs::file_attr(&path)).perm().readonly());
+    p.set_readonly(false);
+    check!(file.set_perm(p));
+    assert!(!check!(fs::file_attr(&path)).perm().readonly());
+}
+
+#[test]
+#[cfg(unix)]
+fn file_test_unix_mode() {
+    use io2::os::unix::fs::FilePermissionExt;
+    use io2::fs::FilePermission;
+
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let path = tmpdir.path().join("mode");
+    let file = check!(File::create(&path));
+    check!(file.set_perm(FilePermission::from_mode(0o640)));
+    assert_eq!(check!(fs::file_attr(&path)).perm().mode(), 0o640);
+
+    let mut p = check!(fs::file_attr(&path)).perm();
+    p.set_mode(0o1755);
+    check!(fs::set_perm(&path, p));
+    let p = check!(fs::file_attr(&path)).perm();
+    assert_eq!(p.mode(), 0o1755);
+    assert!(!p.readonly());
+}
+
+#[test]
+#[cfg(unix)]
+fn file_test_unix_chown() {
+    use io2::os::unix::fs::{self, FileExt};
+
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let input = tmpdir.path().join("in");
+    let link = tmpdir.path().join("link");
+    let file = check!(File::create(&input));
+    check!(io2::fs::sym_link(&input, &link));
+    let uid = unsafe { libc::getuid() } as u32;
+    let gid = unsafe { libc::getgid() } as u32;
+
+    // changing to the current owner is always permitted
+    check!(file.chown(uid, gid));
+    check!(fs::chown(&input, uid, gid));
+    check!(fs::lchown(&link, uid, !0));
+    let attr = check!(io2::fs::symlink_attr(&link));
+    assert_eq!(attr.uid(), uid);
+    assert_eq!(attr.gid(), gid);
+
+    assert!(fs::chown(&tmpdir.path().join("missing"), uid, gid).is_err());
+}
+
+#[test]
+#[cfg(unix)]
+fn file_test_unix_umask() {
+    use io2::env;
+    use io2::os::unix::fs::{umask, set_umask};
+    use io2::os::unix::fs::{FilePermissionExt, OpenOptionsExt};
+    use io2::process::Command;
+
+    // The umask applies to every thread, so it is only changed in a child
+    // which runs nothing but this test, leaving the files created by other
+    // tests alone.
+    if env::var("IO2_TEST_UMASK").is_none() {
+        let exe = env::current_exe().unwrap();
+        let status = check!(Command::new(exe.as_str().unwrap())
+                                    .arg("file_test_unix_umask")
+                                    .env("IO2_TEST_UMASK", "1")
+                                    .status());
+        assert!(status.success());
+        return
+    }
+
+    let old = set_umask(0o027);
+    assert_eq!(umask(), 0o027);
+    assert_eq!(umask(), 0o027);
+
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let path = tmpdir.path().join("default");
+    check!(File::create(&path));
+    assert_eq!(check!(fs::file_attr(&path)).perm().mode(), 0o640);
+    let path = tmpdir.path().join("mode");
+    check!(File::open_opts(&path, OpenOptions::new().write(true)
+                                                    .create(true)
+                                                    .mode(0o755)));
+    assert_eq!(check!(fs::file_attr(&path)).perm().mode(), 0o750);
+
+    assert_eq!(set_umask(old), 0o027);
+    assert_eq!(umask(), old);
+}
+
+#[test]
+#[cfg(unix)]
+fn file_test_unix_from_mode() {
+    use io2::os::unix::fs::FilePermissionExt;
+    use io2::fs::FilePermission;
+
+    assert_eq!(FilePermission::from_mode(0o100644).mode(), 0o644);
+    let mut perm = FilePermission::from_mode(0o7777);
+    assert_eq!(perm.mode(), 0o7777);
+    perm.set_mode(0o40755);
+    assert_eq!(perm.mode(), 0o755);
+}
+
+fn read_contents(path: &Path) -> String {
+    let mut s = String::new();
+    check!(check!(File::open(path)).read_to_string(&mut s));
+    s
+}
+
+#[test]
+fn open_exclusive() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let path = tmpdir.path().join("exclusive");
+    let mut opts = OpenOptions::new();
+    opts.write(true).exclusive(true);
+    check!(File::open_opts(&path, &opts));
+    match File::open_opts(&path, &opts) {
+        Ok(..) => panic!("wanted a failure"),
+        Err(e) => assert_eq!(e.kind(), ErrorKind::PathAlreadyExists),
+    }
+}
+
+#[test]
+fn atomic_file_commit() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let path = tmpdir.path().join("target");
+    check!(check!(File::create(&path)).write_all(b"old"));
+
+    let mut file = check!(fs::AtomicFile::create(&path));
+    check!(file.write_all(b"new contents"));
+    assert_eq!(read_contents(&path), "old");
+    check!(file.commit());
+    assert_eq!(read_contents(&path), "new contents");
+
+    // the temporary file was renamed away
+    assert_eq!(check!(fs::read_dir(tmpdir.path())).count(), 1);
+}
+
+#[test]
+fn atomic_file_new_target() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let path = tmpdir.path().join("target");
+    let mut file = check!(fs::AtomicFile::create(&path));
+    assert!(!path.exists());
+    check!(file.write_all(b"hello"));
+    check!(file.commit());
+    assert_eq!(read_contents(&path), "hello");
+}
+
+#[test]
+fn atomic_file_dropped_without_commit() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let path = tmpdir.path().join("target");
+    check!(check!(File::create(&path)).write_all(b"old"));
+    {
+        let mut file = check!(fs::AtomicFile::create(&path));
+        check!(file.write_all(b"new"));
+        assert_eq!(check!(fs::read_dir(tmpdir.path())).count(), 2);
+    }
+    assert_eq!(read_contents(&path), "old");
+    assert_eq!(check!(fs::read_dir(tmpdir.path())).count(), 1);
+}
+
+#[test]
+fn atomic_file_unique_temp_names() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let path = tmpdir.path().join("target");
+    let mut a = check!(fs::AtomicFile::create(&path));
+    let mut b = check!(fs::AtomicFile::create(&path));
+    check!(a.write_all(b"a"));
+    check!(b.write_all(b"b"));
+    check!(a.commit());
+    check!(b.commit());
+    assert_eq!(read_contents(&path), "b");
+}
+
+#[test]
+fn write_atomic_replaces() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let path = tmpdir.path().join("target");
+    check!(fs::write_atomic(&path, b"one"));
+    assert_eq!(read_contents(&path), "one");
+    check!(fs::write_atomic(&path, b"two"));
+    assert_eq!(read_contents(&path), "two");
+    assert!(fs::write_atomic(&tmpdir.path().join("missing/target"), b"x").is_err());
+}
+
+#[test]
+#[cfg(unix)]
+fn write_atomic_preserves_perm() {
+    use io2::os::unix::fs::FilePermissionExt;
+    use io2::fs::FilePermission;
+
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let path = tmpdir.path().join("target");
+    check!(check!(File::create(&path)).write_all(b"old"));
+    check!(fs::set_perm(&path, FilePermission::from_mode(0o640)));
+    check!(fs::write_atomic(&path, b"new"));
+    assert_eq!(check!(fs::file_attr(&path)).perm().mode(), 0o640);
+
+    let mut file = check!(fs::AtomicFile::create(&path));
+    check!(file.write_all(b"newer"));
+    check!(file.commit());
+    assert_eq!(check!(fs::file_attr(&path)).perm().mode(), 0o600);
+}
+
+#[test]
+#[cfg(unix)]
+fn atomic_file_private_before_commit() {
+    use io2::os::unix::fs::FilePermissionExt;
+
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let path = tmpdir.path().join("secret");
+    let mut file = check!(fs::AtomicFile::create(&path));
+    check!(file.write_all(b"key"));
+
+    // the only entry so far is the temporary file holding the new contents
+    let temps: Vec<_> = check!(fs::read_dir(tmpdir.path())).map(|e| {
+        check!(e).path()
+    }).collect();
+    assert_eq!(temps.len(), 1);
+    assert_eq!(check!(fs::file_attr(&temps[0])).perm().mode(), 0o600);
+    check!(file.commit());
+}
+
+#[test]
+fn temp_dir_removed_on_drop() {
+    let path = {
+        let dir = check!(fs::TempDir::new("io2-test"));
+        assert!(dir.path().is_dir());
+        assert!(dir.path().filename_str().unwrap().starts_with("io2-test"));
+        check!(fs::make_dir(&dir.path().join("sub")));
+        check!(File::create(&dir.path().join("sub/file")));
+        dir.path().clone()
+    };
+    assert!(!path.exists());
+}
+
+#[test]
+fn temp_dir_into_path_and_close() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let dir = check!(fs::TempDir::new_in(tmpdir.path(), "keep"));
+    let path = dir.into_path();
+    assert!(path.is_dir());
+    check!(fs::remove_dir(&path));
+
+    let dir = check!(fs::TempDir::new_in(tmpdir.path(), "close"));
+    let path = dir.path().clone();
+    check!(File::create(&path.join("file")));
+    check!(dir.close());
+    assert!(!path.exists());
+}
+
+#[test]
+fn temp_dir_unique() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let a = check!(fs::TempDir::new_in(tmpdir.path(), ""));
+    let b = check!(fs::TempDir::new_in(tmpdir.path(), ""));
+    assert!(a.path() != b.path());
+}
+
+#[test]
+fn named_temp_file() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let path = {
+        let mut file = check!(fs::NamedTempFile::new_in(tmpdir.path()));
+        check!(file.write_all(b"hello"));
+        assert_eq!(read_contents(file.path()), "hello");
+
+        check!(file.seek(SeekPos::FromStart(0)));
+        let mut s = String::new();
+        check!(file.read_to_string(&mut s));
+        assert_eq!(s, "hello");
+        file.path().clone()
+    };
+    assert!(!path.exists());
+    assert_eq!(check!(fs::read_dir(tmpdir.path())).count(), 0);
+}
+
+#[test]
+fn named_temp_file_persist() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let target = tmpdir.path().join("kept");
+    let mut file = check!(fs::NamedTempFile::new_in(tmpdir.path()));
+    check!(file.write_all(b"kept"));
+    let temp = file.path().clone();
+    let file = check!(file.persist(&target));
+    assert_eq!(file.path(), &target);
+    drop(file);
+    assert!(!temp.exists());
+    assert_eq!(read_contents(&target), "kept");
+}
+
+#[test]
+fn named_temp_file_persist_failure() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let mut file = check!(fs::NamedTempFile::new_in(tmpdir.path()));
+    check!(file.write_all(b"kept"));
+    let temp = file.path().clone();
+
+    let err = match file.persist(&tmpdir.path().join("missing/kept")) {
+        Ok(..) => panic!("persisted into a missing directory"),
+        Err(e) => e,
+    };
+    assert_eq!(err.error().kind(), ErrorKind::FileNotFound);
+    let file = err.into_file();
+    assert_eq!(file.path(), &temp);
+    assert_eq!(read_contents(&temp), "kept");
+
+    let target = tmpdir.path().join("kept");
+    check!(file.persist(&target));
+    assert_eq!(read_contents(&target), "kept");
+}
+
+#[test]
+#[cfg(unix)]
+fn temp_dir_private() {
+    use io2::os::unix::fs::FilePermissionExt;
+
+    let dir = check!(TempDir::new("io2-fs"));
+    assert_eq!(check!(fs::file_attr(dir.path())).perm().mode(), 0o700);
+}
+
+#[test]
+fn tempfile_is_anonymous() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let mut file = check!(fs::tempfile_in(tmpdir.path()));
+    assert_eq!(check!(fs::read_dir(tmpdir.path())).count(), 0);
+
+    check!(file.write_all(b"scratch"));
+    check!(file.seek(SeekPos::FromStart(0)));
+    let mut s = String::new();
+    check!(file.read_to_string(&mut s));
+    assert_eq!(s, "scratch");
+
+    let mut file = check!(fs::tempfile());
+    check!(file.write_all(b"x"));
+}
+
+fn copy_dir_fixture(dir: &Path) {
+    check!(fs::make_dir_all(&dir.join("a/b")));
+    check!(check!(File::create(&dir.join("top"))).write_all(b"top"));
+    check!(check!(File::create(&dir.join("a/mid"))).write_all(b"middle"));
+    check!(File::create(&dir.join("a/b/empty")));
+}
+
+#[test]
+fn copy_dir_tree() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let from = tmpdir.path().join("from");
+    let to = tmpdir.path().join("to");
+    copy_dir_fixture(&from);
+
+    let opts = fs::CopyOptions::new();
+    assert_eq!(check!(fs::copy_dir(&from, &to, &opts)), 9);
+    assert_eq!(read_contents(&to.join("top")), "top");
+    assert_eq!(read_contents(&to.join("a/mid")), "middle");
+    assert_eq!(read_contents(&to.join("a/b/empty")), "");
+    assert!(to.join("a/b").is_dir());
+}
+
+#[test]
+fn copy_dir_invalid() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let from = tmpdir.path().join("from");
+    copy_dir_fixture(&from);
+    let opts = fs::CopyOptions::new();
+
+    match fs::copy_dir(&from.join("top"), &tmpdir.path().join("to"), &opts) {
+        Err(ref e) if e.kind() == ErrorKind::MismatchedFileTypeForOperation => {}
+        r => panic!("unexpected result: {:?}", r),
+    }
+    match fs::copy_dir(&from, &from.join("a/inside"), &opts) {
+        Err(ref e) if e.kind() == ErrorKind::InvalidInput => {}
+        r => panic!("unexpected result: {:?}", r),
+    }
+    assert!(!from.join("a/inside").exists());
+}
+
+#[test]
+fn copy_dir_existing() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let from = tmpdir.path().join("from");
+    let to = tmpdir.path().join("to");
+    copy_dir_fixture(&from);
+    check!(fs::make_dir_all(&to.join("a")));
+    check!(check!(File::create(&to.join("top"))).write_all(b"old"));
+    check!(check!(File::create(&to.join("a/extra"))).write_all(b"extra"));
+
+    let mut opts = fs::CopyOptions::new();
+    match fs::copy_dir(&from, &to, &opts) {
+        Err(ref e) if e.kind() == ErrorKind::PathAlreadyExists => {}
+        r => panic!("unexpected result: {:?}", r),
+    }
+
+    opts.existing(fs::ExistingPolicy::Skip);
+    assert_eq!(check!(fs::copy_dir(&from, &to, &opts)), 6);
+    assert_eq!(read_contents(&to.join("top")), "old");
+    assert_eq!(read_contents(&to.join("a/mid")), "middle");
+
+    opts.existing(fs::ExistingPolicy::Overwrite);
+    assert_eq!(check!(fs::copy_dir(&from, &to, &opts)), 9);
+    assert_eq!(read_contents(&to.join("top")), "top");
+    // directories are merged, not replaced
+    assert_eq!(read_contents(&to.join("a/extra")), "extra");
+}
+
+#[test]
+#[cfg(unix)]
+fn copy_dir_symlinks() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let from = tmpdir.path().join("from");
+    copy_dir_fixture(&from);
+    check!(fs::sym_link(&Path::new("top"), &from.join("file_link")));
+    check!(fs::sym_link(&Path::new("a"), &from.join("dir_link")));
+    let mut opts = fs::CopyOptions::new();
+
+    let to = tmpdir.path().join("copy");
+    opts.symlinks(fs::SymlinkPolicy::Copy);
+    check!(fs::copy_dir(&from, &to, &opts));
+    assert!(check!(fs::symlink_attr(&to.join("file_link"))).is_symlink());
+    assert_eq!(check!(fs::read_link(&to.join("dir_link"))), Path::new("a"));
+    assert_eq!(read_contents(&to.join("dir_link/mid")), "middle");
+
+    let to = tmpdir.path().join("follow");
+    opts.symlinks(fs::SymlinkPolicy::Follow);
+    assert_eq!(check!(fs::copy_dir(&from, &to, &opts)), 18);
+    assert!(check!(fs::symlink_attr(&to.join("file_link"))).is_file());
+    assert!(check!(fs::symlink_attr(&to.join("dir_link"))).is_dir());
+    assert_eq!(read_contents(&to.join("dir_link/mid")), "middle");
+
+    let to = tmpdir.path().join("skip");
+    opts.symlinks(fs::SymlinkPolicy::Skip);
+    check!(fs::copy_dir(&from, &to, &opts));
+    assert!(fs::symlink_attr(&to.join("file_link")).is_err());
+    assert!(fs::symlink_attr(&to.join("dir_link")).is_err());
+    assert!(to.join("a/mid").exists());
+}
+
+#[test]
+fn copy_dir_preserves_attrs() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let from = tmpdir.path().join("from");
+    copy_dir_fixture(&from);
+    let file = from.join("a/mid");
+    let mut perm = check!(fs::file_attr(&file)).perm();
+    perm.set_readonly(true);
+    check!(fs::set_perm(&file, perm));
+    check!(fs::change_file_times(&file, 1000, 2000));
+    check!(fs::change_file_times(&from.join("a"), 3000, 4000));
+
+    let to = tmpdir.path().join("to");
+    let mut opts = fs::CopyOptions::new();
+    opts.preserve_times(true);
+    check!(fs::copy_dir(&from, &to, &opts));
+    let attr = check!(fs::file_attr(&to.join("a/mid")));
+    assert!(attr.perm().readonly());
+    assert_eq!(attr.accessed().milliseconds(), 1000);
+    assert_eq!(attr.modified().milliseconds(), 2000);
+    // set after the directory's contents were copied into it
+    assert_eq!(check!(fs::file_attr(&to.join("a"))).modified().milliseconds(),
+               4000);
+
+    let to = tmpdir.path().join("plain");
+    opts.preserve_perm(false).preserve_times(false);
+    check!(fs::copy_dir(&from, &to, &opts));
+    let attr = check!(fs::file_attr(&to.join("a/mid")));
+    assert!(!attr.perm().readonly());
+    assert!(attr.modified().milliseconds() != 2000);
+
+    // the read-only files can't be removed on windows otherwise
+    for p in [&file, &tmpdir.path().join("to/a/mid")].iter() {
+        let mut perm = check!(fs::file_attr(*p)).perm();
+        perm.set_readonly(false);
+        check!(fs::set_perm(*p, perm));
+    }
+}
+
+#[test]
+#[cfg(unix)]
+fn copy_dir_read_only_dir() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let from = tmpdir.path().join("from");
+    copy_dir_fixture(&from);
+    let dir = from.join("a");
+    let mut perm = check!(fs::file_attr(&dir)).perm();
+    perm.set_readonly(true);
+    check!(fs::set_perm(&dir, perm));
+
+    let to = tmpdir.path().join("to");
+    check!(fs::copy_dir(&from, &to, &fs::CopyOptions::new()));
+    assert_eq!(read_contents(&to.join("a/mid")), "middle");
+    assert!(to.join("a/b/empty").exists());
+    assert!(check!(fs::file_attr(&to.join("a"))).perm().readonly());
+
+    for p in [&dir, &to.join("a")].iter() {
+        let mut perm = check!(fs::file_attr(*p)).perm();
+        perm.set_readonly(false);
+        check!(fs::set_perm(*p, perm));
+    }
+}
+
+#[test]
+fn copy_dir_progress() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let from = tmpdir.path().join("from");
+    copy_dir_fixture(&from);
+
+    let mut seen = Vec::new();
+    let opts = fs::CopyOptions::new();
+    let total = check!(fs::copy_dir_with_progress(&from, &tmpdir.path().join("to"),
+                                                  &opts, |p| {
+        assert!(p.to().exists());
+        seen.push((p.from().clone(), p.bytes(), p.total_bytes(), p.entries()));
+    }));
+    assert_eq!(total, 9);
+    // two directories and three files
+    assert_eq!(seen.len(), 5);
+    assert_eq!(seen.last().unwrap().2, 9);
+    for (i, &(ref from, bytes, _, entries)) in seen.iter().enumerate() {
+        assert_eq!(entries, i as u64 + 1);
+        if from.filename_str() == Some("top") {
+            assert_eq!(bytes, 3);
+        }
+    }
+}
+
+#[test]
+#[cfg(unix)]
+fn copy_dir_into_itself_through_link() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let from = tmpdir.path().join("from");
+    copy_dir_fixture(&from);
+    check!(fs::sym_link(&from, &tmpdir.path().join("link")));
+
+    let opts = fs::CopyOptions::new();
+    match fs::copy_dir(&from, &tmpdir.path().join("link/a/inside"), &opts) {
+        Err(ref e) if e.kind() == ErrorKind::InvalidInput => {}
+        r => panic!("unexpected result: {:?}", r),
+    }
+    assert!(!from.join("a/inside").exists());
+}
+
+#[test]
+#[cfg(unix)]
+fn copy_dir_symlink_loop() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let from = tmpdir.path().join("from");
+    copy_dir_fixture(&from);
+    check!(fs::sym_link(&Path::new(".."), &from.join("a/up")));
+    let mut opts = fs::CopyOptions::new();
+
+    // links which aren't followed can't loop
+    check!(fs::copy_dir(&from, &tmpdir.path().join("copy"), &opts));
+    assert_eq!(check!(fs::read_link(&tmpdir.path().join("copy/a/up"))),
+               Path::new(".."));
+
+    opts.symlinks(fs::SymlinkPolicy::Follow);
+    match fs::copy_dir(&from, &tmpdir.path().join("follow"), &opts) {
+        Err(ref e) if e.kind() == ErrorKind::Other => {}
+        r => panic!("unexpected result: {:?}", r),
+    }
+}
+
+fn walk_fixture(dir: &Path) {
+    check!(fs::make_dir_all(&dir.join("a/b/c")));
+    check!(fs::make_dir_all(&dir.join("d")));
+    check!(File::create(&dir.join("a/1")));
+    check!(File::create(&dir.join("a/b/2")));
+    check!(File::create(&dir.join("a/b/c/3")));
+    check!(File::create(&dir.join("d/4")));
+}
+
+fn walk_names(walk: WalkDir, root: &Path) -> Vec<(String, usize)> {
+    walk.map(|e| {
+        let e = check!(e);
+        let name = e.path().path_relative_from(root).unwrap();
+        (name.as_str().unwrap().replace("\\", "/"), e.depth())
+    }).collect()
+}
+
+fn by_name(a: &fs::DirEntry, b: &fs::DirEntry) -> std::cmp::Ordering {
+    a.path().cmp(&b.path())
+}
+
+#[test]
+fn walk_dir_sorted() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let root = tmpdir.path();
+    walk_fixture(root);
+
+    let names = walk_names(WalkDir::new(root).sort_by(by_name), root);
+    assert_eq!(names, vec![
+        ("a".to_string(), 1), ("a/1".to_string(), 2), ("a/b".to_string(), 2),
+        ("a/b/2".to_string(), 3), ("a/b/c".to_string(), 3),
+        ("a/b/c/3".to_string(), 4), ("d".to_string(), 1),
+        ("d/4".to_string(), 2),
+    ]);
+    assert_eq!(check!(fs::walk_dir(root)).count(), 8);
+    assert!(fs::walk_dir(&root.join("missing")).is_err());
+}
+
+#[test]
+fn walk_dir_depth() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let root = tmpdir.path();
+    walk_fixture(root);
+
+    let walk = WalkDir::new(root).sort_by(by_name).min_depth(2).max_depth(3);
+    let names = walk_names(walk, root);
+    assert_eq!(names, vec![
+        ("a/1".to_string(), 2), ("a/b".to_string(), 2),
+        ("a/b/2".to_string(), 3), ("a/b/c".to_string(), 3),
+        ("d/4".to_string(), 2),
+    ]);
+
+    let walk = WalkDir::new(root).min_depth(0).max_depth(0);
+    assert_eq!(walk_names(walk, root), vec![(".".to_string(), 0)]);
+}
+
+#[test]
+fn walk_dir_contents_first() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let root = tmpdir.path();
+    walk_fixture(root);
+
+    let walk = WalkDir::new(root).sort_by(by_name).contents_first(true);
+    let names: Vec<String> = walk_names(walk, root).into_iter()
+                                                   .map(|(n, _)| n).collect();
+    assert_eq!(names, vec!["a/1", "a/b/2", "a/b/c/3", "a/b/c", "a/b", "a",
+                           "d/4", "d"]);
+}
+
+fn walk_skipping_at(mut walk: WalkDir, at: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    while let Some(e) = walk.next() {
+        let path = check!(e).path();
+        let name = path.filename_str().unwrap().to_string();
+        if name == at {
+            walk.skip_current_dir();
+        }
+        names.push(name);
+    }
+    names
+}
+
+#[test]
+fn walk_dir_skip_current_dir() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let root = tmpdir.path();
+    walk_fixture(root);
+
+    // skipping after a directory prunes it, and after a file prunes the rest
+    // of its parent
+    let walk = || WalkDir::new(root).sort_by(by_name);
+    assert_eq!(walk_skipping_at(walk(), "c"),
+               vec!["a", "1", "b", "2", "c", "d", "4"]);
+    assert_eq!(walk_skipping_at(walk(), "1"), vec!["a", "1", "d", "4"]);
+
+    let walk = WalkDir::new(root).sort_by(by_name).contents_first(true);
+    assert_eq!(walk_skipping_at(walk, "2"),
+               vec!["1", "2", "b", "a", "4", "d"]);
+
+    // skipping after a directory which isn't descended into does nothing
+    let walk = WalkDir::new(root).sort_by(by_name).max_depth(1);
+    assert_eq!(walk_skipping_at(walk, "a"), vec!["a", "d"]);
+    let walk = WalkDir::new(root).sort_by(by_name).contents_first(true);
+    assert_eq!(walk_skipping_at(walk, "c"),
+               vec!["1", "2", "3", "c", "b", "a", "4", "d"]);
+}
+
+#[test]
+fn walk_dir_sort_by_borrowing() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let root = tmpdir.path();
+    walk_fixture(root);
+
+    let mut calls = 0;
+    let names = walk_names(WalkDir::new(root).max_depth(1).sort_by(|a, b| {
+        calls += 1;
+        b.path().cmp(&a.path())
+    }), root);
+    assert_eq!(names, vec![("d".to_string(), 1), ("a".to_string(), 1)]);
+    assert!(calls > 0);
+}
+
+#[test]
+#[cfg(unix)]
+fn walk_dir_follow_links() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let root = tmpdir.path();
+    walk_fixture(root);
+    check!(fs::sym_link(&Path::new("../../d"), &root.join("a/b/to_d")));
+    check!(fs::sym_link(&Path::new(".."), &root.join("a/b/up")));
+
+    // links are yielded but not descended into by default
+    let names = walk_names(WalkDir::new(root), root);
+    assert!(names.iter().any(|&(ref n, _)| *n == "a/b/up"));
+    assert!(!names.iter().any(|&(ref n, _)| *n == "a/b/to_d/4"));
+
+    let mut found = false;
+    let mut loops = 0;
+    for e in WalkDir::new(root).follow_links(true) {
+        match e {
+            Ok(e) => found |= e.path() == root.join("a/b/to_d/4"),
+            Err(e) => {
+                assert_eq!(e.kind(), ErrorKind::Other);
+                loops += 1;
+            }
+        }
+    }
+    assert!(found);
+    assert_eq!(loops, 1);
+}
+
+#[test]
+fn walk_dir_same_file_system() {
+    let tmpdir = check!(TempDir::new("io2-fs"));
+    let root = tmpdir.path();
+    walk_fixture(root);
+    let walk = WalkDir::new(root).same_file_system(true);
+    assert_eq!(walk.count(), 8);
+}
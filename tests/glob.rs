@@ -0,0 +1,164 @@
+#![feature(path)]
+
+extern crate io2;
+
+use io2::fs::{self, File, TempDir};
+use io2::fs::glob::{self, Pattern, MatchOptions};
+use io2::io::ErrorKind;
+
+macro_rules! t {
+    ($e:expr) => {
+        match $e {
+            Ok(t) => t,
+            Err(e) => panic!("received error for `{}`: {}", stringify!($e), e),
+        }
+    }
+}
+
+fn sample_tree() -> TempDir {
+    let ret = t!(TempDir::new("io2-glob"));
+    let root = ret.path().clone();
+    for dir in ["src/fs", "src/.hidden", "docs", "target/debug"].iter() {
+        t!(fs::make_dir_all(&root.join(*dir)));
+    }
+    for file in ["src/lib.rs", "src/fs/mod.rs", "src/fs/glob.rs",
+                 "src/.hidden/secret.rs", "src/.dot.rs", "docs/README.md",
+                 "docs/guide.MD", "target/debug/out.rs", "Cargo.toml"].iter() {
+        t!(File::create(&root.join(*file)));
+    }
+    ret
+}
+
+// Globs `pattern` below `dir`, returning the matches relative to it.
+fn glob_in(dir: &TempDir, pattern: &str, opts: &MatchOptions) -> Vec<String> {
+    let pattern = format!("{}/{}", dir.path().display(), pattern);
+    t!(glob::glob_with(&pattern, opts)).map(|e| {
+        let path = t!(e).path();
+        let rel = path.path_relative_from(dir.path()).unwrap();
+        rel.as_str().unwrap().replace("\\", "/")
+    }).collect()
+}
+
+fn pattern(s: &str) -> Pattern { t!(Pattern::new(s)) }
+
+#[test]
+fn wildcards() {
+    assert!(pattern("*.rs").matches("lib.rs"));
+    assert!(!pattern("*.rs").matches(".rs"));
+    assert!(!pattern("*.rs").matches("src/lib.rs"));
+    assert!(pattern("src/*.rs").matches("src/lib.rs"));
+    assert!(pattern("l?b.rs").matches("lib.rs"));
+    assert!(!pattern("l?b.rs").matches("lb.rs"));
+    assert!(pattern("a*b*c").matches("aXXbYYbc"));
+    assert!(!pattern("a*b*c").matches("aXXbYYbcd"));
+    assert!(pattern("/usr/*").matches("/usr/lib"));
+    assert!(!pattern("/usr/*").matches("usr/lib"));
+    assert_eq!(pattern("src/*.rs").as_str(), "src/*.rs");
+}
+
+#[test]
+fn classes() {
+    assert!(pattern("[abc].rs").matches("b.rs"));
+    assert!(!pattern("[abc].rs").matches("d.rs"));
+    assert!(pattern("[a-c0-9]").matches("7"));
+    assert!(pattern("[!a-c]").matches("d"));
+    assert!(!pattern("[!a-c]").matches("b"));
+    assert!(pattern("[]]").matches("]"));
+    assert!(pattern("[!]]").matches("a"));
+    assert!(pattern("[*]").matches("*"));
+    assert!(!pattern("[*]").matches("a"));
+    assert!(pattern("[-a]").matches("-"));
+}
+
+#[test]
+fn alternation() {
+    let p = pattern("*.{rs,toml}");
+    assert!(p.matches("lib.rs"));
+    assert!(p.matches("Cargo.toml"));
+    assert!(!p.matches("README.md"));
+
+    let p = pattern("{src/**,docs}/*.{md,rs}");
+    assert!(p.matches("src/fs/mod.rs"));
+    assert!(p.matches("docs/README.md"));
+    assert!(!p.matches("target/out.rs"));
+
+    assert!(pattern("a{b,{c,d}e}").matches("ade"));
+    assert!(pattern("{,x}y").matches("y"));
+    assert!(pattern("[{]").matches("{"));
+}
+
+#[test]
+fn recursive() {
+    let p = pattern("src/**/*.rs");
+    assert!(p.matches("src/lib.rs"));
+    assert!(p.matches("src/fs/glob.rs"));
+    assert!(p.matches("src/a/b/c/d.rs"));
+    assert!(!p.matches("lib.rs"));
+    assert!(!p.matches("src/.hidden/secret.rs"));
+    assert!(pattern("**").matches("a/b/c"));
+}
+
+#[test]
+fn invalid() {
+    for s in ["[abc", "a{b,c", "{[}]", "a**", "**b/c", "x/***",
+              "[a/]x"].iter() {
+        match Pattern::new(*s) {
+            Err(ref e) if e.kind() == ErrorKind::InvalidInput => {}
+            r => panic!("unexpected result for {}: {:?}", s, r),
+        }
+    }
+}
+
+#[test]
+fn options() {
+    let mut opts = MatchOptions::new();
+    assert!(!pattern("*.MD").matches_with("guide.md", &opts));
+    assert!(pattern(".*").matches_with(".git", &opts));
+    assert!(!pattern("*").matches_with(".git", &opts));
+    assert!(!pattern("?git").matches_with(".git", &opts));
+    assert!(!pattern("[.]git").matches_with(".git", &opts));
+
+    opts.case_sensitive(false).hidden(true);
+    assert!(pattern("*.MD").matches_with("guide.md", &opts));
+    assert!(pattern("[A-C].rs").matches_with("b.rs", &opts));
+    assert!(pattern("*").matches_with(".git", &opts));
+    assert!(pattern("**/*.rs").matches_with(".hidden/secret.rs", &opts));
+    assert!(pattern("*").matches_path_with(&Path::new(".git"), &opts));
+}
+
+#[test]
+fn glob_files() {
+    let dir = sample_tree();
+    let opts = MatchOptions::new();
+    assert_eq!(glob_in(&dir, "src/*.rs", &opts), vec!["src/lib.rs"]);
+    assert_eq!(glob_in(&dir, "src/**/*.rs", &opts),
+               vec!["src/fs/glob.rs", "src/fs/mod.rs", "src/lib.rs"]);
+    assert_eq!(glob_in(&dir, "*", &opts),
+               vec!["Cargo.toml", "docs", "src", "target"]);
+    assert_eq!(glob_in(&dir, "{docs/*.md,src/fs}", &opts),
+               vec!["docs/README.md", "src/fs"]);
+    assert_eq!(glob_in(&dir, "*/*/*.rs", &opts),
+               vec!["src/fs/glob.rs", "src/fs/mod.rs", "target/debug/out.rs"]);
+    assert_eq!(glob_in(&dir, "missing/*", &opts), Vec::<String>::new());
+    assert_eq!(glob_in(&dir, "Cargo.toml", &opts), vec!["Cargo.toml"]);
+}
+
+#[test]
+fn glob_options() {
+    let dir = sample_tree();
+    let mut opts = MatchOptions::new();
+    opts.hidden(true);
+    assert_eq!(glob_in(&dir, "src/**/*.rs", &opts),
+               vec!["src/.dot.rs", "src/.hidden/secret.rs", "src/fs/glob.rs",
+                    "src/fs/mod.rs", "src/lib.rs"]);
+
+    opts.hidden(false).case_sensitive(false);
+    assert_eq!(glob_in(&dir, "DOCS/*.md", &opts),
+               vec!["docs/README.md", "docs/guide.MD"]);
+}
+
+#[test]
+fn glob_invalid() {
+    assert!(glob::glob("src/[a").is_err());
+    assert!(glob::glob("{/a,b}").is_err());
+}
@@ -0,0 +1,571 @@
+#![feature(core)]
+
+extern crate io2;
+
+use io2::io::prelude::*;
+use io2::io::{self, BufReader, BufWriter, LineWriter, Cursor, SeekPos};
+use io2::io::ErrorKind;
+use io2::env;
+use io2::process::{Command, Stdio};
+
+macro_rules! t {
+    ($e:expr) => {
+        match $e {
+            Ok(t) => t,
+            Err(e) => panic!("received error for `{}`: {}", stringify!($e), e),
+        }
+    }
+}
+
+/// A dummy reader intended at testing short-reads propagation.
+struct ShortReader {
+    lengths: Vec<usize>,
+}
+
+impl Read for ShortReader {
+    fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+        if self.lengths.is_empty() {
+            Ok(0)
+        } else {
+            Ok(self.lengths.remove(0))
+        }
+    }
+}
+
+/// A reader which always fails.
+struct FailingReader;
+
+impl Read for FailingReader {
+    fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::new(ErrorKind::Other, "reader failed", None))
+    }
+}
+
+/// A writer which accepts at most `limit` bytes in total, after which every
+/// write fails.
+struct FailingWriter {
+    written: Vec<u8>,
+    limit: usize,
+}
+
+impl Write for FailingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.written.len() == self.limit {
+            return Err(io::Error::new(ErrorKind::Other, "writer is full", None))
+        }
+        let amt = std::cmp::min(buf.len(), self.limit - self.written.len());
+        self.written.push_all(&buf[..amt]);
+        Ok(amt)
+    }
+}
+
+#[test]
+fn buffered_reader() {
+    let inner: &[u8] = &[5, 6, 7, 0, 1, 2, 3, 4];
+    let mut reader = BufReader::with_capacity(2, inner);
+
+    let mut buf = [0, 0, 0];
+    let nread = t!(reader.read(&mut buf));
+    assert_eq!(nread, 3);
+    assert_eq!(buf, [5, 6, 7]);
+
+    let mut buf = [0, 0];
+    let nread = t!(reader.read(&mut buf));
+    assert_eq!(nread, 2);
+    assert_eq!(buf, [0, 1]);
+
+    let mut buf = [0];
+    let nread = t!(reader.read(&mut buf));
+    assert_eq!(nread, 1);
+    assert_eq!(buf, [2]);
+
+    let mut buf = [0, 0, 0];
+    let nread = t!(reader.read(&mut buf));
+    assert_eq!(nread, 1);
+    assert_eq!(buf, [3, 0, 0]);
+
+    let nread = t!(reader.read(&mut buf));
+    assert_eq!(nread, 1);
+    assert_eq!(buf, [4, 0, 0]);
+
+    assert_eq!(t!(reader.read(&mut buf)), 0);
+}
+
+#[test]
+fn buffered_reader_fill_buf() {
+    let inner: &[u8] = &[0, 1, 2, 3, 4];
+    let mut reader = BufReader::with_capacity(3, inner);
+    assert_eq!(t!(reader.fill_buf()), [0, 1, 2]);
+    reader.consume(1);
+    assert_eq!(t!(reader.fill_buf()), [1, 2]);
+    reader.consume(2);
+    assert_eq!(t!(reader.fill_buf()), [3, 4]);
+    reader.consume(10);
+    assert_eq!(t!(reader.fill_buf()), []);
+}
+
+#[test]
+fn buffered_reader_short_reads() {
+    let inner = ShortReader { lengths: vec![0, 1, 2, 0, 1, 0] };
+    let mut reader = BufReader::new(inner);
+    let mut buf = [0, 0];
+    assert_eq!(t!(reader.read(&mut buf)), 0);
+    assert_eq!(t!(reader.read(&mut buf)), 1);
+    assert_eq!(t!(reader.read(&mut buf)), 2);
+    assert_eq!(t!(reader.read(&mut buf)), 0);
+    assert_eq!(t!(reader.read(&mut buf)), 1);
+    assert_eq!(t!(reader.read(&mut buf)), 0);
+    assert_eq!(t!(reader.read(&mut buf)), 0);
+}
+
+#[test]
+fn buffered_reader_seek() {
+    let inner: &[u8] = &[5, 6, 7, 0, 1, 2, 3, 4];
+    let mut reader = BufReader::with_capacity(2, Cursor::new(inner));
+
+    assert_eq!(t!(reader.seek(SeekPos::FromStart(3))), 3);
+    assert_eq!(t!(reader.fill_buf()), [0, 1]);
+    assert_eq!(t!(reader.seek(SeekPos::FromCur(0))), 3);
+    assert_eq!(t!(reader.fill_buf()), [0, 1]);
+    reader.consume(1);
+    assert_eq!(t!(reader.seek(SeekPos::FromCur(1))), 5);
+    assert_eq!(t!(reader.fill_buf()), [2, 3]);
+    assert_eq!(t!(reader.seek(SeekPos::FromEnd(-1))), 7);
+    assert_eq!(t!(reader.fill_buf()), [4]);
+}
+
+#[test]
+fn buffered_reader_seek_discards_buffer() {
+    let inner: &[u8] = &[0, 1, 2, 3, 4, 5];
+    let mut reader = BufReader::with_capacity(4, Cursor::new(inner));
+
+    assert_eq!(t!(reader.fill_buf()), [0, 1, 2, 3]);
+    reader.consume(1);
+    assert_eq!(t!(reader.seek(SeekPos::FromCur(0))), 1);
+    assert_eq!(reader.get_ref().position(), 1);
+    assert_eq!(reader.into_inner().position(), 1);
+}
+
+#[test]
+fn buffered_reader_inner() {
+    let inner: &[u8] = &[0, 1, 2];
+    let mut reader = BufReader::new(inner);
+    assert_eq!(reader.get_ref().len(), 3);
+    reader.get_mut();
+    let mut buf = [0];
+    assert_eq!(t!(reader.read(&mut buf)), 1);
+    assert_eq!(reader.into_inner().len(), 0);
+}
+
+#[test]
+fn buffered_writer() {
+    let inner = Vec::new();
+    let mut writer = BufWriter::with_capacity(2, inner);
+
+    t!(writer.write(&[0, 1]));
+    assert_eq!(*writer.get_ref(), [0, 1]);
+
+    t!(writer.write(&[2]));
+    assert_eq!(*writer.get_ref(), [0, 1]);
+
+    t!(writer.write(&[3]));
+    assert_eq!(*writer.get_ref(), [0, 1]);
+
+    t!(writer.flush());
+    assert_eq!(*writer.get_ref(), [0, 1, 2, 3]);
+
+    t!(writer.write(&[4]));
+    t!(writer.write(&[5]));
+    assert_eq!(*writer.get_ref(), [0, 1, 2, 3]);
+
+    t!(writer.write(&[6]));
+    assert_eq!(*writer.get_ref(), [0, 1, 2, 3, 4, 5]);
+
+    t!(writer.write(&[7, 8]));
+    assert_eq!(*writer.get_ref(), [0, 1, 2, 3, 4, 5, 6, 7, 8]);
+
+    t!(writer.write(&[9, 10, 11]));
+    assert_eq!(*writer.get_ref(), [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]);
+
+    t!(writer.flush());
+    assert_eq!(*writer.get_ref(), [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]);
+}
+
+#[test]
+fn buffered_writer_inner_flushes() {
+    let mut w = BufWriter::with_capacity(3, Vec::new());
+    t!(w.write(&[0, 1]));
+    assert_eq!(*w.get_ref(), []);
+    let w = t!(w.into_inner());
+    assert_eq!(w, [0, 1]);
+}
+
+#[test]
+fn buffered_writer_flushes_on_drop() {
+    let mut out = Vec::new();
+    {
+        let mut w = BufWriter::new(&mut out);
+        t!(write!(&mut w, "{} {}", 1, "two"));
+    }
+    assert_eq!(out, b"1 two");
+}
+
+#[test]
+fn buffered_writer_into_inner_partial() {
+    let inner = FailingWriter { written: Vec::new(), limit: 3 };
+    let mut w = BufWriter::with_capacity(8, inner);
+    t!(w.write(&[0, 1, 2, 3, 4]));
+
+    let err = match w.into_inner() {
+        Ok(..) => panic!("into_inner should have failed"),
+        Err(e) => e,
+    };
+    assert_eq!(err.error().kind(), ErrorKind::Other);
+    let w = err.into_inner();
+    assert_eq!(w.get_ref().written, [0, 1, 2]);
+    assert_eq!(w.buffer(), &[3, 4][..]);
+}
+
+#[test]
+fn buffered_writer_seek() {
+    let mut w = BufWriter::with_capacity(3, Cursor::new(Vec::new()));
+    t!(w.write(&[0, 1, 2, 3, 4, 5]));
+    t!(w.write(&[6, 7]));
+    assert_eq!(t!(w.seek(SeekPos::FromStart(2))), 2);
+    assert_eq!(*w.get_ref().get_ref(), [0, 1, 2, 3, 4, 5, 6, 7]);
+    t!(w.write(&[8, 9]));
+    assert_eq!(t!(w.into_inner()).into_inner(), [0, 1, 8, 9, 4, 5, 6, 7]);
+}
+
+#[test]
+fn line_writer() {
+    let mut writer = LineWriter::new(Vec::new());
+    t!(writer.write(&[0]));
+    assert_eq!(*writer.get_ref(), []);
+    t!(writer.write(&[1]));
+    assert_eq!(*writer.get_ref(), []);
+    t!(writer.flush());
+    assert_eq!(*writer.get_ref(), [0, 1]);
+    t!(writer.write(&[0, b'\n', 1, b'\n', 2]));
+    assert_eq!(*writer.get_ref(), [0, 1, 0, b'\n', 1, b'\n']);
+    t!(writer.flush());
+    assert_eq!(*writer.get_ref(), [0, 1, 0, b'\n', 1, b'\n', 2]);
+    t!(writer.write(&[3, b'\n']));
+    assert_eq!(*writer.get_ref(), [0, 1, 0, b'\n', 1, b'\n', 2, 3, b'\n']);
+}
+
+#[test]
+fn line_writer_into_inner() {
+    let mut writer = LineWriter::new(Vec::new());
+    t!(writer.write(b"no newline"));
+    assert_eq!(*writer.get_ref(), []);
+    assert_eq!(t!(writer.into_inner()), b"no newline");
+}
+
+#[test]
+fn read_until() {
+    let mut buf = Cursor::new(b"12".to_vec());
+    let mut v = Vec::new();
+    assert_eq!(t!(buf.read_until(b'3', &mut v)), 2);
+    assert_eq!(v, b"12");
+
+    let mut buf = Cursor::new(b"1233".to_vec());
+    let mut v = Vec::new();
+    assert_eq!(t!(buf.read_until(b'3', &mut v)), 3);
+    assert_eq!(v, b"123");
+    v.truncate(0);
+    assert_eq!(t!(buf.read_until(b'3', &mut v)), 1);
+    assert_eq!(v, b"3");
+    v.truncate(0);
+    assert_eq!(t!(buf.read_until(b'3', &mut v)), 0);
+    assert!(v.is_empty());
+}
+
+#[test]
+fn read_until_across_buffers() {
+    let inner: &[u8] = b"abc,defgh,ij";
+    let mut reader = BufReader::with_capacity(2, inner);
+    let mut v = Vec::new();
+    assert_eq!(t!(reader.read_until(b',', &mut v)), 4);
+    assert_eq!(v, b"abc,");
+    v.truncate(0);
+    assert_eq!(t!(reader.read_until(b',', &mut v)), 6);
+    assert_eq!(v, b"defgh,");
+    v.truncate(0);
+    assert_eq!(t!(reader.read_until(b',', &mut v)), 2);
+    assert_eq!(v, b"ij");
+}
+
+#[test]
+fn read_line() {
+    let mut buf = Cursor::new(b"12".to_vec());
+    let mut v = String::new();
+    assert_eq!(t!(buf.read_line(&mut v)), 2);
+    assert_eq!(v, "12");
+
+    let mut buf = Cursor::new(b"12\n\n".to_vec());
+    let mut v = String::new();
+    assert_eq!(t!(buf.read_line(&mut v)), 3);
+    assert_eq!(v, "12\n");
+    v.truncate(0);
+    assert_eq!(t!(buf.read_line(&mut v)), 1);
+    assert_eq!(v, "\n");
+    v.truncate(0);
+    assert_eq!(t!(buf.read_line(&mut v)), 0);
+    assert_eq!(v, "");
+}
+
+#[test]
+fn read_line_invalid_utf8() {
+    let mut buf = Cursor::new(b"\xff\n".to_vec());
+    let mut v = String::from_str("ok");
+    match buf.read_line(&mut v) {
+        Ok(..) => panic!("invalid utf-8 should not be accepted"),
+        Err(e) => assert_eq!(e.kind(), ErrorKind::InvalidInput),
+    }
+    assert_eq!(v, "ok");
+}
+
+#[test]
+fn read_line_keeps_data_on_error() {
+    let inner: &[u8] = b"abc";
+    let mut reader = BufReader::with_capacity(2, inner.chain(FailingReader));
+    let mut v = String::new();
+    match reader.read_line(&mut v) {
+        Ok(..) => panic!("expected an error"),
+        Err(e) => assert_eq!(e.kind(), ErrorKind::Other),
+    }
+    assert_eq!(v, "abc");
+}
+
+#[test]
+fn split() {
+    let buf = Cursor::new(b"12".to_vec());
+    let mut s = buf.split(b'3');
+    assert_eq!(t!(s.next().unwrap()), [b'1', b'2']);
+    assert!(s.next().is_none());
+
+    let buf = Cursor::new(b"1233".to_vec());
+    let mut s = buf.split(b'3');
+    assert_eq!(t!(s.next().unwrap()), [b'1', b'2']);
+    assert!(t!(s.next().unwrap()).is_empty());
+    assert!(s.next().is_none());
+}
+
+#[test]
+fn lines() {
+    let buf = Cursor::new(b"12\r".to_vec());
+    let mut s = buf.lines();
+    assert_eq!(t!(s.next().unwrap()), "12\r");
+    assert!(s.next().is_none());
+
+    let buf = Cursor::new(b"12\r\n\n34".to_vec());
+    let mut s = buf.lines();
+    assert_eq!(t!(s.next().unwrap()), "12");
+    assert_eq!(t!(s.next().unwrap()), "");
+    assert_eq!(t!(s.next().unwrap()), "34");
+    assert!(s.next().is_none());
+}
+
+#[test]
+fn lines_by_ref() {
+    let inner: &[u8] = b"a\nb\nc";
+    let mut reader = BufReader::new(inner);
+    assert_eq!(t!(reader.by_ref().lines().next().unwrap()), "a");
+    let rest: Vec<String> = reader.lines().map(|l| t!(l)).collect();
+    assert_eq!(rest, ["b", "c"]);
+}
+
+// Runs only the test `name` from this binary in a child process, feeding it
+// `input` on stdin, and returns what it wrote to stdout and stderr. The child
+// sees IO2_TEST_STDIO set, which tells it to do the work of the test.
+fn run_child(name: &str, input: &[u8]) -> (String, String) {
+    let exe = env::current_exe().unwrap();
+    let mut child = t!(Command::new(exe.as_str().unwrap())
+                               .arg(name)
+                               .env("IO2_TEST_STDIO", "1")
+                               .stdin(Stdio::piped())
+                               .stdout(Stdio::piped())
+                               .stderr(Stdio::piped())
+                               .spawn());
+    t!(child.stdin.take().unwrap().write_all(input));
+    let output = t!(child.wait_with_output());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(output.status.success(), "{}\n{}", stdout, stderr);
+    (stdout, stderr)
+}
+
+fn in_child() -> bool { env::var("IO2_TEST_STDIO").is_some() }
+
+#[test]
+fn stdout_and_stderr_accept_writes() {
+    if !in_child() {
+        let (out, err) = run_child("stdout_and_stderr_accept_writes", b"");
+        assert!(out.contains("stdout locked\n"), "{}", out);
+        assert!(err.contains("stderr\n"), "{}", err);
+        return
+    }
+    t!(write!(&mut io::stdout(), "stdout "));
+    t!(io::stdout().flush());
+    t!(writeln!(&mut io::stderr(), "stderr"));
+
+    let stdout = io::stdout();
+    let mut lock = stdout.lock();
+    t!(lock.write_all(b"locked\n"));
+}
+
+#[test]
+fn stdio_handles_are_shared_across_threads() {
+    if !in_child() {
+        let (_, err) = run_child("stdio_handles_are_shared_across_threads",
+                                 b"");
+        for i in 0..4 {
+            let line = format!("thread {} says hello\n", i);
+            assert!(err.contains(&line), "{}", err);
+        }
+        return
+    }
+    let threads: Vec<_> = (0..4).map(|i| {
+        std::thread::Thread::scoped(move || {
+            let stderr = io::stderr();
+            let mut lock = stderr.lock();
+            t!(writeln!(&mut lock, "thread {} says hello", i));
+        })
+    }).collect();
+    drop(threads);
+}
+
+#[test]
+fn stdin_lines_in_child() {
+    if !in_child() {
+        let (out, _) = run_child("stdin_lines_in_child", b"one\ntwo\nthree");
+        assert!(out.contains("[one][two][three]"), "{}", out);
+        return
+    }
+    let stdin = io::stdin();
+    let mut out = io::stdout();
+    for line in stdin.lock().lines() {
+        t!(write!(&mut out, "[{}]", t!(line)));
+    }
+    t!(out.flush());
+}
+
+#[test]
+#[cfg(unix)]
+fn stdin_read_flushes_prompt() {
+    use io2::fs::File;
+    use io2::os::unix::FromRawFd;
+
+    if !in_child() {
+        let (out, _) = run_child("stdin_read_flushes_prompt", b"answer\n");
+        assert!(out.contains("prompt> answer\n"), "{}", out);
+        return
+    }
+    t!(write!(&mut io::stdout(), "prompt> "));
+    let mut line = String::new();
+    t!(io::stdin().read_line(&mut line));
+    // write around the stdout buffer, so this lands after the prompt only if
+    // reading flushed it
+    let mut raw = unsafe { File::from_raw_fd(1) };
+    t!(raw.write_all(line.as_bytes()));
+}
+
+#[test]
+fn stdin_read_with_stdout_locked() {
+    if !in_child() {
+        let (out, _) = run_child("stdin_read_with_stdout_locked", b"answer\n");
+        assert!(out.contains("locked> answer\n"), "{}", out);
+        return
+    }
+    // reading must not wait for the stdout lock this thread holds
+    let stdout = io::stdout();
+    let mut lock = stdout.lock();
+    t!(write!(&mut lock, "locked> "));
+    t!(lock.flush());
+    let mut line = String::new();
+    t!(io::stdin().read_line(&mut line));
+    t!(write!(&mut lock, "{}", line));
+}
+
+#[test]
+fn read_vectored_default() {
+    // the default implementation only fills the first nonempty buffer
+    let mut r = ShortReader { lengths: vec![2] };
+    let (mut a, mut b) = ([0; 0], [0; 4]);
+    assert_eq!(t!(r.read_vectored(&mut [&mut a, &mut b])), 2);
+    assert_eq!(t!(r.read_vectored(&mut [&mut a])), 0);
+
+    let mut c = Cursor::new(vec![1, 2, 3]);
+    let (mut a, mut b) = ([0; 2], [0; 2]);
+    assert_eq!(t!(c.read_vectored(&mut [&mut a, &mut b])), 2);
+    assert_eq!(a, [1, 2]);
+    assert_eq!(b, [0, 0]);
+}
+
+#[test]
+fn write_vectored_vec() {
+    let mut v = Vec::new();
+    assert_eq!(t!(v.write_vectored(&[b"ab", b"", b"cd"])), 4);
+    assert_eq!(v, b"abcd".to_vec());
+}
+
+#[test]
+fn write_all_vectored() {
+    // a writer which accepts a single byte at a time forces every buffer to
+    // be resumed part way through
+    struct OneByte(Vec<u8>);
+    impl Write for OneByte {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.push(buf[0]);
+            Ok(1)
+        }
+    }
+
+    let mut w = OneByte(Vec::new());
+    t!(w.write_all_vectored(&[b"abc", b"", b"de", b"f"]));
+    assert_eq!(w.0, b"abcdef".to_vec());
+
+    let mut w = OneByte(Vec::new());
+    t!(w.write_all_vectored(&[]));
+    t!(w.write_all_vectored(&[b"", b""]));
+    assert!(w.0.is_empty());
+
+    let mut buf = [0; 3];
+    let mut w = &mut buf[..];
+    let err = w.write_all_vectored(&[b"ab", b"cd"]).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::EndOfFile);
+}
+
+#[test]
+fn take_read_vectored() {
+    let mut r = Cursor::new(vec![1, 2, 3, 4, 5]).take(3);
+    let (mut a, mut b) = ([0; 2], [0; 2]);
+    let n = t!(r.read_vectored(&mut [&mut a, &mut b]));
+    assert!(n > 0 && n <= 2);
+    let mut rest = Vec::new();
+    t!(r.read_to_end(&mut rest));
+    assert_eq!(n + rest.len(), 3);
+}
+
+#[test]
+fn chain_read_vectored() {
+    let mut r = Cursor::new(vec![1]).chain(Cursor::new(vec![2]));
+    let mut a = [0; 4];
+    assert_eq!(t!(r.read_vectored(&mut [&mut a])), 1);
+    assert_eq!(a[0], 1);
+    assert_eq!(t!(r.read_vectored(&mut [&mut a])), 1);
+    assert_eq!(a[0], 2);
+    assert_eq!(t!(r.read_vectored(&mut [&mut a])), 0);
+}
+
+#[test]
+fn broadcast_write_vectored() {
+    let mut out = Vec::new();
+    {
+        let mut b = Vec::new().broadcast(&mut out);
+        assert_eq!(t!(b.write_vectored(&[b"xy", b"z"])), 3);
+    }
+    // the second writer receives exactly what the first one accepted
+    assert_eq!(out, b"xyz".to_vec());
+}
@@ -0,0 +1,146 @@
+#![feature(path)]
+
+extern crate io2;
+
+use io2::fs::{File, OpenOptions, TempDir};
+use io2::io::prelude::*;
+use io2::io::{ErrorKind, SeekPos};
+use io2::memory_map::{MemoryMap, Protection};
+
+macro_rules! t {
+    ($e:expr) => {
+        match $e {
+            Ok(t) => t,
+            Err(e) => panic!("received error for `{}`: {}", stringify!($e), e),
+        }
+    }
+}
+
+fn read_write(path: &Path) -> File {
+    t!(File::open_opts(path, OpenOptions::new().read(true).write(true)))
+}
+
+fn contents(path: &Path) -> Vec<u8> {
+    let mut v = Vec::new();
+    t!(t!(File::open(path)).read_to_end(&mut v));
+    v
+}
+
+#[test]
+fn map_read() {
+    let dir = t!(TempDir::new("io2-memory-map"));
+    let path = dir.path().join("file");
+    t!(t!(File::create(&path)).write_all(b"hello world"));
+
+    let map = t!(MemoryMap::open(&t!(File::open(&path)), Protection::Read));
+    assert_eq!(map.len(), 11);
+    assert_eq!(&map[..], b"hello world");
+    assert_eq!(map.protection(), Protection::Read);
+}
+
+#[test]
+fn map_read_is_not_writable() {
+    let dir = t!(TempDir::new("io2-memory-map"));
+    let path = dir.path().join("file");
+    t!(t!(File::create(&path)).write_all(b"hello world"));
+
+    let mut map = t!(MemoryMap::open(&t!(File::open(&path)), Protection::Read));
+    match map.as_mut_slice() {
+        Err(ref e) if e.kind() == ErrorKind::PermissionDenied => {}
+        Err(e) => panic!("unexpected error: {}", e),
+        Ok(..) => panic!("read-only map was writable"),
+    }
+    t!(map.flush());
+}
+
+#[test]
+fn map_read_write() {
+    let dir = t!(TempDir::new("io2-memory-map"));
+    let path = dir.path().join("file");
+    t!(t!(File::create(&path)).write_all(b"hello world"));
+
+    {
+        let mut map = t!(MemoryMap::open(&read_write(&path),
+                                         Protection::ReadWrite));
+        t!(map.as_mut_slice())[0] = b'j';
+        t!(map.flush());
+    }
+    assert_eq!(contents(&path), b"jello world".to_vec());
+}
+
+#[test]
+fn map_copy_on_write() {
+    let dir = t!(TempDir::new("io2-memory-map"));
+    let path = dir.path().join("file");
+    t!(t!(File::create(&path)).write_all(b"hello world"));
+
+    let mut map = t!(MemoryMap::open(&t!(File::open(&path)),
+                                     Protection::ReadCopy));
+    t!(map.as_mut_slice())[0] = b'j';
+    t!(map.flush());
+    assert_eq!(&map[..], b"jello world");
+    assert_eq!(contents(&path), b"hello world".to_vec());
+}
+
+#[test]
+fn map_unaligned_range() {
+    let dir = t!(TempDir::new("io2-memory-map"));
+    let path = dir.path().join("file");
+    let data = (0..20000).map(|i| i as u8).collect::<Vec<u8>>();
+    t!(t!(File::create(&path)).write_all(&data));
+
+    let mut map = t!(MemoryMap::open_range(&read_write(&path),
+                                           Protection::ReadWrite,
+                                           5000, 10000));
+    assert_eq!(map.len(), 10000);
+    assert_eq!(&map[..], &data[5000..15000]);
+
+    t!(map.as_mut_slice())[9999] = 0;
+    t!(map.flush_range(9000, 1000));
+    drop(map);
+
+    let mut file = t!(File::open(&path));
+    t!(file.seek(SeekPos::FromStart(14999)));
+    let mut buf = [1];
+    t!(file.read(&mut buf));
+    assert_eq!(buf[0], 0);
+}
+
+#[test]
+fn map_invalid_ranges() {
+    let dir = t!(TempDir::new("io2-memory-map"));
+    let path = dir.path().join("file");
+    t!(t!(File::create(&path)).write_all(b"hello"));
+    let file = t!(File::open(&path));
+
+    let err = MemoryMap::open_range(&file, Protection::Read, 0, 0).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    let err = MemoryMap::open_range(&file, Protection::Read, 3, 3).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    let err = MemoryMap::open_range(&file, Protection::Read, 6, 1).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+    let map = t!(MemoryMap::open_range(&file, Protection::Read, 1, 4));
+    assert_eq!(&map[..], b"ello");
+    assert!(map.flush_range(2, 3).is_err());
+}
+
+#[test]
+fn map_empty_file() {
+    let dir = t!(TempDir::new("io2-memory-map"));
+    let path = dir.path().join("file");
+    t!(File::create(&path));
+    assert!(MemoryMap::open(&t!(File::open(&path)), Protection::Read).is_err());
+}
+
+#[test]
+fn map_outlives_file() {
+    let dir = t!(TempDir::new("io2-memory-map"));
+    let path = dir.path().join("file");
+    t!(t!(File::create(&path)).write_all(b"foo"));
+    let map = {
+        let file = t!(File::open(&path));
+        t!(MemoryMap::open(&file, Protection::Read))
+    };
+    assert_eq!(&map[..], b"foo");
+}
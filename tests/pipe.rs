@@ -0,0 +1,119 @@
+#![feature(std_misc)]
+#![cfg(unix)]
+
+extern crate io2;
+
+use io2::io::prelude::*;
+use io2::io::{self, ErrorKind};
+
+use std::thread::Thread;
+
+macro_rules! t {
+    ($e:expr) => {
+        match $e {
+            Ok(t) => t,
+            Err(e) => panic!("received error for `{}`: {}", stringify!($e), e),
+        }
+    }
+}
+
+#[test]
+fn smoke() {
+    let (mut reader, mut writer) = t!(io::pipe());
+    t!(writer.write_all(b"hello"));
+    let mut buf = [0; 5];
+    assert_eq!(t!(reader.read(&mut buf)), 5);
+    assert_eq!(&buf, b"hello");
+}
+
+#[test]
+fn eof_after_writer_dropped() {
+    let (mut reader, mut writer) = t!(io::pipe());
+    t!(writer.write_all(b"abc"));
+    drop(writer);
+    let mut s = String::new();
+    t!(reader.read_to_string(&mut s));
+    assert_eq!(s, "abc");
+    assert_eq!(t!(reader.read(&mut [0; 4])), 0);
+}
+
+#[test]
+fn broken_pipe() {
+    let (reader, mut writer) = t!(io::pipe());
+    drop(reader);
+    match writer.write(b"abc") {
+        Ok(n) => panic!("wrote {} bytes to a closed pipe", n),
+        Err(e) => assert_eq!(e.kind(), ErrorKind::BrokenPipe),
+    }
+}
+
+#[test]
+fn across_threads() {
+    let (mut reader, mut writer) = t!(io::pipe());
+    let t = Thread::scoped(move || {
+        for i in 0..100u8 {
+            t!(writer.write_all(&[i]));
+        }
+    });
+    let mut data = Vec::new();
+    t!(reader.read_to_end(&mut data));
+    t.join();
+    assert_eq!(data, (0..100u8).collect::<Vec<_>>());
+}
+
+#[test]
+fn try_clone_reader() {
+    let (reader, mut writer) = t!(io::pipe());
+    let mut reader2 = t!(reader.try_clone());
+    drop(reader);
+    t!(writer.write_all(b"x"));
+    let mut buf = [0];
+    assert_eq!(t!(reader2.read(&mut buf)), 1);
+    assert_eq!(buf[0], b'x');
+}
+
+#[test]
+fn try_clone_writer() {
+    let (mut reader, writer) = t!(io::pipe());
+    let mut writer2 = t!(writer.try_clone());
+    t!(writer2.write_all(b"a"));
+    drop(writer2);
+
+    // the pipe stays open while the original writer is alive
+    let mut buf = [0; 2];
+    assert_eq!(t!(reader.read(&mut buf)), 1);
+    drop(writer);
+    assert_eq!(t!(reader.read(&mut buf)), 0);
+}
+
+#[test]
+fn nonblocking_read() {
+    let (mut reader, mut writer) = t!(io::pipe());
+    t!(reader.set_nonblocking(true));
+    match reader.read(&mut [0; 4]) {
+        Ok(n) => panic!("read {} bytes from an empty pipe", n),
+        Err(e) => assert_eq!(e.kind(), ErrorKind::WouldBlock),
+    }
+    t!(writer.write_all(b"a"));
+    assert_eq!(t!(reader.read(&mut [0; 4])), 1);
+
+    t!(reader.set_nonblocking(false));
+    drop(writer);
+    assert_eq!(t!(reader.read(&mut [0; 4])), 0);
+}
+
+#[test]
+fn nonblocking_write() {
+    let (_reader, mut writer) = t!(io::pipe());
+    t!(writer.set_nonblocking(true));
+    let buf = [0; 1024];
+    loop {
+        match writer.write(&buf) {
+            Ok(..) => {}
+            Err(e) => {
+                assert_eq!(e.kind(), ErrorKind::WouldBlock);
+                break
+            }
+        }
+    }
+}
@@ -0,0 +1,230 @@
+#![feature(std_misc, core)]
+#![cfg(any(target_os = "linux", target_os = "android"))]
+
+extern crate io2;
+
+use io2::io::prelude::*;
+use io2::io::{ErrorKind, Poll, Events, Token, Interest, Trigger};
+use io2::net::*;
+use io2::os::unix::{AsRawFd, Fd};
+
+use std::sync::atomic::{AtomicUsize, ATOMIC_USIZE_INIT, Ordering};
+use std::time::Duration;
+
+fn next_test_ip4() -> SocketAddr {
+    static PORT: AtomicUsize = ATOMIC_USIZE_INIT;
+    SocketAddr {
+        ip: IpAddr::new_v4(127, 0, 0, 1),
+        port: PORT.fetch_add(1, Ordering::SeqCst) as u16 + 20000,
+    }
+}
+
+macro_rules! t {
+    ($e:expr) => {
+        match $e {
+            Ok(t) => t,
+            Err(e) => panic!("received error for `{}`: {}", stringify!($e), e),
+        }
+    }
+}
+
+fn pair() -> (TcpStream, TcpStream) {
+    let addr = next_test_ip4();
+    let listener = t!(TcpListener::bind(&addr));
+    let a = t!(TcpStream::connect(&addr));
+    let b = t!(listener.accept()).0;
+    (a, b)
+}
+
+fn timeout() -> Option<Duration> { Some(Duration::seconds(5)) }
+fn short() -> Option<Duration> { Some(Duration::milliseconds(10)) }
+
+#[test]
+fn nothing_ready() {
+    let poll = t!(Poll::new());
+    let mut events = Events::with_capacity(16);
+    assert_eq!(t!(poll.poll(&mut events, short())), 0);
+    assert!(events.is_empty());
+    assert_eq!(events.capacity(), 16);
+}
+
+#[test]
+fn zero_capacity() {
+    let (mut a, b) = pair();
+    let poll = t!(Poll::new());
+    t!(poll.register(&b, Token(0), Interest::Readable, Trigger::Level));
+    t!(a.write(&[1]));
+
+    let mut events = Events::with_capacity(0);
+    assert!(events.capacity() >= 1);
+    assert_eq!(t!(poll.poll(&mut events, timeout())), 1);
+}
+
+#[test]
+fn register_foreign_fd() {
+    struct Foreign(Fd);
+    impl AsRawFd for Foreign {
+        fn as_raw_fd(&self) -> Fd { self.0 }
+    }
+
+    let (mut a, b) = pair();
+    let foreign = Foreign(b.as_raw_fd());
+    let poll = t!(Poll::new());
+    t!(poll.register(&foreign, Token(5), Interest::Readable, Trigger::Level));
+    t!(a.write(&[1]));
+
+    let mut events = Events::with_capacity(16);
+    assert_eq!(t!(poll.poll(&mut events, timeout())), 1);
+    assert_eq!(events.get(0).unwrap().token(), Token(5));
+    t!(poll.deregister(&foreign));
+}
+
+#[test]
+fn listener_readable() {
+    let addr = next_test_ip4();
+    let listener = t!(TcpListener::bind(&addr));
+    let poll = t!(Poll::new());
+    t!(poll.register(&listener, Token(3), Interest::Readable, Trigger::Level));
+
+    let mut events = Events::with_capacity(16);
+    assert_eq!(t!(poll.poll(&mut events, short())), 0);
+
+    let _s = t!(TcpStream::connect(&addr));
+    assert_eq!(t!(poll.poll(&mut events, timeout())), 1);
+    let event = events.get(0).unwrap();
+    assert_eq!(event.token(), Token(3));
+    assert!(event.is_readable());
+    assert!(!event.is_writable());
+}
+
+#[test]
+fn stream_writable() {
+    let (a, _b) = pair();
+    let poll = t!(Poll::new());
+    t!(poll.register(&a, Token(1), Interest::Writable, Trigger::Level));
+
+    let mut events = Events::with_capacity(16);
+    assert_eq!(t!(poll.poll(&mut events, timeout())), 1);
+    let event = events.get(0).unwrap();
+    assert_eq!(event.token(), Token(1));
+    assert!(event.is_writable());
+    assert!(!event.is_readable());
+}
+
+#[test]
+fn many_streams() {
+    let (mut a1, b1) = pair();
+    let (mut a2, b2) = pair();
+    let (_a3, b3) = pair();
+    let poll = t!(Poll::new());
+    t!(poll.register(&b1, Token(1), Interest::Readable, Trigger::Level));
+    t!(poll.register(&b2, Token(2), Interest::Readable, Trigger::Level));
+    t!(poll.register(&b3, Token(3), Interest::Readable, Trigger::Level));
+
+    t!(a1.write(&[1]));
+    t!(a2.write(&[2]));
+
+    let mut events = Events::with_capacity(16);
+    let mut tokens = Vec::new();
+    while tokens.len() < 2 {
+        t!(poll.poll(&mut events, timeout()));
+        for event in events.iter() {
+            assert!(event.is_readable());
+            if !tokens.contains(&event.token()) {
+                tokens.push(event.token());
+            }
+        }
+    }
+    tokens.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(tokens, vec![Token(1), Token(2)]);
+}
+
+#[test]
+fn level_triggered_repeats() {
+    let (mut a, b) = pair();
+    let poll = t!(Poll::new());
+    t!(poll.register(&b, Token(0), Interest::Readable, Trigger::Level));
+    t!(a.write(&[1]));
+
+    let mut events = Events::with_capacity(16);
+    assert_eq!(t!(poll.poll(&mut events, timeout())), 1);
+    // nothing was read, so the stream is still readable
+    assert_eq!(t!(poll.poll(&mut events, timeout())), 1);
+}
+
+#[test]
+fn edge_triggered_once() {
+    let (mut a, mut b) = pair();
+    t!(b.set_nonblocking(true));
+    let poll = t!(Poll::new());
+    t!(poll.register(&b, Token(0), Interest::Readable, Trigger::Edge));
+    t!(a.write(&[1]));
+
+    let mut events = Events::with_capacity(16);
+    assert_eq!(t!(poll.poll(&mut events, timeout())), 1);
+    assert_eq!(t!(poll.poll(&mut events, short())), 0);
+
+    // drain the stream, after which new data is a new edge
+    let mut buf = [0; 16];
+    assert_eq!(t!(b.read(&mut buf)), 1);
+    match b.read(&mut buf) {
+        Ok(n) => panic!("read {} more bytes", n),
+        Err(e) => assert_eq!(e.kind(), ErrorKind::WouldBlock),
+    }
+    t!(a.write(&[2]));
+    assert_eq!(t!(poll.poll(&mut events, timeout())), 1);
+    assert_eq!(events.get(0).unwrap().token(), Token(0));
+}
+
+#[test]
+fn hangup() {
+    let (a, b) = pair();
+    let poll = t!(Poll::new());
+    t!(poll.register(&b, Token(0), Interest::Readable, Trigger::Level));
+    drop(a);
+
+    let mut events = Events::with_capacity(16);
+    assert_eq!(t!(poll.poll(&mut events, timeout())), 1);
+    assert!(events.get(0).unwrap().is_hangup());
+}
+
+#[test]
+fn reregister_and_deregister() {
+    let (mut a, b) = pair();
+    let poll = t!(Poll::new());
+    t!(poll.register(&b, Token(0), Interest::Writable, Trigger::Level));
+
+    let mut events = Events::with_capacity(16);
+    t!(a.write(&[1]));
+    t!(poll.reregister(&b, Token(7), Interest::Readable, Trigger::Level));
+    assert_eq!(t!(poll.poll(&mut events, timeout())), 1);
+    let event = events.get(0).unwrap();
+    assert_eq!(event.token(), Token(7));
+    assert!(event.is_readable());
+    assert!(!event.is_writable());
+
+    t!(poll.deregister(&b));
+    assert_eq!(t!(poll.poll(&mut events, short())), 0);
+}
+
+#[test]
+fn double_register() {
+    let (a, _b) = pair();
+    let poll = t!(Poll::new());
+    t!(poll.register(&a, Token(0), Interest::Both, Trigger::Level));
+    assert!(poll.register(&a, Token(0), Interest::Both, Trigger::Level).is_err());
+}
+
+#[test]
+fn udp_readable() {
+    let a = t!(UdpSocket::bind(&next_test_ip4()));
+    let b_addr = next_test_ip4();
+    let b = t!(UdpSocket::bind(&b_addr));
+    let poll = t!(Poll::new());
+    t!(poll.register(&b, Token(9), Interest::Readable, Trigger::Level));
+
+    t!(a.send_to(&[1], &b_addr));
+    let mut events = Events::with_capacity(16);
+    assert_eq!(t!(poll.poll(&mut events, timeout())), 1);
+    assert_eq!(events.get(0).unwrap().token(), Token(9));
+}
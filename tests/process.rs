@@ -0,0 +1,140 @@
+#![cfg(unix)]
+#![feature(core, path)]
+
+extern crate io2;
+
+use io2::io::prelude::*;
+use io2::io::ErrorKind;
+use io2::process::{Command, Stdio};
+
+macro_rules! t {
+    ($e:expr) => {
+        match $e {
+            Ok(t) => t,
+            Err(e) => panic!("received error for `{}`: {}", stringify!($e), e),
+        }
+    }
+}
+
+#[test]
+fn smoke() {
+    let mut p = t!(Command::new("true").spawn());
+    assert!(t!(p.wait()).success());
+}
+
+#[test]
+fn smoke_failure() {
+    match Command::new("if-this-is-a-binary-then-the-world-has-ended").spawn() {
+        Ok(..) => panic!(),
+        Err(e) => assert_eq!(e.kind(), ErrorKind::FileNotFound),
+    }
+}
+
+#[test]
+fn exit_reported_right() {
+    let status = t!(Command::new("false").status());
+    assert!(!status.success());
+    assert_eq!(status.code(), Some(1));
+    assert_eq!(status.signal(), None);
+}
+
+#[test]
+fn signal_reported_right() {
+    let mut p = t!(Command::new("sleep").arg("1000").spawn());
+    t!(p.kill());
+    let status = t!(p.wait());
+    assert_eq!(status.signal(), Some(9));
+    assert_eq!(status.code(), None);
+    assert!(p.kill().is_err());
+}
+
+#[test]
+fn stdout_works() {
+    let output = t!(Command::new("echo").arg("foobar").output());
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"foobar\n".to_vec());
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn stderr_works() {
+    let output = t!(Command::new("sh").args(&["-c", "echo foo 1>&2; exit 3"])
+                                      .output());
+    assert_eq!(output.status.code(), Some(3));
+    assert!(output.stdout.is_empty());
+    assert_eq!(output.stderr, b"foo\n".to_vec());
+}
+
+#[test]
+fn output_does_not_deadlock() {
+    // Write more than a pipe buffer's worth to both stdout and stderr
+    let output = t!(Command::new("sh").arg("-c").arg(
+        "i=0; while [ $i -lt 20000 ]; do echo aaaaaaaa; echo bbbbbbbb 1>&2; \
+         i=$((i+1)); done").output());
+    assert!(output.status.success());
+    assert_eq!(output.stdout.len(), 9 * 20000);
+    assert_eq!(output.stderr.len(), 9 * 20000);
+}
+
+#[test]
+fn stdin_works() {
+    let mut p = t!(Command::new("cat").stdin(Stdio::piped())
+                                      .stdout(Stdio::piped())
+                                      .spawn());
+    t!(p.stdin.as_mut().unwrap().write_all(b"foobar"));
+    drop(p.stdin.take());
+    let mut out = Vec::new();
+    t!(p.stdout.as_mut().unwrap().read_to_end(&mut out));
+    assert!(t!(p.wait()).success());
+    assert_eq!(out, b"foobar".to_vec());
+}
+
+#[test]
+fn null_stdio() {
+    let output = t!(Command::new("cat").stdin(Stdio::null()).output());
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let output = t!(Command::new("echo").arg("foo").stdout(Stdio::null())
+                                        .output());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn try_wait() {
+    let mut p = t!(Command::new("cat").stdin(Stdio::piped()).spawn());
+    assert!(t!(p.try_wait()).is_none());
+    drop(p.stdin.take());
+    let status = t!(p.wait());
+    assert!(status.success());
+    assert_eq!(t!(p.try_wait()), Some(status));
+}
+
+#[test]
+fn set_current_dir_works() {
+    let output = t!(Command::new("pwd").current_dir(&Path::new("/"))
+                                       .output());
+    assert_eq!(output.stdout, b"/\n".to_vec());
+}
+
+#[test]
+fn env_works() {
+    let output = t!(Command::new("sh").args(&["-c", "echo $RUN_TEST_NEW_ENV"])
+                                      .env("RUN_TEST_NEW_ENV", "123")
+                                      .output());
+    assert_eq!(output.stdout, b"123\n".to_vec());
+}
+
+#[test]
+fn env_remove_and_clear() {
+    let output = t!(Command::new("/usr/bin/env").env("FOO", "bar")
+                                                .env_remove("FOO")
+                                                .output());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!stdout.contains("FOO=bar"));
+
+    let output = t!(Command::new("/usr/bin/env").env_clear()
+                                                .env("FOO", "bar")
+                                                .output());
+    assert_eq!(output.stdout, b"FOO=bar\n".to_vec());
+}
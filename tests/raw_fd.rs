@@ -0,0 +1,135 @@
+#![cfg(unix)]
+#![feature(path, libc, core)]
+
+extern crate io2;
+extern crate libc;
+
+use io2::fs::{File, TempDir};
+use io2::io::prelude::*;
+use io2::io;
+use io2::net::*;
+use io2::net::unix::UnixStream;
+use io2::os::unix::prelude::*;
+
+use std::sync::atomic::{AtomicUsize, ATOMIC_USIZE_INIT, Ordering};
+
+macro_rules! t {
+    ($e:expr) => {
+        match $e {
+            Ok(t) => t,
+            Err(e) => panic!("received error for `{}`: {}", stringify!($e), e),
+        }
+    }
+}
+
+fn next_test_ip4() -> SocketAddr {
+    static PORT: AtomicUsize = ATOMIC_USIZE_INIT;
+    SocketAddr {
+        ip: IpAddr::new_v4(127, 0, 0, 1),
+        port: PORT.fetch_add(1, Ordering::SeqCst) as u16 + 30000,
+    }
+}
+
+fn is_open(fd: Fd) -> bool {
+    unsafe { libc::fcntl(fd, libc::F_GETFD) != -1 }
+}
+
+#[test]
+fn stdio() {
+    assert_eq!(io::stdin().as_raw_fd(), 0);
+    assert_eq!(io::stdout().as_raw_fd(), 1);
+    assert_eq!(io::stderr().as_raw_fd(), 2);
+}
+
+#[test]
+fn file_round_trip() {
+    let dir = t!(TempDir::new("io2-raw-fd"));
+    let path = dir.path().join("file");
+    let mut file = t!(File::create(&path));
+    t!(file.write_all(b"hello"));
+
+    let fd = file.as_raw_fd();
+    assert_eq!(file.into_raw_fd(), fd);
+    // ownership was given up, so the descriptor is still open
+    assert!(is_open(fd));
+
+    let mut file = unsafe { File::from_raw_fd(fd) };
+    t!(file.write_all(b" world"));
+    drop(file);
+
+    let mut s = String::new();
+    t!(t!(File::open(&path)).read_to_string(&mut s));
+    assert_eq!(s, "hello world");
+}
+
+#[test]
+fn file_from_foreign_fd() {
+    let dir = t!(TempDir::new("io2-raw-fd"));
+    let path = dir.path().join("file");
+    t!(t!(File::create(&path)).write_all(b"foreign"));
+
+    let file = t!(File::open(&path));
+    let fd = unsafe { libc::dup(file.as_raw_fd()) };
+    assert!(fd >= 0);
+    drop(file);
+
+    let mut file = unsafe { File::from_raw_fd(fd) };
+    let mut s = String::new();
+    t!(file.read_to_string(&mut s));
+    assert_eq!(s, "foreign");
+}
+
+#[test]
+fn pipe() {
+    let (reader, writer) = t!(io::pipe());
+    let (reader, writer) = (reader.into_raw_fd(), writer.into_raw_fd());
+    let mut reader = unsafe { io::PipeReader::from_raw_fd(reader) };
+    let mut writer = unsafe { io::PipeWriter::from_raw_fd(writer) };
+    t!(writer.write_all(b"abc"));
+    drop(writer);
+    let mut s = String::new();
+    t!(reader.read_to_string(&mut s));
+    assert_eq!(s, "abc");
+}
+
+#[test]
+fn tcp() {
+    let addr = next_test_ip4();
+    let listener = t!(TcpListener::bind(&addr));
+    let listener = unsafe {
+        TcpListener::from_raw_fd(listener.into_raw_fd())
+    };
+    let a = t!(TcpStream::connect(&addr));
+    let mut a = unsafe { TcpStream::from_raw_fd(a.into_raw_fd()) };
+    let mut b = t!(listener.accept()).0;
+
+    t!(a.write_all(&[1, 2, 3]));
+    let mut buf = [0; 3];
+    assert_eq!(t!(b.read(&mut buf)), 3);
+    assert_eq!(buf, [1, 2, 3]);
+    assert_eq!(t!(a.peer_addr()), t!(b.socket_addr()));
+}
+
+#[test]
+fn udp() {
+    let a_addr = next_test_ip4();
+    let b_addr = next_test_ip4();
+    let a = t!(UdpSocket::bind(&a_addr));
+    let a = unsafe { UdpSocket::from_raw_fd(a.into_raw_fd()) };
+    let b = t!(UdpSocket::bind(&b_addr));
+
+    t!(a.send_to(&[7], &b_addr));
+    let mut buf = [0; 1];
+    assert_eq!(t!(b.recv_from(&mut buf)), (1, a_addr));
+    assert_eq!(buf, [7]);
+}
+
+#[test]
+fn unix_stream() {
+    let (a, mut b) = t!(UnixStream::pair());
+    let mut a = unsafe { UnixStream::from_raw_fd(a.into_raw_fd()) };
+    t!(a.write_all(b"ping"));
+    let mut buf = [0; 4];
+    assert_eq!(t!(b.read(&mut buf)), 4);
+    assert_eq!(&buf, b"ping");
+}
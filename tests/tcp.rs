@@ -9,6 +9,7 @@ use io2::net::*;
 use std::sync::atomic::{AtomicUsize, ATOMIC_USIZE_INIT, Ordering};
 use std::sync::mpsc::channel;
 use std::thread::Thread;
+use std::time::Duration;
 
 fn next_test_ip4() -> SocketAddr {
     static PORT: AtomicUsize = ATOMIC_USIZE_INIT;
@@ -600,3 +601,193 @@ fn clone_accept_concurrent() {
         rx.recv().unwrap();
     })
 }
+
+#[test]
+fn connect_timeout_smoke() {
+    each_ip(&mut |addr| {
+        let acceptor = t!(TcpListener::bind(&addr));
+
+        let _t = Thread::scoped(move|| {
+            let mut stream = t!(TcpStream::connect_timeout(&addr,
+                                                           Duration::seconds(5)));
+            t!(stream.write(&[144]));
+        });
+
+        let mut stream = t!(acceptor.accept()).0;
+        let mut buf = [0];
+        t!(stream.read(&mut buf));
+        assert!(buf[0] == 144);
+    })
+}
+
+#[test]
+fn connect_timeout_refused() {
+    each_ip(&mut |addr| {
+        match TcpStream::connect_timeout(&addr, Duration::seconds(5)) {
+            Ok(..) => panic!("connected to nothing"),
+            Err(e) => assert_eq!(e.kind(), ErrorKind::ConnectionRefused),
+        }
+    })
+}
+
+#[test]
+fn connect_timeout_invalid() {
+    let addr = next_test_ip4();
+    match TcpStream::connect_timeout(&addr, Duration::zero()) {
+        Ok(..) => panic!("connected with a zero timeout"),
+        Err(e) => assert_eq!(e.kind(), ErrorKind::InvalidInput),
+    }
+}
+
+#[test]
+fn timeouts_set_and_clear() {
+    each_ip(&mut |addr| {
+        let _acceptor = t!(TcpListener::bind(&addr));
+        let stream = t!(TcpStream::connect(&addr));
+
+        assert!(t!(stream.read_timeout()).is_none());
+        assert!(t!(stream.write_timeout()).is_none());
+
+        t!(stream.set_read_timeout(Some(Duration::seconds(1))));
+        t!(stream.set_write_timeout(Some(Duration::seconds(1))));
+        assert!(t!(stream.read_timeout()).is_some());
+        assert!(t!(stream.write_timeout()).is_some());
+
+        t!(stream.set_read_timeout(None));
+        t!(stream.set_write_timeout(None));
+        assert!(t!(stream.read_timeout()).is_none());
+        assert!(t!(stream.write_timeout()).is_none());
+
+        let err = stream.set_read_timeout(Some(Duration::zero())).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    })
+}
+
+#[test]
+fn read_timeout() {
+    each_ip(&mut |addr| {
+        let acceptor = t!(TcpListener::bind(&addr));
+        let mut stream = t!(TcpStream::connect(&addr));
+        let _other = t!(acceptor.accept());
+
+        t!(stream.set_read_timeout(Some(Duration::milliseconds(10))));
+        let mut buf = [0];
+        match stream.read(&mut buf) {
+            Ok(n) => panic!("read {} bytes from an idle stream", n),
+            Err(e) => assert_eq!(e.kind(), ErrorKind::TimedOut),
+        }
+    })
+}
+
+#[test]
+fn read_with_timeout() {
+    each_ip(&mut |addr| {
+        let acceptor = t!(TcpListener::bind(&addr));
+        let mut stream = t!(TcpStream::connect(&addr));
+        let mut other = t!(acceptor.accept()).0;
+
+        t!(stream.set_read_timeout(Some(Duration::seconds(5))));
+        t!(other.write(&[1]));
+        let mut buf = [0];
+        assert_eq!(t!(stream.read(&mut buf)), 1);
+        assert_eq!(buf[0], 1);
+    })
+}
+
+#[test]
+fn write_timeout() {
+    each_ip(&mut |addr| {
+        let acceptor = t!(TcpListener::bind(&addr));
+        let mut stream = t!(TcpStream::connect(&addr));
+        let _other = t!(acceptor.accept());
+
+        // Nobody reads on the other end, so eventually the socket buffers
+        // fill up and the write has to time out.
+        t!(stream.set_write_timeout(Some(Duration::milliseconds(10))));
+        let buf = [0; 64 * 1024];
+        loop {
+            match stream.write(&buf) {
+                Ok(..) => {}
+                Err(e) => {
+                    assert_eq!(e.kind(), ErrorKind::TimedOut);
+                    break
+                }
+            }
+        }
+    })
+}
+
+#[test]
+fn nonblocking_accept() {
+    each_ip(&mut |addr| {
+        let acceptor = t!(TcpListener::bind(&addr));
+        t!(acceptor.set_nonblocking(true));
+        match acceptor.accept() {
+            Ok(..) => panic!("accepted a connection nobody made"),
+            Err(e) => assert_eq!(e.kind(), ErrorKind::WouldBlock),
+        }
+
+        let _s = t!(TcpStream::connect(&addr));
+        t!(acceptor.set_nonblocking(false));
+        t!(acceptor.accept());
+    })
+}
+
+#[test]
+fn nonblocking_read() {
+    each_ip(&mut |addr| {
+        let acceptor = t!(TcpListener::bind(&addr));
+        let mut stream = t!(TcpStream::connect(&addr));
+        let mut other = t!(acceptor.accept()).0;
+
+        t!(stream.set_nonblocking(true));
+        let mut buf = [0];
+        match stream.read(&mut buf) {
+            Ok(n) => panic!("read {} bytes from an idle stream", n),
+            Err(e) => assert_eq!(e.kind(), ErrorKind::WouldBlock),
+        }
+
+        // a timeout doesn't turn a non-blocking read into a timed out one
+        t!(stream.set_read_timeout(Some(Duration::seconds(1))));
+        match stream.read(&mut buf) {
+            Ok(n) => panic!("read {} bytes from an idle stream", n),
+            Err(e) => assert_eq!(e.kind(), ErrorKind::WouldBlock),
+        }
+
+        t!(stream.set_nonblocking(false));
+        t!(other.write(&[1]));
+        assert_eq!(t!(stream.read(&mut buf)), 1);
+    })
+}
+
+#[test]
+fn vectored_read_write() {
+    each_ip(&mut |addr| {
+        let acceptor = t!(TcpListener::bind(&addr));
+        let mut stream = t!(TcpStream::connect(&addr));
+        let mut other = t!(acceptor.accept()).0;
+
+        t!(stream.write_all_vectored(&[b"head", b"", b"er", b"body"]));
+        drop(stream);
+
+        let mut header = [0; 6];
+        let mut body = [0; 16];
+        let mut n = 0;
+        loop {
+            let amt = {
+                // resume filling the header where the last read stopped
+                let (h, b) = if n < 6 {
+                    (&mut header[n..], &mut body[..])
+                } else {
+                    (&mut header[6..], &mut body[n - 6..])
+                };
+                t!(other.read_vectored(&mut [h, b]))
+            };
+            if amt == 0 { break }
+            n += amt;
+        }
+        assert_eq!(n, 10);
+        assert_eq!(&header, b"header");
+        assert_eq!(&body[..4], b"body");
+    })
+}
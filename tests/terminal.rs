@@ -0,0 +1,193 @@
+#![feature(libc, path, std_misc)]
+#![cfg(unix)]
+
+extern crate io2;
+extern crate libc;
+
+use io2::io::prelude::*;
+use io2::fs::File;
+use io2::terminal::Terminal;
+
+use std::ffi;
+
+macro_rules! t {
+    ($e:expr) => {
+        match $e {
+            Ok(t) => t,
+            Err(e) => panic!("received error for `{}`: {}", stringify!($e), e),
+        }
+    }
+}
+
+extern {
+    fn posix_openpt(flags: libc::c_int) -> libc::c_int;
+    fn grantpt(fd: libc::c_int) -> libc::c_int;
+    fn unlockpt(fd: libc::c_int) -> libc::c_int;
+    fn ptsname(fd: libc::c_int) -> *mut libc::c_char;
+}
+
+/// The master side of a pseudo-terminal, along with the path of its slave.
+struct Pty {
+    master: libc::c_int,
+    slave: Path,
+}
+
+impl Pty {
+    fn new() -> Pty {
+        unsafe {
+            let master = posix_openpt(libc::O_RDWR);
+            assert!(master >= 0);
+            assert_eq!(grantpt(master), 0);
+            assert_eq!(unlockpt(master), 0);
+            let name = ptsname(master) as *const libc::c_char;
+            assert!(!name.is_null());
+            let slave = Path::new(ffi::c_str_to_bytes(&name));
+            Pty { master: master, slave: slave }
+        }
+    }
+
+    fn write(&self, data: &[u8]) {
+        let n = unsafe {
+            libc::write(self.master, data.as_ptr() as *const libc::c_void,
+                        data.len() as libc::size_t)
+        };
+        assert_eq!(n, data.len() as libc::ssize_t);
+    }
+}
+
+impl Drop for Pty {
+    fn drop(&mut self) {
+        unsafe { libc::close(self.master); }
+    }
+}
+
+#[test]
+fn not_a_terminal() {
+    let null = Path::new("/dev/null");
+    assert!(!t!(File::open(&null)).is_terminal());
+    assert!(Terminal::open(&null).is_err());
+}
+
+#[test]
+fn pty_is_terminal() {
+    let pty = Pty::new();
+    assert!(t!(File::open(&pty.slave)).is_terminal());
+    t!(Terminal::open(&pty.slave));
+}
+
+#[test]
+fn echo_restored_on_drop() {
+    let pty = Pty::new();
+    let term = t!(Terminal::open(&pty.slave));
+    assert!(t!(term.echo()));
+    {
+        let _guard = t!(term.set_echo(false));
+        assert!(!t!(term.echo()));
+    }
+    assert!(t!(term.echo()));
+}
+
+#[test]
+fn explicit_restore() {
+    let pty = Pty::new();
+    let term = t!(Terminal::open(&pty.slave));
+    let guard = t!(term.set_echo(false));
+    t!(guard.restore());
+    assert!(t!(term.echo()));
+}
+
+#[test]
+fn nested_guards() {
+    let pty = Pty::new();
+    let term = t!(Terminal::open(&pty.slave));
+    let outer = t!(term.set_echo(false));
+    {
+        let _inner = t!(term.set_echo(true));
+        assert!(t!(term.echo()));
+    }
+    assert!(!t!(term.echo()));
+    drop(outer);
+    assert!(t!(term.echo()));
+}
+
+#[test]
+fn raw_mode() {
+    let pty = Pty::new();
+    let term = t!(Terminal::open(&pty.slave));
+    {
+        let _guard = t!(term.raw_mode());
+        assert!(!t!(term.echo()));
+
+        // without line buffering a single byte is readable right away, and
+        // control characters arrive as input
+        pty.write(b"a\x03");
+        let mut buf = [0; 2];
+        let mut n = 0;
+        while n < 2 {
+            n += t!((&term).read(&mut buf[n..]));
+        }
+        assert_eq!(&buf, b"a\x03");
+    }
+    assert!(t!(term.echo()));
+}
+
+#[test]
+fn cbreak_mode() {
+    let pty = Pty::new();
+    let term = t!(Terminal::open(&pty.slave));
+    {
+        let _guard = t!(term.cbreak_mode());
+        assert!(!t!(term.echo()));
+
+        pty.write(b"b");
+        let mut buf = [0; 1];
+        assert_eq!(t!((&term).read(&mut buf)), 1);
+        assert_eq!(buf[0], b'b');
+    }
+    assert!(t!(term.echo()));
+}
+
+#[test]
+fn canonical_by_default() {
+    let pty = Pty::new();
+    let mut term = t!(Terminal::open(&pty.slave));
+    pty.write(b"line\n");
+    let mut buf = [0; 16];
+    assert_eq!(t!(term.read(&mut buf)), 5);
+    assert_eq!(&buf[..5], b"line\n");
+}
+
+#[test]
+fn window_size() {
+    let pty = Pty::new();
+    let term = t!(Terminal::open(&pty.slave));
+    t!(term.window_size());
+}
+
+#[cfg(all(target_os = "linux",
+          any(target_arch = "x86",
+              target_arch = "x86_64",
+              target_arch = "arm",
+              target_arch = "aarch64")))]
+#[test]
+fn window_size_set_by_master() {
+    const TIOCSWINSZ: libc::c_ulong = 0x5414;
+
+    #[repr(C)]
+    struct winsize {
+        ws_row: libc::c_ushort,
+        ws_col: libc::c_ushort,
+        ws_xpixel: libc::c_ushort,
+        ws_ypixel: libc::c_ushort,
+    }
+
+    extern {
+        fn ioctl(fd: libc::c_int, req: libc::c_ulong, ...) -> libc::c_int;
+    }
+
+    let pty = Pty::new();
+    let size = winsize { ws_row: 24, ws_col: 80, ws_xpixel: 0, ws_ypixel: 0 };
+    assert_eq!(unsafe { ioctl(pty.master, TIOCSWINSZ, &size) }, 0);
+    let term = t!(Terminal::open(&pty.slave));
+    assert_eq!(t!(term.window_size()), (80, 24));
+}
@@ -0,0 +1,140 @@
+#![feature(std_misc)]
+#![cfg(unix)]
+
+extern crate io2;
+
+use io2::time::{Duration, Timer};
+
+use std::sync::mpsc::channel;
+use std::thread::Thread;
+
+macro_rules! t {
+    ($e:expr) => {
+        match $e {
+            Ok(t) => t,
+            Err(e) => panic!("received error for `{}`: {}", stringify!($e), e),
+        }
+    }
+}
+
+fn ms(n: i64) -> Duration { Duration::milliseconds(n) }
+
+#[test]
+fn sleep() {
+    let mut timer = t!(Timer::new());
+    timer.sleep(ms(1));
+    timer.sleep(Duration::zero());
+    timer.sleep(ms(-1));
+}
+
+#[test]
+fn oneshot() {
+    let mut timer = t!(Timer::new());
+    let rx = timer.oneshot(ms(1));
+    rx.recv().unwrap();
+    // a oneshot only fires once, after which the channel is closed
+    assert!(rx.recv().is_err());
+}
+
+#[test]
+fn oneshot_zero() {
+    let mut timer = t!(Timer::new());
+    let rx = timer.oneshot(Duration::zero());
+    rx.recv().unwrap();
+    let rx = timer.oneshot(ms(-10));
+    rx.recv().unwrap();
+}
+
+#[test]
+fn oneshot_twice() {
+    let mut timer = t!(Timer::new());
+    let rx1 = timer.oneshot(Duration::seconds(10000));
+    let rx = timer.oneshot(ms(1));
+    rx.recv().unwrap();
+    assert!(rx1.recv().is_err());
+}
+
+#[test]
+fn sleep_cancels_oneshot() {
+    let mut timer = t!(Timer::new());
+    let rx = timer.oneshot(Duration::seconds(10000));
+    timer.sleep(ms(1));
+    assert!(rx.recv().is_err());
+}
+
+#[test]
+fn periodic() {
+    let mut timer = t!(Timer::new());
+    let rx = timer.periodic(ms(1));
+    rx.recv().unwrap();
+    rx.recv().unwrap();
+    rx.recv().unwrap();
+
+    let rx2 = timer.periodic(ms(1));
+    rx2.recv().unwrap();
+    rx2.recv().unwrap();
+
+    // the first periodic timer was cancelled by the second
+    while rx.recv().is_ok() {}
+}
+
+#[test]
+fn periodic_zero() {
+    let mut timer = t!(Timer::new());
+    let rx = timer.periodic(Duration::zero());
+    rx.recv().unwrap();
+    rx.recv().unwrap();
+}
+
+#[test]
+fn drop_cancels() {
+    let rx = {
+        let mut timer = t!(Timer::new());
+        timer.periodic(ms(1))
+    };
+    while rx.recv().is_ok() {}
+
+    let rx = {
+        let mut timer = t!(Timer::new());
+        timer.oneshot(Duration::seconds(10000))
+    };
+    assert!(rx.recv().is_err());
+}
+
+#[test]
+fn dropped_receiver() {
+    let mut timer = t!(Timer::new());
+    drop(timer.periodic(ms(1)));
+    timer.sleep(ms(5));
+    let rx = timer.oneshot(ms(1));
+    rx.recv().unwrap();
+}
+
+#[test]
+fn many_timers() {
+    let (tx, rx) = channel();
+    for _ in 0..10 {
+        let tx = tx.clone();
+        Thread::spawn(move|| {
+            let mut timer = t!(Timer::new());
+            let ticks = timer.periodic(ms(2));
+            for _ in 0..5 {
+                ticks.recv().unwrap();
+            }
+            tx.send(()).unwrap();
+        });
+    }
+    for _ in 0..10 {
+        rx.recv().unwrap();
+    }
+}
+
+#[test]
+fn oneshot_ordering() {
+    let mut short = t!(Timer::new());
+    let mut long = t!(Timer::new());
+    let long_rx = long.oneshot(Duration::seconds(10000));
+    let short_rx = short.oneshot(ms(5));
+    short_rx.recv().unwrap();
+    assert!(long_rx.try_recv().is_err());
+}
@@ -185,3 +185,25 @@ fn udp_clone_two_write() {
         serv_rx.recv().unwrap();
     })
 }
+
+#[test]
+fn nonblocking_recv_from() {
+    each_ip(&mut |server_ip, client_ip| {
+        let server = t!(UdpSocket::bind(&server_ip));
+        let client = t!(UdpSocket::bind(&client_ip));
+        t!(server.set_nonblocking(true));
+
+        let mut buf = [0];
+        match server.recv_from(&mut buf) {
+            Ok(..) => panic!("received a datagram nobody sent"),
+            Err(e) => assert_eq!(e.kind(), ErrorKind::WouldBlock),
+        }
+
+        t!(server.set_nonblocking(false));
+        t!(client.send_to(&[99], &server_ip));
+        let (nread, src) = t!(server.recv_from(&mut buf));
+        assert_eq!(nread, 1);
+        assert_eq!(buf[0], 99);
+        assert_eq!(src, client_ip);
+    })
+}
@@ -0,0 +1,182 @@
+#![cfg(unix)]
+#![feature(rand, path, std_misc)]
+
+extern crate io2;
+
+use io2::fs::TempDir;
+use io2::io::prelude::*;
+use io2::net::Shutdown;
+use io2::net::unix::{UnixStream, UnixListener, UnixDatagram, SocketAddr};
+
+use std::rand;
+use std::thread::Thread;
+
+macro_rules! t {
+    ($e:expr) => {
+        match $e {
+            Ok(t) => t,
+            Err(e) => panic!("received error for `{}`: {}", stringify!($e), e),
+        }
+    }
+}
+
+#[test]
+fn smoke_stream() {
+    let dir = t!(TempDir::new("io2-unix"));
+    let path = dir.path().join("sock");
+    let listener = t!(UnixListener::bind(&path));
+
+    let path2 = path.clone();
+    let _t = Thread::scoped(move || {
+        let mut stream = t!(UnixStream::connect(&path2));
+        t!(stream.write(&[99]));
+    });
+
+    let (mut stream, addr) = t!(listener.accept());
+    assert!(addr.is_unnamed());
+    let mut buf = [0];
+    t!(stream.read(&mut buf));
+    assert_eq!(buf[0], 99);
+}
+
+#[test]
+fn stream_addrs() {
+    let dir = t!(TempDir::new("io2-unix"));
+    let path = dir.path().join("sock");
+    let listener = t!(UnixListener::bind(&path));
+    assert_eq!(t!(listener.local_addr()).as_pathname(), Some(path.clone()));
+
+    let client = t!(UnixStream::connect(&path));
+    let (server, _) = t!(listener.accept());
+    assert_eq!(t!(client.peer_addr()).as_pathname(), Some(path.clone()));
+    assert!(t!(client.local_addr()).is_unnamed());
+    assert_eq!(t!(server.local_addr()).as_pathname(), Some(path.clone()));
+    assert!(t!(server.peer_addr()).is_unnamed());
+}
+
+#[test]
+fn stream_pair() {
+    let (mut a, mut b) = t!(UnixStream::pair());
+    t!(a.write_all(b"hello"));
+    let mut buf = [0; 5];
+    assert_eq!(t!(b.read(&mut buf)), 5);
+    assert_eq!(&buf, b"hello");
+
+    t!(a.shutdown(Shutdown::Write));
+    assert_eq!(t!(b.read(&mut buf)), 0);
+    assert!(t!(a.peer_addr()).is_unnamed());
+}
+
+#[test]
+fn stream_by_ref() {
+    let (a, b) = t!(UnixStream::pair());
+    t!((&a).write(&[1, 2, 3]));
+    let mut buf = [0; 3];
+    assert_eq!(t!((&b).read(&mut buf)), 3);
+    assert_eq!(buf, [1, 2, 3]);
+}
+
+#[test]
+fn incoming() {
+    let dir = t!(TempDir::new("io2-unix"));
+    let path = dir.path().join("sock");
+    let listener = t!(UnixListener::bind(&path));
+
+    let path2 = path.clone();
+    let _t = Thread::scoped(move || {
+        for _ in 0..3 {
+            let mut stream = t!(UnixStream::connect(&path2));
+            t!(stream.write(&[1]));
+        }
+    });
+
+    for stream in listener.incoming().take(3) {
+        let mut buf = [0];
+        t!(t!(stream).read(&mut buf));
+        assert_eq!(buf[0], 1);
+    }
+}
+
+#[test]
+fn path_too_long() {
+    let dir = t!(TempDir::new("io2-unix"));
+    let path = dir.path().join(String::from_utf8(vec![b'a'; 200]).unwrap().as_slice());
+    assert!(UnixListener::bind(&path).is_err());
+    assert!(UnixStream::connect(&path).is_err());
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+fn abstract_addrs() {
+    let name = format!("io2-{}", rand::random::<u32>());
+    let addr = t!(SocketAddr::from_abstract_name(name.as_bytes()));
+    let listener = t!(UnixListener::bind_addr(&addr));
+    let local = t!(listener.local_addr());
+    assert_eq!(local.as_abstract_name(), Some(name.as_bytes()));
+    assert_eq!(local.as_pathname(), None);
+
+    let mut client = t!(UnixStream::connect_addr(&addr));
+    let (mut server, _) = t!(listener.accept());
+    assert_eq!(t!(client.peer_addr()).as_abstract_name(),
+               Some(name.as_bytes()));
+    t!(client.write(&[7]));
+    let mut buf = [0];
+    t!(server.read(&mut buf));
+    assert_eq!(buf[0], 7);
+}
+
+#[test]
+fn datagram_send_to_recv_from() {
+    let dir = t!(TempDir::new("io2-unix"));
+    let path1 = dir.path().join("sock1");
+    let path2 = dir.path().join("sock2");
+    let sock1 = t!(UnixDatagram::bind(&path1));
+    let sock2 = t!(UnixDatagram::bind(&path2));
+
+    assert_eq!(t!(sock1.send_to(b"hello", &path2)), 5);
+    let mut buf = [0; 10];
+    let (n, addr) = t!(sock2.recv_from(&mut buf));
+    assert_eq!(&buf[..n], b"hello");
+    assert_eq!(addr.as_pathname(), Some(path1));
+}
+
+#[test]
+fn datagram_unbound() {
+    let dir = t!(TempDir::new("io2-unix"));
+    let path = dir.path().join("sock");
+    let server = t!(UnixDatagram::bind(&path));
+    let client = t!(UnixDatagram::unbound());
+
+    t!(client.send_to(&[1, 2], &path));
+    let mut buf = [0; 2];
+    let (n, addr) = t!(server.recv_from(&mut buf));
+    assert_eq!(n, 2);
+    assert!(addr.is_unnamed());
+}
+
+#[test]
+fn datagram_connect() {
+    let dir = t!(TempDir::new("io2-unix"));
+    let path = dir.path().join("sock");
+    let server = t!(UnixDatagram::bind(&path));
+    let client = t!(UnixDatagram::unbound());
+    t!(client.connect(&path));
+    assert_eq!(t!(client.peer_addr()).as_pathname(), Some(path));
+
+    t!(client.send(&[3]));
+    let mut buf = [0];
+    assert_eq!(t!(server.recv(&mut buf)), 1);
+    assert_eq!(buf[0], 3);
+}
+
+#[test]
+fn datagram_pair() {
+    let (a, b) = t!(UnixDatagram::pair());
+    t!(a.send(b"ab"));
+    t!(a.send(b"c"));
+    let mut buf = [0; 4];
+    assert_eq!(t!(b.recv(&mut buf)), 2);
+    assert_eq!(&buf[..2], b"ab");
+    assert_eq!(t!(b.recv(&mut buf)), 1);
+    assert_eq!(buf[0], b'c');
+}